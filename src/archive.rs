@@ -0,0 +1,293 @@
+use axum::body::{Body, Bytes};
+use flate2::{write::GzEncoder, Compression};
+use serde::Deserialize;
+use std::{
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+};
+use tokio::sync::mpsc;
+use tracing::{error, warn};
+use zip::{
+    write::{SimpleFileOptions, StreamWriter},
+    CompressionMethod, ZipWriter,
+};
+
+/// 每次发送给客户端的数据块大小
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// 打包线程与响应之间最多缓存的数据块数量
+const CHANNEL_CAPACITY: usize = 16;
+
+/// 打包格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum ArchiveFormat {
+    #[default]
+    #[serde(rename = "zip")]
+    Zip,
+    #[serde(rename = "tar.gz", alias = "tgz")]
+    TarGz,
+}
+
+impl ArchiveFormat {
+    /// 压缩包文件扩展名
+    pub fn extension(self) -> &'static str {
+        match self {
+            ArchiveFormat::Zip => "zip",
+            ArchiveFormat::TarGz => "tar.gz",
+        }
+    }
+
+    /// 响应的 Content-Type
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ArchiveFormat::Zip => "application/zip",
+            ArchiveFormat::TarGz => "application/gzip",
+        }
+    }
+}
+
+/// 需要打包的条目
+pub struct ArchiveEntry {
+    /// 完整路径
+    pub path: PathBuf,
+    /// 在压缩包中的名称
+    pub name: String,
+}
+
+/// 在后台线程中边打包边输出，不使用临时文件，也不在内存中缓存整个压缩包
+///
+/// 打包中途出错时响应流以错误结束，客户端会看到下载失败而不是一个不完整的压缩包。
+pub fn stream(root: PathBuf, entries: Vec<ArchiveEntry>, format: ArchiveFormat) -> Body {
+    let (tx, rx) = mpsc::channel::<io::Result<Bytes>>(CHANNEL_CAPACITY);
+
+    tokio::task::spawn_blocking(move || {
+        let writer = ChannelWriter {
+            tx: tx.clone(),
+            buf: Vec::with_capacity(CHUNK_SIZE),
+        };
+        let result = match format {
+            ArchiveFormat::Zip => write_archive(&root, &entries, ZipBuilder::new(writer)),
+            ArchiveFormat::TarGz => write_archive(&root, &entries, TarBuilder::new(writer)),
+        };
+
+        // 客户端断开连接时写入会失败，不需要再报告
+        if let Err(e) = result {
+            if e.kind() != io::ErrorKind::BrokenPipe {
+                error!("打包失败: {}", e);
+                let _ = tx.blocking_send(Err(e));
+            }
+        }
+    });
+
+    Body::from_stream(futures::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    }))
+}
+
+/// 把写入的数据按块发送到响应流
+struct ChannelWriter {
+    tx: mpsc::Sender<io::Result<Bytes>>,
+    buf: Vec<u8>,
+}
+
+impl ChannelWriter {
+    fn send_buffer(&mut self) -> io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        let chunk = Bytes::from(std::mem::replace(&mut self.buf, Vec::with_capacity(CHUNK_SIZE)));
+        self.tx
+            .blocking_send(Ok(chunk))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "客户端已断开连接"))
+    }
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(data);
+        if self.buf.len() >= CHUNK_SIZE {
+            self.send_buffer()?;
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.send_buffer()
+    }
+}
+
+impl Drop for ChannelWriter {
+    fn drop(&mut self) {
+        let _ = self.send_buffer();
+    }
+}
+
+/// 不同压缩包格式的写入器
+trait ArchiveBuilder {
+    fn add_dir(&mut self, name: &str, metadata: &fs::Metadata) -> io::Result<()>;
+    fn add_file(&mut self, name: &str, file: File, metadata: &fs::Metadata) -> io::Result<()>;
+    fn add_symlink(&mut self, name: &str, target: &Path, metadata: &fs::Metadata) -> io::Result<()>;
+    fn finish(self) -> io::Result<()>;
+}
+
+fn write_archive(root: &Path, entries: &[ArchiveEntry], mut builder: impl ArchiveBuilder) -> io::Result<()> {
+    for entry in entries {
+        add_entry(&mut builder, root, &entry.path, &entry.name)?;
+    }
+    builder.finish()
+}
+
+/// 递归添加条目，无法读取的文件和目录会被跳过
+fn add_entry(builder: &mut impl ArchiveBuilder, root: &Path, path: &Path, name: &str) -> io::Result<()> {
+    let metadata = fs::symlink_metadata(path)?;
+
+    if metadata.is_dir() {
+        builder.add_dir(name, &metadata)?;
+
+        let mut children: Vec<_> = match fs::read_dir(path) {
+            Ok(entries) => entries.filter_map(|entry| entry.ok()).collect(),
+            Err(e) => {
+                warn!("打包时跳过无法读取的目录 {}: {}", path.display(), e);
+                return Ok(());
+            }
+        };
+        children.sort_by_key(|entry| entry.file_name());
+
+        for child in children {
+            let child_path = child.path();
+            // 不打包回收站和上传临时目录
+            if crate::is_reserved_path(root, &child_path) {
+                continue;
+            }
+            let child_name = format!("{}/{}", name, child.file_name().to_string_lossy());
+            add_entry(builder, root, &child_path, &child_name)?;
+        }
+    } else if metadata.file_type().is_symlink() {
+        // 只保存链接本身，不跟随链接，避免打包根目录外的内容
+        match fs::read_link(path) {
+            Ok(target) => builder.add_symlink(name, &target, &metadata)?,
+            Err(e) => warn!("打包时跳过无法读取的链接 {}: {}", path.display(), e),
+        }
+    } else {
+        match File::open(path) {
+            Ok(file) => builder.add_file(name, file, &metadata)?,
+            Err(e) => warn!("打包时跳过无法读取的文件 {}: {}", path.display(), e),
+        }
+    }
+
+    Ok(())
+}
+
+/// zip 格式，使用数据描述符，不需要回写已输出的内容
+struct ZipBuilder {
+    zip: ZipWriter<StreamWriter<ChannelWriter>>,
+}
+
+impl ZipBuilder {
+    fn new(writer: ChannelWriter) -> Self {
+        ZipBuilder {
+            zip: ZipWriter::new_stream(writer),
+        }
+    }
+
+    fn options(metadata: &fs::Metadata, method: CompressionMethod) -> SimpleFileOptions {
+        let mut options = SimpleFileOptions::default()
+            .compression_method(method)
+            .large_file(metadata.len() >= u32::MAX as u64);
+
+        // zip 只能表示 1980 年之后的时间
+        if let Some(modified) = metadata
+            .modified()
+            .ok()
+            .map(time::OffsetDateTime::from)
+            .and_then(|t| zip::DateTime::try_from(time::PrimitiveDateTime::new(t.date(), t.time())).ok())
+        {
+            options = options.last_modified_time(modified);
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            options = options.unix_permissions(metadata.permissions().mode());
+        }
+
+        options
+    }
+}
+
+impl ArchiveBuilder for ZipBuilder {
+    fn add_dir(&mut self, name: &str, metadata: &fs::Metadata) -> io::Result<()> {
+        self.zip
+            .add_directory(name, Self::options(metadata, CompressionMethod::Stored))?;
+        Ok(())
+    }
+
+    fn add_file(&mut self, name: &str, file: File, metadata: &fs::Metadata) -> io::Result<()> {
+        self.zip
+            .start_file(name, Self::options(metadata, CompressionMethod::Deflated))?;
+        io::copy(&mut file.take(metadata.len()), &mut self.zip)?;
+        Ok(())
+    }
+
+    fn add_symlink(&mut self, name: &str, target: &Path, metadata: &fs::Metadata) -> io::Result<()> {
+        self.zip.add_symlink(
+            name,
+            target.to_string_lossy(),
+            Self::options(metadata, CompressionMethod::Stored),
+        )?;
+        Ok(())
+    }
+
+    fn finish(self) -> io::Result<()> {
+        self.zip.finish()?;
+        Ok(())
+    }
+}
+
+/// tar.gz 格式
+struct TarBuilder {
+    tar: tar::Builder<GzEncoder<ChannelWriter>>,
+}
+
+impl TarBuilder {
+    fn new(writer: ChannelWriter) -> Self {
+        let mut tar = tar::Builder::new(GzEncoder::new(writer, Compression::default()));
+        tar.follow_symlinks(false);
+        TarBuilder { tar }
+    }
+
+    fn header(metadata: &fs::Metadata, entry_type: tar::EntryType) -> tar::Header {
+        let mut header = tar::Header::new_gnu();
+        header.set_metadata_in_mode(metadata, tar::HeaderMode::Complete);
+        header.set_entry_type(entry_type);
+        if !entry_type.is_file() {
+            header.set_size(0);
+        }
+        header
+    }
+}
+
+impl ArchiveBuilder for TarBuilder {
+    fn add_dir(&mut self, name: &str, metadata: &fs::Metadata) -> io::Result<()> {
+        let mut header = Self::header(metadata, tar::EntryType::Directory);
+        self.tar.append_data(&mut header, name, io::empty())
+    }
+
+    fn add_file(&mut self, name: &str, file: File, metadata: &fs::Metadata) -> io::Result<()> {
+        let mut header = Self::header(metadata, tar::EntryType::Regular);
+        // 按打开时的大小读取，避免文件在打包过程中增长导致条目损坏
+        self.tar
+            .append_data(&mut header, name, file.take(metadata.len()))
+    }
+
+    fn add_symlink(&mut self, name: &str, target: &Path, metadata: &fs::Metadata) -> io::Result<()> {
+        let mut header = Self::header(metadata, tar::EntryType::Symlink);
+        self.tar.append_link(&mut header, name, target)
+    }
+
+    fn finish(self) -> io::Result<()> {
+        self.tar.into_inner()?.finish()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,283 @@
+use crate::AppState;
+use argon2::{
+    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Request, State},
+    http::{header, request::Parts, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{Mutex, MutexGuard, OnceLock},
+    time::{Duration, Instant},
+};
+use tracing::{info, warn};
+
+/// 会话 Cookie 名称
+pub const SESSION_COOKIE: &str = "fb_session";
+
+/// 登录会话
+struct Session {
+    username: String,
+    expires_at: Instant,
+}
+
+/// 服务端会话存储，令牌为随机生成的不透明字符串
+#[derive(Default)]
+pub struct SessionStore {
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl SessionStore {
+    /// 为用户创建新会话并返回令牌
+    fn create(&self, username: &str, ttl: Duration) -> String {
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes);
+        let token: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+
+        let now = Instant::now();
+        let mut sessions = self.lock();
+        // 顺便清理已过期的会话
+        sessions.retain(|_, s| s.expires_at > now);
+        sessions.insert(
+            token.clone(),
+            Session {
+                username: username.to_string(),
+                expires_at: now + ttl,
+            },
+        );
+
+        token
+    }
+
+    /// 查找令牌对应的用户名，过期会话会被移除
+    fn lookup(&self, token: &str) -> Option<String> {
+        let mut sessions = self.lock();
+        match sessions.get(token) {
+            Some(session) if session.expires_at > Instant::now() => Some(session.username.clone()),
+            Some(_) => {
+                sessions.remove(token);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// 注销会话
+    fn remove(&self, token: &str) {
+        self.lock().remove(token);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Session>> {
+        self.sessions.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// 当前登录用户，未启用认证时为 `None`
+#[derive(Debug, Clone)]
+pub struct CurrentUser(pub Option<String>);
+
+impl CurrentUser {
+    pub fn name(&self) -> Option<&str> {
+        self.0.as_deref()
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for CurrentUser {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        if !auth_enabled(state) {
+            return Ok(CurrentUser(None));
+        }
+
+        current_user(state, &parts.headers)
+            .map(|username| CurrentUser(Some(username)))
+            .ok_or(StatusCode::UNAUTHORIZED)
+    }
+}
+
+/// 登录请求
+#[derive(Debug, Deserialize)]
+pub struct LoginRequest {
+    username: String,
+    password: String,
+}
+
+/// 登录响应
+#[derive(Debug, Serialize)]
+pub struct LoginResponse {
+    success: bool,
+    message: String,
+    username: String,
+    token: String,
+}
+
+/// 会话状态响应
+#[derive(Debug, Serialize)]
+pub struct SessionResponse {
+    #[serde(rename = "authEnabled")]
+    auth_enabled: bool,
+    username: Option<String>,
+}
+
+/// 是否启用了认证，只有显式允许匿名访问时才关闭
+pub fn auth_enabled(state: &AppState) -> bool {
+    !state.config.anonymous_access()
+}
+
+/// 生成 argon2 密码哈希（PHC 字符串格式）
+pub fn hash_password(password: &str) -> Result<String, argon2::password_hash::Error> {
+    let salt = SaltString::generate(&mut OsRng);
+    Ok(Argon2::default()
+        .hash_password(password.as_bytes(), &salt)?
+        .to_string())
+}
+
+/// 校验密码是否与 argon2 哈希匹配
+fn verify_password(password: &str, password_hash: &str) -> bool {
+    match PasswordHash::new(password_hash) {
+        Ok(parsed) => Argon2::default()
+            .verify_password(password.as_bytes(), &parsed)
+            .is_ok(),
+        Err(e) => {
+            warn!("无效的密码哈希: {}", e);
+            false
+        }
+    }
+}
+
+/// 用户不存在时用来校验的哈希，使登录耗时与用户存在时相同，避免通过响应时间判断用户名是否存在
+fn dummy_hash() -> &'static str {
+    static DUMMY_HASH: OnceLock<String> = OnceLock::new();
+    DUMMY_HASH.get_or_init(|| hash_password("dummy-password").unwrap_or_default())
+}
+
+/// 从 Authorization 头或 Cookie 中提取会话令牌
+fn extract_token(headers: &HeaderMap) -> Option<String> {
+    if let Some(token) = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+    {
+        return Some(token.trim().to_string());
+    }
+
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(name, _)| *name == SESSION_COOKIE)
+        .map(|(_, value)| value.to_string())
+}
+
+/// 查询请求对应的登录用户
+fn current_user(state: &AppState, headers: &HeaderMap) -> Option<String> {
+    extract_token(headers).and_then(|token| state.sessions.lookup(&token))
+}
+
+/// 生成会话 Cookie
+fn session_cookie(token: &str, max_age: u64) -> Result<HeaderValue, StatusCode> {
+    HeaderValue::from_str(&format!(
+        "{}={}; Path=/; HttpOnly; SameSite=Strict; Max-Age={}",
+        SESSION_COOKIE, token, max_age
+    ))
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// 认证中间件：没有有效会话时返回 401
+pub async fn require_auth(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    if auth_enabled(&state) && current_user(&state, req.headers()).is_none() {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    Ok(next.run(req).await)
+}
+
+/// 处理登录请求
+pub async fn handle_login(
+    State(state): State<AppState>,
+    Json(req): Json<LoginRequest>,
+) -> Result<Response, StatusCode> {
+    let user = state
+        .config
+        .users
+        .iter()
+        .find(|u| u.username == req.username)
+        .cloned();
+
+    // argon2 校验较耗时，放到阻塞线程中执行；用户不存在时同样校验一次
+    let password = req.password;
+    let verified = tokio::task::spawn_blocking(move || match user {
+        Some(user) => verify_password(&password, &user.password_hash),
+        None => {
+            verify_password(&password, dummy_hash());
+            false
+        }
+    })
+    .await
+    .unwrap_or(false);
+
+    if !verified {
+        warn!("用户登录失败: {}", req.username);
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let ttl = state.config.session_ttl;
+    let token = state.sessions.create(&req.username, Duration::from_secs(ttl));
+    info!("用户登录: {}", req.username);
+
+    let mut headers = HeaderMap::new();
+    headers.insert(header::SET_COOKIE, session_cookie(&token, ttl)?);
+
+    Ok((
+        headers,
+        Json(LoginResponse {
+            success: true,
+            message: "登录成功".to_string(),
+            username: req.username,
+            token,
+        }),
+    )
+        .into_response())
+}
+
+/// 处理注销请求
+pub async fn handle_logout(State(state): State<AppState>, headers: HeaderMap) -> Result<Response, StatusCode> {
+    if let Some(token) = extract_token(&headers) {
+        state.sessions.remove(&token);
+    }
+
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert(header::SET_COOKIE, session_cookie("", 0)?);
+
+    Ok((
+        response_headers,
+        Json(crate::SuccessResponse {
+            success: true,
+            message: "已注销".to_string(),
+        }),
+    )
+        .into_response())
+}
+
+/// 处理会话状态查询，供前端判断是否需要登录
+pub async fn handle_session(State(state): State<AppState>, headers: HeaderMap) -> Json<SessionResponse> {
+    Json(SessionResponse {
+        auth_enabled: auth_enabled(&state),
+        username: current_user(&state, &headers),
+    })
+}
@@ -0,0 +1,232 @@
+use flate2::bufread::MultiGzDecoder;
+use ruzstd::decoding::{
+    errors::{FrameDecoderError, ReadFrameHeaderError},
+    BlockDecodingStrategy, FrameDecoder,
+};
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Read},
+    path::Path,
+};
+
+/// gzip 文件头
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// zstd 帧头
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// 压缩格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// 根据文件开头的魔数判断压缩格式
+    pub fn from_magic(header: &[u8]) -> Compression {
+        if header.starts_with(&GZIP_MAGIC) {
+            Compression::Gzip
+        } else if header.starts_with(&ZSTD_MAGIC) {
+            Compression::Zstd
+        } else {
+            Compression::None
+        }
+    }
+}
+
+/// 根据文件开头的魔数判断文件的压缩格式，不看扩展名
+pub fn detect(path: &Path) -> io::Result<Compression> {
+    let mut header = Vec::with_capacity(ZSTD_MAGIC.len());
+    File::open(path)?.take(ZSTD_MAGIC.len() as u64).read_to_end(&mut header)?;
+    Ok(Compression::from_magic(&header))
+}
+
+/// 打开文件并按需解压，压缩格式只看文件开头的魔数，扩展名为 `.gz` 的纯文本文件按未压缩读取
+pub fn open(path: &Path) -> io::Result<(Box<dyn BufRead + Send>, Compression)> {
+    let mut reader = BufReader::with_capacity(64 * 1024, File::open(path)?);
+    let compression = Compression::from_magic(reader.fill_buf()?);
+    Ok((buffered(reader, compression)?, compression))
+}
+
+/// 与 [`decoder`] 相同，但返回带缓冲的读取器，未压缩时不再额外包一层缓冲
+pub fn buffered<R: BufRead + Send + 'static>(reader: R, compression: Compression) -> io::Result<Box<dyn BufRead + Send>> {
+    Ok(match compression {
+        Compression::None => Box::new(reader),
+        Compression::Gzip => Box::new(BufReader::with_capacity(64 * 1024, MultiGzDecoder::new(reader))),
+        Compression::Zstd => Box::new(BufReader::with_capacity(64 * 1024, ZstdDecoder::new(reader)?)),
+    })
+}
+
+/// 按压缩格式包装解压读取器
+pub fn decoder<'a, R: BufRead + 'a>(reader: R, compression: Compression) -> io::Result<Box<dyn Read + 'a>> {
+    Ok(match compression {
+        Compression::None => Box::new(reader),
+        // 多个 gzip 成员拼接的文件（如 logrotate 追加压缩）需要全部解压
+        Compression::Gzip => Box::new(MultiGzDecoder::new(reader)),
+        Compression::Zstd => Box::new(ZstdDecoder::new(reader)?),
+    })
+}
+
+/// zstd 解码器，支持多帧和可跳过帧
+pub struct ZstdDecoder<R: BufRead> {
+    source: R,
+    decoder: FrameDecoder,
+}
+
+impl<R: BufRead> ZstdDecoder<R> {
+    pub fn new(source: R) -> io::Result<Self> {
+        let mut decoder = ZstdDecoder {
+            source,
+            decoder: FrameDecoder::new(),
+        };
+        decoder.next_frame()?;
+        Ok(decoder)
+    }
+
+    /// 开始解码下一帧，没有更多数据时返回 `false`
+    fn next_frame(&mut self) -> io::Result<bool> {
+        loop {
+            if self.source.fill_buf()?.is_empty() {
+                return Ok(false);
+            }
+            match self.decoder.reset(&mut self.source) {
+                Ok(()) => return Ok(true),
+                Err(FrameDecoderError::ReadFrameHeaderError(ReadFrameHeaderError::SkipFrame {
+                    length,
+                    ..
+                })) => {
+                    io::copy(&mut (&mut self.source).take(length as u64), &mut io::sink())?;
+                }
+                Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Read for ZstdDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if self.decoder.can_collect() > 0 {
+                return self.decoder.read(buf);
+            }
+            if !self.decoder.is_finished() {
+                self.decoder
+                    .decode_blocks(&mut self.source, BlockDecodingStrategy::UptoBlocks(1))
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                continue;
+            }
+            if !self.next_frame()? {
+                return Ok(0);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{write::GzEncoder, Compression as GzLevel};
+    use crate::testutil::temp_file;
+    use std::io::Write;
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), GzLevel::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    /// 只包含一个未压缩块的 zstd 帧
+    fn zstd_raw_frame(data: &[u8]) -> Vec<u8> {
+        assert!(data.len() < 256);
+        let block_header = ((data.len() as u32) << 3) | 1;
+        let mut frame = ZSTD_MAGIC.to_vec();
+        // 单段帧，内容大小占 1 个字节
+        frame.extend_from_slice(&[0x20, data.len() as u8]);
+        frame.extend_from_slice(&block_header.to_le_bytes()[..3]);
+        frame.extend_from_slice(data);
+        frame
+    }
+
+    /// zstd 可跳过帧
+    fn zstd_skippable_frame(data: &[u8]) -> Vec<u8> {
+        let mut frame = vec![0x50, 0x2a, 0x4d, 0x18];
+        frame.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        frame.extend_from_slice(data);
+        frame
+    }
+
+    fn decode(data: &[u8], compression: Compression) -> io::Result<String> {
+        let mut text = String::new();
+        decoder(data, compression)?.read_to_string(&mut text)?;
+        Ok(text)
+    }
+
+    #[test]
+    fn detects_compression_from_magic() {
+        assert_eq!(Compression::from_magic(&gzip(b"x")), Compression::Gzip);
+        assert_eq!(Compression::from_magic(&zstd_raw_frame(b"x")), Compression::Zstd);
+        assert_eq!(Compression::from_magic(b"plain text"), Compression::None);
+        assert_eq!(Compression::from_magic(&[0x1f]), Compression::None);
+        assert_eq!(Compression::from_magic(&[]), Compression::None);
+    }
+
+    #[test]
+    fn decodes_concatenated_gzip_members() {
+        let mut data = gzip(b"first\n");
+        data.extend(gzip(b"second\n"));
+        assert_eq!(decode(&data, Compression::Gzip).unwrap(), "first\nsecond\n");
+    }
+
+    #[test]
+    fn decodes_multiple_and_skippable_zstd_frames() {
+        let mut data = zstd_skippable_frame(b"meta");
+        data.extend(zstd_raw_frame(b"first\n"));
+        data.extend(zstd_skippable_frame(b""));
+        data.extend(zstd_raw_frame(b"second\n"));
+        assert_eq!(decode(&data, Compression::Zstd).unwrap(), "first\nsecond\n");
+        assert_eq!(decode(&[], Compression::Zstd).unwrap(), "");
+    }
+
+    #[test]
+    fn rejects_corrupt_zstd() {
+        let err = decode(&[0x28, 0xb5, 0x2f, 0xfd, 0xff, 0xff], Compression::Zstd).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn open_sniffs_compression_without_extension() {
+        let file = temp_file(".log.1", gzip(b"hello\n"));
+        let (mut reader, compression) = open(file.path()).unwrap();
+        assert_eq!(compression, Compression::Gzip);
+        let mut text = String::new();
+        reader.read_to_string(&mut text).unwrap();
+        assert_eq!(text, "hello\n");
+
+        let file = temp_file(".log", b"hello\n");
+        let (mut reader, compression) = open(file.path()).unwrap();
+        assert_eq!(compression, Compression::None);
+        let mut text = String::new();
+        reader.read_to_string(&mut text).unwrap();
+        assert_eq!(text, "hello\n");
+    }
+
+    #[test]
+    fn open_ignores_mislabeled_extension() {
+        for suffix in [".log.gz", ".log.zst"] {
+            let file = temp_file(suffix, b"hello\n");
+            assert_eq!(detect(file.path()).unwrap(), Compression::None);
+            let (mut reader, compression) = open(file.path()).unwrap();
+            assert_eq!(compression, Compression::None);
+            let mut text = String::new();
+            reader.read_to_string(&mut text).unwrap();
+            assert_eq!(text, "hello\n");
+        }
+
+        let file = temp_file(".log.1", gzip(b"hello\n"));
+        assert_eq!(detect(file.path()).unwrap(), Compression::Gzip);
+        let file = temp_file(".log", b"");
+        assert_eq!(detect(file.path()).unwrap(), Compression::None);
+    }
+}
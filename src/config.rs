@@ -9,12 +9,227 @@ pub struct Config {
     pub port: u16,
     #[serde(rename = "staticDirs", default = "default_static_dirs")]
     pub static_dirs: Vec<StaticDirConfig>,
+    /// 用户列表，为空时必须开启 `allowAnonymous`，否则拒绝启动
+    #[serde(default)]
+    pub users: Vec<UserConfig>,
+    /// 未配置用户时允许匿名访问所有接口，需要显式开启
+    #[serde(rename = "allowAnonymous", default)]
+    pub allow_anonymous: bool,
+    /// 角色列表，为空时所有用户都可以访问全部根目录
+    #[serde(default)]
+    pub roles: Vec<RoleConfig>,
+    /// 会话有效期（秒）
+    #[serde(rename = "sessionTtl", default = "default_session_ttl")]
+    pub session_ttl: u64,
+    /// 允许跨域访问的来源，为空时只允许同源访问
+    #[serde(rename = "corsAllowedOrigins", default)]
+    pub cors_allowed_origins: Vec<String>,
+    /// 回收站条目保留天数，0 表示不自动清理
+    #[serde(rename = "trashRetentionDays", default = "default_trash_retention_days")]
+    pub trash_retention_days: u64,
+    /// 单个上传文件的最大字节数，0 表示不限制
+    #[serde(rename = "maxUploadSize", default)]
+    pub max_upload_size: u64,
+    /// 单次解压最多写入的字节数，默认 4 GiB，显式配置为 0 时不限制
+    #[serde(rename = "maxExtractSize", default = "default_max_extract_size")]
+    pub max_extract_size: u64,
+    /// 单次解压最多创建的文件和目录数量，0 表示不限制
+    #[serde(rename = "maxExtractEntries", default = "default_max_extract_entries")]
+    pub max_extract_entries: u64,
+    /// 同时进行的目录内容搜索和流式搜索数量
+    #[serde(rename = "searchConcurrency", default = "default_search_concurrency")]
+    pub search_concurrency: usize,
+    /// 文件查看和搜索结果的分页设置
+    #[serde(default)]
+    pub paging: Paging,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RootDirConfig {
     pub name: String,
     pub path: String,
+    /// 根目录允许的操作，未配置时全部允许
+    #[serde(default)]
+    pub permissions: Permissions,
+    /// 覆盖全局分页设置，未配置的项使用全局设置
+    #[serde(default)]
+    pub paging: PagingOverrides,
+    /// 日志行首时间戳的格式（strftime 语法），用于按时间跳转，默认为 `%Y-%m-%d %H:%M:%S`
+    #[serde(rename = "timestampFormat", default, skip_serializing_if = "Option::is_none")]
+    pub timestamp_format: Option<String>,
+}
+
+/// 分页设置
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Paging {
+    /// 大文件每页的行数
+    #[serde(rename = "linesPerPage", default = "default_lines_per_page")]
+    pub lines_per_page: usize,
+    /// 内容达到该大小（字节）的文件分页显示，压缩文件按解压后的大小判断
+    #[serde(rename = "largeFileThreshold", default = "default_large_file_threshold")]
+    pub large_file_threshold: u64,
+    /// 客户端通过 `pageSize` 最多可以请求的每页行数
+    #[serde(rename = "maxPageSize", default = "default_max_page_size")]
+    pub max_page_size: usize,
+}
+
+impl Paging {
+    /// 计算每页的行数，不分页时返回 `None`
+    ///
+    /// 客户端指定了 `requested` 时总是分页，并限制在 `max_page_size` 以内；
+    /// 否则内容达到 `large_file_threshold` 时按 `lines_per_page` 分页。
+    pub fn lines_per_page(&self, content_size: u64, requested: Option<usize>) -> Option<usize> {
+        match requested {
+            Some(page_size) => Some(page_size.clamp(1, self.max_page_size.max(1))),
+            None => (content_size >= self.large_file_threshold).then_some(self.lines_per_page.max(1)),
+        }
+    }
+
+    /// 用根目录的设置覆盖
+    fn with_overrides(self, overrides: &PagingOverrides) -> Paging {
+        Paging {
+            lines_per_page: overrides.lines_per_page.unwrap_or(self.lines_per_page),
+            large_file_threshold: overrides.large_file_threshold.unwrap_or(self.large_file_threshold),
+            max_page_size: overrides.max_page_size.unwrap_or(self.max_page_size),
+        }
+    }
+}
+
+impl Default for Paging {
+    fn default() -> Self {
+        Paging {
+            lines_per_page: default_lines_per_page(),
+            large_file_threshold: default_large_file_threshold(),
+            max_page_size: default_max_page_size(),
+        }
+    }
+}
+
+/// 根目录的分页设置，各项含义见 `Paging`
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct PagingOverrides {
+    #[serde(rename = "linesPerPage", skip_serializing_if = "Option::is_none")]
+    pub lines_per_page: Option<usize>,
+    #[serde(rename = "largeFileThreshold", skip_serializing_if = "Option::is_none")]
+    pub large_file_threshold: Option<u64>,
+    #[serde(rename = "maxPageSize", skip_serializing_if = "Option::is_none")]
+    pub max_page_size: Option<usize>,
+}
+
+impl From<Paging> for PagingOverrides {
+    fn from(paging: Paging) -> Self {
+        PagingOverrides {
+            lines_per_page: Some(paging.lines_per_page),
+            large_file_threshold: Some(paging.large_file_threshold),
+            max_page_size: Some(paging.max_page_size),
+        }
+    }
+}
+
+/// 根目录操作权限
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    Read,
+    Write,
+    Create,
+    Delete,
+    Upload,
+    Download,
+}
+
+/// 根目录权限标志
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Permissions {
+    #[serde(default = "default_true")]
+    pub read: bool,
+    #[serde(default = "default_true")]
+    pub write: bool,
+    #[serde(default = "default_true")]
+    pub create: bool,
+    #[serde(default = "default_true")]
+    pub delete: bool,
+    #[serde(default = "default_true")]
+    pub upload: bool,
+    #[serde(default = "default_true")]
+    pub download: bool,
+}
+
+impl Permissions {
+    /// 判断是否允许指定操作
+    pub fn allows(&self, permission: Permission) -> bool {
+        match permission {
+            Permission::Read => self.read,
+            Permission::Write => self.write,
+            Permission::Create => self.create,
+            Permission::Delete => self.delete,
+            Permission::Upload => self.upload,
+            Permission::Download => self.download,
+        }
+    }
+
+    /// 两组权限的并集
+    pub fn union(self, other: Permissions) -> Permissions {
+        Permissions {
+            read: self.read || other.read,
+            write: self.write || other.write,
+            create: self.create || other.create,
+            delete: self.delete || other.delete,
+            upload: self.upload || other.upload,
+            download: self.download || other.download,
+        }
+    }
+
+    /// 两组权限的交集
+    pub fn intersect(self, other: Permissions) -> Permissions {
+        Permissions {
+            read: self.read && other.read,
+            write: self.write && other.write,
+            create: self.create && other.create,
+            delete: self.delete && other.delete,
+            upload: self.upload && other.upload,
+            download: self.download && other.download,
+        }
+    }
+}
+
+impl Default for Permissions {
+    fn default() -> Self {
+        Permissions {
+            read: true,
+            write: true,
+            create: true,
+            delete: true,
+            upload: true,
+            download: true,
+        }
+    }
+}
+
+/// 用户配置，密码以 argon2 哈希保存
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserConfig {
+    pub username: String,
+    #[serde(rename = "passwordHash")]
+    pub password_hash: String,
+    /// 用户所属的角色名称
+    #[serde(default)]
+    pub roles: Vec<String>,
+}
+
+/// 角色配置，授予一组根目录及对应权限
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoleConfig {
+    pub name: String,
+    #[serde(default)]
+    pub roots: Vec<RoleGrant>,
+}
+
+/// 角色对单个根目录（按名称引用）的授权
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoleGrant {
+    pub root: String,
+    #[serde(default)]
+    pub permissions: Permissions,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,13 +242,52 @@ fn default_root_dirs() -> Vec<RootDirConfig> {
     vec![RootDirConfig {
         name: "默认目录".to_string(),
         path: ".".to_string(),
+        permissions: Permissions::default(),
+        paging: PagingOverrides::default(),
+        timestamp_format: None,
     }]
 }
 
+fn default_true() -> bool {
+    true
+}
+
 fn default_port() -> u16 {
     8080
 }
 
+fn default_session_ttl() -> u64 {
+    12 * 60 * 60
+}
+
+fn default_trash_retention_days() -> u64 {
+    30
+}
+
+fn default_search_concurrency() -> usize {
+    2
+}
+
+fn default_max_extract_size() -> u64 {
+    4 * 1024 * 1024 * 1024
+}
+
+fn default_max_extract_entries() -> u64 {
+    100_000
+}
+
+fn default_lines_per_page() -> usize {
+    1000
+}
+
+fn default_large_file_threshold() -> u64 {
+    10 * 1024 * 1024
+}
+
+fn default_max_page_size() -> usize {
+    10000
+}
+
 fn default_static_dirs() -> Vec<StaticDirConfig> {
     vec![StaticDirConfig {
         name: "default".to_string(),
@@ -48,6 +302,59 @@ impl Config {
         let config: Config = serde_json::from_str(&content)?;
         Ok(config)
     }
+
+    /// 是否不需要登录即可访问：只有未配置用户且显式开启 `allowAnonymous` 时才允许
+    pub fn anonymous_access(&self) -> bool {
+        self.users.is_empty() && self.allow_anonymous
+    }
+
+    /// 根目录生效的分页设置，`root_index` 为 `root_dirs` 中的下标（不是用户可见列表中的下标），下标无效时返回全局设置
+    pub fn paging(&self, root_index: usize) -> Paging {
+        match self.root_dirs.get(root_index) {
+            Some(root) => self.paging.with_overrides(&root.paging),
+            None => self.paging,
+        }
+    }
+
+    /// 计算用户可见的根目录，返回其在 `root_dirs` 中的下标和生效权限
+    ///
+    /// 未启用认证或未配置角色时所有根目录都可见；否则只返回用户角色授权的根目录，
+    /// 生效权限为根目录自身权限与角色授权的交集。
+    pub fn visible_roots(&self, username: Option<&str>) -> Vec<(usize, Permissions)> {
+        let user = match username {
+            Some(username) if !self.roles.is_empty() => {
+                self.users.iter().find(|u| u.username == username)
+            }
+            _ => {
+                return self
+                    .root_dirs
+                    .iter()
+                    .enumerate()
+                    .map(|(i, root)| (i, root.permissions))
+                    .collect();
+            }
+        };
+
+        let grants: Vec<&RoleGrant> = self
+            .roles
+            .iter()
+            .filter(|role| user.is_some_and(|u| u.roles.contains(&role.name)))
+            .flat_map(|role| role.roots.iter())
+            .collect();
+
+        self.root_dirs
+            .iter()
+            .enumerate()
+            .filter_map(|(i, root)| {
+                grants
+                    .iter()
+                    .filter(|grant| grant.root == root.name)
+                    .map(|grant| grant.permissions)
+                    .reduce(Permissions::union)
+                    .map(|granted| (i, root.permissions.intersect(granted)))
+            })
+            .collect()
+    }
 }
 
 impl Default for Config {
@@ -56,6 +363,138 @@ impl Default for Config {
             root_dirs: default_root_dirs(),
             port: default_port(),
             static_dirs: default_static_dirs(),
+            users: Vec::new(),
+            allow_anonymous: false,
+            roles: Vec::new(),
+            session_ttl: default_session_ttl(),
+            cors_allowed_origins: Vec::new(),
+            trash_retention_days: default_trash_retention_days(),
+            max_upload_size: 0,
+            max_extract_size: default_max_extract_size(),
+            max_extract_entries: default_max_extract_entries(),
+            search_concurrency: default_search_concurrency(),
+            paging: Paging::default(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Permission; 6] = [
+        Permission::Read,
+        Permission::Write,
+        Permission::Create,
+        Permission::Delete,
+        Permission::Upload,
+        Permission::Download,
+    ];
+
+    fn config() -> Config {
+        serde_json::from_value(serde_json::json!({
+            "rootDirs": [
+                { "name": "logs", "path": "/var/log", "permissions": { "delete": false } },
+                { "name": "data", "path": "/data" },
+                { "name": "private", "path": "/private" }
+            ],
+            "users": [
+                { "username": "alice", "passwordHash": "", "roles": ["viewer", "uploader"] },
+                { "username": "bob", "passwordHash": "", "roles": ["viewer"] },
+                { "username": "carol", "passwordHash": "" }
+            ],
+            "roles": [
+                {
+                    "name": "viewer",
+                    "roots": [
+                        { "root": "logs", "permissions": { "write": false, "create": false, "delete": false, "upload": false } },
+                        { "root": "data", "permissions": { "write": false, "create": false, "delete": false, "upload": false, "download": false } }
+                    ]
+                },
+                {
+                    "name": "uploader",
+                    "roots": [{ "root": "data", "permissions": { "delete": false } }]
+                }
+            ]
+        }))
+        .unwrap()
+    }
+
+    /// 权限转换为允许的操作列表，便于比较
+    fn allowed(permissions: Permissions) -> Vec<Permission> {
+        ALL.into_iter().filter(|p| permissions.allows(*p)).collect()
+    }
+
+    fn roots(config: &Config, username: Option<&str>) -> Vec<(usize, Vec<Permission>)> {
+        config
+            .visible_roots(username)
+            .into_iter()
+            .map(|(i, permissions)| (i, allowed(permissions)))
+            .collect()
+    }
+
+    #[test]
+    fn grants_are_intersected_with_root_permissions() {
+        let config = config();
+        assert_eq!(
+            roots(&config, Some("bob")),
+            vec![
+                (0, vec![Permission::Read, Permission::Download]),
+                (1, vec![Permission::Read]),
+            ]
+        );
+    }
+
+    #[test]
+    fn grants_from_several_roles_are_united() {
+        let config = config();
+        assert_eq!(
+            roots(&config, Some("alice")),
+            vec![
+                (0, vec![Permission::Read, Permission::Download]),
+                (
+                    1,
+                    vec![
+                        Permission::Read,
+                        Permission::Write,
+                        Permission::Create,
+                        Permission::Upload,
+                        Permission::Download,
+                    ]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn users_without_roles_see_nothing() {
+        let config = config();
+        assert!(roots(&config, Some("carol")).is_empty());
+        assert!(roots(&config, Some("mallory")).is_empty());
+    }
+
+    #[test]
+    fn all_roots_are_visible_without_roles() {
+        let mut config = config();
+        let expected = vec![
+            (0, vec![Permission::Read, Permission::Write, Permission::Create, Permission::Upload, Permission::Download]),
+            (1, ALL.to_vec()),
+            (2, ALL.to_vec()),
+        ];
+        assert_eq!(roots(&config, None), expected);
+
+        config.roles.clear();
+        assert_eq!(roots(&config, Some("carol")), expected);
+    }
+
+    #[test]
+    fn extract_size_is_limited_by_default() {
+        let config: Config = serde_json::from_value(serde_json::json!({ "maxUploadSize": 0 })).unwrap();
+        assert_eq!(config.max_extract_size, 4 * 1024 * 1024 * 1024);
+        assert_eq!(Config::default().max_extract_size, config.max_extract_size);
+
+        // 只有显式配置为 0 时才不限制
+        let config: Config = serde_json::from_value(serde_json::json!({ "maxExtractSize": 0 })).unwrap();
+        assert_eq!(config.max_extract_size, 0);
+    }
+}
@@ -0,0 +1,399 @@
+use axum::{
+    body::{Body, Bytes},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
+use std::{
+    fs::Metadata,
+    io::{self, SeekFrom},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio_util::io::ReaderStream;
+
+/// 流式读取时的缓冲区大小
+const STREAM_BUFFER_SIZE: usize = 64 * 1024;
+
+/// 单个请求允许的最大区间数，超出时忽略 Range 头返回完整文件
+const MAX_RANGES: usize = 64;
+
+/// multipart/byteranges 响应的分隔符
+const BYTERANGES_BOUNDARY: &str = "filebrowser-byteranges";
+
+/// 闭区间字节范围
+#[derive(Debug, Clone, Copy)]
+struct ByteRange {
+    start: u64,
+    end: u64,
+}
+
+impl ByteRange {
+    fn len(&self) -> u64 {
+        self.end - self.start + 1
+    }
+
+    fn content_range(&self, size: u64) -> String {
+        format!("bytes {}-{}/{}", self.start, self.end, size)
+    }
+}
+
+/// Range 头解析结果
+enum RangeRequest {
+    /// 没有或忽略 Range 头
+    Full,
+    /// 可满足的区间列表
+    Partial(Vec<ByteRange>),
+    /// 所有区间都超出文件范围
+    Unsatisfiable,
+}
+
+/// 以流的方式返回文件内容，支持条件请求和 Range 请求
+pub async fn serve_file(
+    path: PathBuf,
+    metadata: &Metadata,
+    headers: &HeaderMap,
+    disposition: &str,
+) -> Result<Response, StatusCode> {
+    let size = metadata.len();
+    let modified = metadata.modified().ok();
+    let etag = make_etag(size, modified);
+    let last_modified = modified.map(httpdate::fmt_http_date);
+
+    let content_type = mime_guess::from_path(&path)
+        .first_or_octet_stream()
+        .to_string();
+
+    let mut common = HeaderMap::new();
+    common.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    common.insert(header::ETAG, header_value(&etag)?);
+    if let Some(last_modified) = &last_modified {
+        common.insert(header::LAST_MODIFIED, header_value(last_modified)?);
+    }
+
+    // 条件请求：内容未变化时返回 304
+    if is_not_modified(headers, &etag, modified) {
+        return Ok((StatusCode::NOT_MODIFIED, common).into_response());
+    }
+
+    common.insert(header::CONTENT_DISPOSITION, header_value(disposition)?);
+
+    let range_request = if if_range_matches(headers, &etag, last_modified.as_deref()) {
+        parse_range(headers, size)
+    } else {
+        RangeRequest::Full
+    };
+
+    match range_request {
+        RangeRequest::Full => {
+            common.insert(header::CONTENT_TYPE, header_value(&content_type)?);
+            common.insert(header::CONTENT_LENGTH, HeaderValue::from(size));
+            let body = Body::from_stream(file_stream(path, 0, size));
+            Ok((StatusCode::OK, common, body).into_response())
+        }
+        RangeRequest::Unsatisfiable => {
+            common.insert(
+                header::CONTENT_RANGE,
+                header_value(&format!("bytes */{}", size))?,
+            );
+            Ok((StatusCode::RANGE_NOT_SATISFIABLE, common).into_response())
+        }
+        RangeRequest::Partial(ranges) if ranges.len() == 1 => {
+            let range = ranges[0];
+            common.insert(header::CONTENT_TYPE, header_value(&content_type)?);
+            common.insert(header::CONTENT_LENGTH, HeaderValue::from(range.len()));
+            common.insert(header::CONTENT_RANGE, header_value(&range.content_range(size))?);
+            let body = Body::from_stream(file_stream(path, range.start, range.len()));
+            Ok((StatusCode::PARTIAL_CONTENT, common, body).into_response())
+        }
+        RangeRequest::Partial(ranges) => {
+            let mut content_length = 0u64;
+            let mut parts = Vec::with_capacity(ranges.len());
+            for range in ranges {
+                let part_header = format!(
+                    "\r\n--{}\r\nContent-Type: {}\r\nContent-Range: {}\r\n\r\n",
+                    BYTERANGES_BOUNDARY,
+                    content_type,
+                    range.content_range(size)
+                );
+                content_length += part_header.len() as u64 + range.len();
+                let part = stream::once(async move { Ok(Bytes::from(part_header)) })
+                    .chain(file_stream(path.clone(), range.start, range.len()));
+                parts.push(part);
+            }
+
+            let trailer = format!("\r\n--{}--\r\n", BYTERANGES_BOUNDARY);
+            content_length += trailer.len() as u64;
+
+            let body = stream::iter(parts)
+                .flatten()
+                .chain(stream::once(async move { Ok(Bytes::from(trailer)) }));
+
+            common.insert(
+                header::CONTENT_TYPE,
+                header_value(&format!(
+                    "multipart/byteranges; boundary={}",
+                    BYTERANGES_BOUNDARY
+                ))?,
+            );
+            common.insert(header::CONTENT_LENGTH, HeaderValue::from(content_length));
+            Ok((StatusCode::PARTIAL_CONTENT, common, Body::from_stream(body)).into_response())
+        }
+    }
+}
+
+/// 从指定偏移开始流式读取 `len` 字节
+fn file_stream(path: PathBuf, start: u64, len: u64) -> impl Stream<Item = io::Result<Bytes>> {
+    stream::once(async move {
+        let mut file = tokio::fs::File::open(&path).await?;
+        file.seek(SeekFrom::Start(start)).await?;
+        Ok::<_, io::Error>(ReaderStream::with_capacity(
+            file.take(len),
+            STREAM_BUFFER_SIZE,
+        ))
+    })
+    .try_flatten()
+}
+
+/// 根据文件大小和修改时间生成 ETag
+fn make_etag(size: u64, modified: Option<SystemTime>) -> String {
+    let nanos = modified
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    format!("\"{:x}-{:x}\"", nanos, size)
+}
+
+/// 判断 If-None-Match / If-Modified-Since 是否命中
+fn is_not_modified(headers: &HeaderMap, etag: &str, modified: Option<SystemTime>) -> bool {
+    // If-None-Match 优先于 If-Modified-Since
+    if let Some(if_none_match) = header_str(headers, &header::IF_NONE_MATCH) {
+        return if_none_match
+            .split(',')
+            .map(|tag| tag.trim())
+            .any(|tag| tag == "*" || weak_eq(tag, etag));
+    }
+
+    match (header_str(headers, &header::IF_MODIFIED_SINCE), modified) {
+        (Some(since), Some(modified)) => match httpdate::parse_http_date(since) {
+            // HTTP 日期只精确到秒
+            Ok(since) => truncate_to_secs(modified) <= since,
+            Err(_) => false,
+        },
+        _ => false,
+    }
+}
+
+/// 判断 If-Range 是否允许使用 Range 头
+fn if_range_matches(headers: &HeaderMap, etag: &str, last_modified: Option<&str>) -> bool {
+    match header_str(headers, &header::IF_RANGE) {
+        None => true,
+        // If-Range 要求强比较，弱 ETag 永不匹配
+        Some(value) if value.starts_with('"') => value == etag,
+        Some(value) => Some(value) == last_modified,
+    }
+}
+
+/// 解析 Range 头，只支持 bytes 单位
+fn parse_range(headers: &HeaderMap, size: u64) -> RangeRequest {
+    let value = match header_str(headers, &header::RANGE) {
+        Some(value) => value,
+        None => return RangeRequest::Full,
+    };
+
+    let specs = match value.trim().strip_prefix("bytes=") {
+        Some(specs) => specs,
+        None => return RangeRequest::Full,
+    };
+
+    let mut ranges = Vec::new();
+    for spec in specs.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+        let (first, last) = match spec.split_once('-') {
+            Some(pair) => pair,
+            // 语法错误时按规范忽略整个 Range 头
+            None => return RangeRequest::Full,
+        };
+
+        let range = if first.is_empty() {
+            // 后缀区间：最后 N 个字节
+            let suffix: u64 = match last.parse() {
+                Ok(n) => n,
+                Err(_) => return RangeRequest::Full,
+            };
+            if suffix == 0 || size == 0 {
+                continue;
+            }
+            ByteRange {
+                start: size.saturating_sub(suffix),
+                end: size - 1,
+            }
+        } else {
+            let start: u64 = match first.parse() {
+                Ok(n) => n,
+                Err(_) => return RangeRequest::Full,
+            };
+            let end: u64 = if last.is_empty() {
+                u64::MAX
+            } else {
+                match last.parse() {
+                    Ok(n) => n,
+                    Err(_) => return RangeRequest::Full,
+                }
+            };
+            if end < start {
+                return RangeRequest::Full;
+            }
+            if start >= size {
+                continue;
+            }
+            ByteRange {
+                start,
+                end: end.min(size - 1),
+            }
+        };
+
+        ranges.push(range);
+    }
+
+    if ranges.len() > MAX_RANGES {
+        return RangeRequest::Full;
+    }
+
+    if ranges.is_empty() {
+        RangeRequest::Unsatisfiable
+    } else {
+        RangeRequest::Partial(merge_ranges(ranges))
+    }
+}
+
+/// 排序并合并重叠或相邻的区间，防止重复请求同一段内容放大响应（RFC 9110 §14.2）
+fn merge_ranges(mut ranges: Vec<ByteRange>) -> Vec<ByteRange> {
+    ranges.sort_by_key(|range| range.start);
+
+    let mut merged: Vec<ByteRange> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end.saturating_add(1) => {
+                last.end = last.end.max(range.end);
+            }
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// 弱比较两个 ETag
+fn weak_eq(a: &str, b: &str) -> bool {
+    a.trim_start_matches("W/") == b.trim_start_matches("W/")
+}
+
+fn truncate_to_secs(time: SystemTime) -> SystemTime {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| UNIX_EPOCH + std::time::Duration::from_secs(d.as_secs()))
+        .unwrap_or(time)
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &header::HeaderName) -> Option<&'a str> {
+    headers.get(name).and_then(|v| v.to_str().ok())
+}
+
+fn header_value(value: &str) -> Result<HeaderValue, StatusCode> {
+    HeaderValue::from_str(value).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// 生成 Content-Disposition 头，非 ASCII 文件名使用 RFC 5987 编码
+pub fn attachment_disposition(path: &Path) -> String {
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("download");
+
+    let ascii_name: String = file_name
+        .chars()
+        .map(|c| if c.is_ascii() && c != '"' && c != '\\' && !c.is_ascii_control() { c } else { '_' })
+        .collect();
+
+    format!(
+        "attachment; filename=\"{}\"; filename*=UTF-8''{}",
+        ascii_name,
+        urlencoding::encode(file_name)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(value: &str, size: u64) -> RangeRequest {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::RANGE, HeaderValue::from_str(value).unwrap());
+        parse_range(&headers, size)
+    }
+
+    fn spans(value: &str, size: u64) -> Vec<(u64, u64)> {
+        match parse(value, size) {
+            RangeRequest::Partial(ranges) => ranges.iter().map(|r| (r.start, r.end)).collect(),
+            _ => panic!("{} 应解析为部分内容", value),
+        }
+    }
+
+    #[test]
+    fn parses_single_ranges() {
+        assert_eq!(spans("bytes=0-99", 1000), vec![(0, 99)]);
+        assert_eq!(spans("bytes=900-", 1000), vec![(900, 999)]);
+        assert_eq!(spans("bytes=-100", 1000), vec![(900, 999)]);
+        // 结束位置和后缀长度超出文件大小时截断
+        assert_eq!(spans("bytes=990-2000", 1000), vec![(990, 999)]);
+        assert_eq!(spans("bytes=-5000", 1000), vec![(0, 999)]);
+    }
+
+    #[test]
+    fn parses_multiple_ranges() {
+        assert_eq!(spans("bytes=0-9, 20-29,-5", 100), vec![(0, 9), (20, 29), (95, 99)]);
+        // 超出文件范围的区间被丢弃，其余区间保留
+        assert_eq!(spans("bytes=0-9,500-600", 100), vec![(0, 9)]);
+        assert_eq!(spans("bytes=0-9,,", 100), vec![(0, 9)]);
+    }
+
+    #[test]
+    fn ignores_malformed_ranges() {
+        let headers = HeaderMap::new();
+        assert!(matches!(parse_range(&headers, 100), RangeRequest::Full));
+
+        for value in ["items=0-9", "bytes=abc", "bytes=a-9", "bytes=0-b", "bytes=-x", "bytes=9-0", "bytes=0-9,5"] {
+            assert!(matches!(parse(value, 100), RangeRequest::Full), "{}", value);
+        }
+    }
+
+    #[test]
+    fn ignores_too_many_ranges() {
+        let value = format!("bytes={}", vec!["0-0"; MAX_RANGES + 1].join(","));
+        assert!(matches!(parse(&value, 100), RangeRequest::Full));
+
+        let specs: Vec<String> = (0..MAX_RANGES).map(|i| format!("{0}-{0}", i * 2)).collect();
+        let value = format!("bytes={}", specs.join(","));
+        assert_eq!(spans(&value, 1000).len(), MAX_RANGES);
+    }
+
+    #[test]
+    fn merges_overlapping_ranges() {
+        // 重复的整文件区间合并为一个，不会把文件内容放大数十倍
+        let value = format!("bytes={}", vec!["0-"; MAX_RANGES].join(","));
+        assert_eq!(spans(&value, 1000), vec![(0, 999)]);
+
+        assert_eq!(spans("bytes=50-59,0-9,5-20", 100), vec![(0, 20), (50, 59)]);
+        // 相邻区间同样合并
+        assert_eq!(spans("bytes=0-9,10-19,-80", 100), vec![(0, 99)]);
+        assert_eq!(spans("bytes=10-19,0-9,30-39", 100), vec![(0, 19), (30, 39)]);
+    }
+
+    #[test]
+    fn reports_unsatisfiable_ranges() {
+        assert!(matches!(parse("bytes=100-", 100), RangeRequest::Unsatisfiable));
+        assert!(matches!(parse("bytes=100-200,300-", 100), RangeRequest::Unsatisfiable));
+        assert!(matches!(parse("bytes=-0", 100), RangeRequest::Unsatisfiable));
+        assert!(matches!(parse("bytes=0-", 0), RangeRequest::Unsatisfiable));
+        assert!(matches!(parse("bytes=-10", 0), RangeRequest::Unsatisfiable));
+    }
+}
@@ -0,0 +1,76 @@
+use chardetng::{EncodingDetector, Iso2022JpDetection, Utf8Detection};
+use encoding_rs::{Encoding, UTF_8};
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+/// 检测编码时最多查看的字节数
+const SAMPLE_SIZE: usize = 64 * 1024;
+
+/// 根据内容开头的字节判断编码
+///
+/// 依次检查 BOM、是否为有效的 UTF-8，最后按字节分布猜测（如 GBK、Big5、windows-1252）。
+/// 按行读取要求编码兼容 ASCII，UTF-16 等其他编码按 UTF-8 处理。
+pub fn detect(sample: &[u8]) -> &'static Encoding {
+    let sample = &sample[..sample.len().min(SAMPLE_SIZE)];
+
+    if let Some((encoding, _)) = Encoding::for_bom(sample) {
+        return ascii_compatible_or_utf8(encoding);
+    }
+
+    match std::str::from_utf8(sample) {
+        Ok(_) => return UTF_8,
+        // 样本末尾被截断的多字节字符不算无效
+        Err(e) if e.error_len().is_none() => return UTF_8,
+        Err(_) => {}
+    }
+
+    let mut detector = EncodingDetector::new(Iso2022JpDetection::Deny);
+    detector.feed(sample, false);
+    ascii_compatible_or_utf8(detector.guess(None, Utf8Detection::Allow))
+}
+
+/// 读取文件开头判断编码，不解压
+pub fn detect_file(path: &Path) -> io::Result<&'static Encoding> {
+    let mut sample = Vec::with_capacity(SAMPLE_SIZE);
+    File::open(path)?.take(SAMPLE_SIZE as u64).read_to_end(&mut sample)?;
+    Ok(detect(&sample))
+}
+
+fn ascii_compatible_or_utf8(encoding: &'static Encoding) -> &'static Encoding {
+    if encoding.is_ascii_compatible() {
+        encoding
+    } else {
+        UTF_8
+    }
+}
+
+/// 按名称查找编码，如 `gbk`、`gb18030`、`latin1`，不支持时返回错误说明
+pub fn from_label(label: &str) -> Result<&'static Encoding, String> {
+    match Encoding::for_label(label.trim().as_bytes()) {
+        Some(encoding) if encoding.is_ascii_compatible() => Ok(encoding),
+        _ => Err(format!("不支持的编码: {}", label)),
+    }
+}
+
+/// 解码一行，无效的字节按替换字符显示，第一行同时去掉开头的 BOM
+pub fn decode_line(encoding: &'static Encoding, bytes: &[u8], first: bool, line: &mut String) {
+    let text = if first {
+        encoding.decode_with_bom_removal(bytes).0
+    } else {
+        encoding.decode_without_bom_handling(bytes).0
+    };
+    line.clear();
+    line.push_str(&text);
+}
+
+/// 把文本编码为指定编码，包含无法表示的字符时返回错误说明
+pub fn encode(encoding: &'static Encoding, text: &str) -> Result<Vec<u8>, String> {
+    let (bytes, _, had_errors) = encoding.encode(text);
+    if had_errors {
+        return Err(format!("内容包含无法用 {} 编码的字符", encoding.name()));
+    }
+    Ok(bytes.into_owned())
+}
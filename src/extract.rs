@@ -0,0 +1,539 @@
+use crate::compression::{self, Compression};
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, BufReader, Read},
+    path::{Component, Path, PathBuf},
+    time::SystemTime,
+};
+use tracing::warn;
+use zip::ZipArchive;
+
+/// 支持浏览和解压的压缩包格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Zip,
+    Tar(Compression),
+}
+
+/// 压缩包条目类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemberKind {
+    File,
+    Dir,
+    /// 符号链接、硬链接、设备文件等，浏览时显示但不会被解压
+    Other,
+}
+
+/// 压缩包中的条目
+#[derive(Debug, Clone)]
+pub struct Member {
+    /// 条目路径（规范化后，使用 `/` 分隔）
+    pub path: String,
+    pub kind: MemberKind,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    pub mode: Option<u32>,
+}
+
+impl Member {
+    pub fn is_dir(&self) -> bool {
+        self.kind == MemberKind::Dir
+    }
+
+    /// 条目名称（路径的最后一段）
+    pub fn name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+}
+
+/// 解压结果统计
+#[derive(Debug, Default)]
+pub struct ExtractSummary {
+    pub files: u64,
+    pub skipped: u64,
+}
+
+/// 单次解压的上限，防止压缩炸弹占满磁盘，0 表示不限制
+#[derive(Debug, Clone, Copy)]
+pub struct ExtractLimits {
+    /// 解压后最多写入的字节数
+    pub max_bytes: u64,
+    /// 最多创建的文件和目录数量
+    pub max_entries: u64,
+}
+
+/// 根据文件名判断压缩包格式
+pub fn archive_kind(path: &Path) -> Option<ArchiveKind> {
+    let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+    if name.ends_with(".zip") {
+        Some(ArchiveKind::Zip)
+    } else if name.ends_with(".tar") {
+        Some(ArchiveKind::Tar(Compression::None))
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        Some(ArchiveKind::Tar(Compression::Gzip))
+    } else if name.ends_with(".tar.zst") || name.ends_with(".tzst") {
+        Some(ArchiveKind::Tar(Compression::Zstd))
+    } else {
+        None
+    }
+}
+
+/// 判断请求路径是否指向压缩包或压缩包内部，返回压缩包的完整路径和包内路径
+///
+/// 例如 `logs/2024.tar.gz/app/error.log` 返回 `(<root>/logs/2024.tar.gz, "app/error.log")`。
+/// 压缩包本身仍然经过 `validate_and_resolve_path` 校验。
+pub fn locate(root_path: &Path, requested_path: &str) -> Option<(PathBuf, String)> {
+    let parts: Vec<&str> = requested_path
+        .split('/')
+        .filter(|part| !part.is_empty() && *part != ".")
+        .collect();
+
+    for i in 1..=parts.len() {
+        let prefix = parts[..i].join("/");
+        let path = crate::validate_and_resolve_path(root_path, &prefix).ok()?;
+        let metadata = fs::metadata(&path).ok()?;
+        if metadata.is_dir() {
+            continue;
+        }
+        // 路径中第一个不是目录的部分必须是压缩包
+        archive_kind(&path)?;
+        return Some((path, parts[i..].join("/")));
+    }
+
+    None
+}
+
+/// 列出压缩包中某个目录下的直接子条目，`inner` 为空时列出顶层
+///
+/// 没有单独目录条目的中间目录会根据文件路径补全。
+pub fn list_dir(archive: &Path, inner: &str) -> io::Result<Vec<Member>> {
+    let inner = inner.trim_matches('/');
+    let prefix = if inner.is_empty() {
+        String::new()
+    } else {
+        format!("{}/", inner)
+    };
+
+    let mut children: BTreeMap<String, Member> = BTreeMap::new();
+    let mut found = inner.is_empty();
+
+    for member in list_members(archive)? {
+        if member.path == inner {
+            if !member.is_dir() {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, "不是目录"));
+            }
+            found = true;
+            continue;
+        }
+        let Some(rest) = member.path.strip_prefix(&prefix) else {
+            continue;
+        };
+        found = true;
+
+        match rest.split_once('/') {
+            Some((dir, _)) => {
+                children.entry(dir.to_string()).or_insert_with(|| Member {
+                    path: format!("{}{}", prefix, dir),
+                    kind: MemberKind::Dir,
+                    size: 0,
+                    modified: None,
+                    mode: None,
+                });
+            }
+            None => {
+                children.insert(rest.to_string(), member);
+            }
+        }
+    }
+
+    if !found {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "压缩包中不存在该路径"));
+    }
+
+    Ok(children.into_values().collect())
+}
+
+/// 列出压缩包中的所有条目，路径不安全的条目会被忽略
+pub fn list_members(archive: &Path) -> io::Result<Vec<Member>> {
+    let mut members = Vec::new();
+    match archive_kind(archive).ok_or_else(unsupported)? {
+        ArchiveKind::Zip => {
+            let mut zip = ZipArchive::new(File::open(archive)?)?;
+            for i in 0..zip.len() {
+                // 只读取条目信息，不解压数据
+                if let Some(member) = zip_member(&zip.by_index_raw(i)?) {
+                    members.push(member);
+                }
+            }
+        }
+        ArchiveKind::Tar(compression) => {
+            for_each_tar_member(archive, compression, |member, _| {
+                members.push(member);
+                Ok(false)
+            })?;
+        }
+    }
+    Ok(members)
+}
+
+/// 打开压缩包中的文件并交给回调读取，回调的第二个参数为解压后的大小
+pub fn with_member<T>(
+    archive: &Path,
+    inner: &str,
+    f: impl FnOnce(&mut dyn Read, u64) -> io::Result<T>,
+) -> io::Result<T> {
+    let inner = inner.trim_matches('/');
+    let mut f = Some(f);
+    let mut result = None;
+
+    match archive_kind(archive).ok_or_else(unsupported)? {
+        ArchiveKind::Zip => {
+            let mut zip = ZipArchive::new(File::open(archive)?)?;
+            for i in 0..zip.len() {
+                let member = zip_member(&zip.by_index_raw(i)?);
+                if let Some(member) = member.filter(|m| m.path == inner) {
+                    if member.kind != MemberKind::File {
+                        return Err(io::Error::new(io::ErrorKind::InvalidInput, "不是文件"));
+                    }
+                    let mut reader = zip.by_index(i)?;
+                    if let Some(f) = f.take() {
+                        result = Some(f(&mut reader, member.size)?);
+                    }
+                    break;
+                }
+            }
+        }
+        ArchiveKind::Tar(compression) => {
+            for_each_tar_member(archive, compression, |member, reader| {
+                if member.path != inner {
+                    return Ok(false);
+                }
+                if member.kind != MemberKind::File {
+                    return Err(io::Error::new(io::ErrorKind::InvalidInput, "不是文件"));
+                }
+                if let Some(f) = f.take() {
+                    result = Some(f(reader, member.size)?);
+                }
+                Ok(true)
+            })?;
+        }
+    }
+
+    result.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "压缩包中不存在该文件"))
+}
+
+/// 把压缩包解压到根目录内的指定目录
+///
+/// 目录逐级经过 `validate_and_resolve_path` 校验后再创建，已存在的符号链接目录指向根目录之外时会被拒绝，
+/// 防止通过 `../` 或符号链接写到根目录之外（zip-slip）。包内的符号链接等特殊条目不会被解压。
+/// 写入的字节数或创建的条目数超过 `limits` 时中止，返回 `FileTooLarge` 或 `QuotaExceeded` 错误，
+/// 已经解压的内容会保留，超出上限的那个文件会被删除。
+pub fn extract(
+    archive: &Path,
+    root_path: &Path,
+    destination: &Path,
+    overwrite: bool,
+    limits: ExtractLimits,
+) -> io::Result<ExtractSummary> {
+    let relative_destination = destination
+        .strip_prefix(root_path)
+        .map_err(|_| io::Error::new(io::ErrorKind::PermissionDenied, "访问被拒绝：路径超出根目录"))?
+        .to_path_buf();
+
+    let mut summary = ExtractSummary::default();
+    let mut count = |outcome: Outcome| match outcome {
+        Outcome::File => summary.files += 1,
+        Outcome::Dir => {}
+        Outcome::Skipped => summary.skipped += 1,
+    };
+
+    let (mut written, mut entries) = (0u64, 0u64);
+    let mut extract_member = |member: Member, reader: &mut dyn Read| -> io::Result<Outcome> {
+        if member.kind == MemberKind::Other {
+            return Ok(Outcome::Skipped);
+        }
+
+        let relative = relative_destination.join(&member.path);
+        if crate::is_reserved_path(root_path, &root_path.join(&relative)) {
+            warn!("解压时跳过内部目录中的条目: {}", member.path);
+            return Ok(Outcome::Skipped);
+        }
+
+        entries += 1;
+        if limits.max_entries > 0 && entries > limits.max_entries {
+            return Err(io::Error::new(
+                io::ErrorKind::QuotaExceeded,
+                format!("压缩包中的条目超过 {} 个上限", limits.max_entries),
+            ));
+        }
+
+        if member.is_dir() {
+            create_dirs(root_path, &relative)?;
+            return Ok(Outcome::Dir);
+        }
+
+        let parent = create_dirs(root_path, relative.parent().unwrap_or(Path::new("")))?;
+        let target = parent.join(member.name());
+
+        // 已存在的符号链接和目录不会被覆盖
+        let mut file = match fs::symlink_metadata(&target) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                File::options().write(true).create_new(true).open(&target)?
+            }
+            Ok(existing) if overwrite && existing.is_file() => File::create(&target)?,
+            Ok(_) => return Ok(Outcome::Skipped),
+            Err(e) => return Err(e),
+        };
+
+        // 多读一个字节，用来判断是否超过上限
+        let remaining = match limits.max_bytes {
+            0 => u64::MAX,
+            max => max.saturating_sub(written),
+        };
+        let copied = io::copy(&mut (&mut *reader).take(remaining.saturating_add(1)), &mut file)?;
+        if copied > remaining {
+            drop(file);
+            let _ = fs::remove_file(&target);
+            return Err(io::Error::new(
+                io::ErrorKind::FileTooLarge,
+                format!("解压后的内容超过 {} 字节上限", limits.max_bytes),
+            ));
+        }
+        written += copied;
+
+        if let Some(modified) = member.modified {
+            file.set_modified(modified)?;
+        }
+        #[cfg(unix)]
+        if let Some(mode) = member.mode {
+            use std::os::unix::fs::PermissionsExt;
+            // 不保留 setuid 等特殊权限位
+            file.set_permissions(fs::Permissions::from_mode(mode & 0o777))?;
+        }
+
+        Ok(Outcome::File)
+    };
+
+    match archive_kind(archive).ok_or_else(unsupported)? {
+        ArchiveKind::Zip => {
+            let mut zip = ZipArchive::new(File::open(archive)?)?;
+            for i in 0..zip.len() {
+                let (member, name) = {
+                    let file = zip.by_index_raw(i)?;
+                    (zip_member(&file), file.name().map(|n| n.into_owned()).unwrap_or_default())
+                };
+                match member {
+                    Some(member) => count(extract_member(member, &mut zip.by_index(i)?)?),
+                    None => {
+                        warn!("解压时跳过路径不安全的条目: {}", name);
+                        count(Outcome::Skipped);
+                    }
+                }
+            }
+        }
+        ArchiveKind::Tar(compression) => {
+            for_each_tar_member(archive, compression, |member, reader| {
+                count(extract_member(member, reader)?);
+                Ok(false)
+            })?;
+        }
+    }
+
+    Ok(summary)
+}
+
+/// 单个条目的解压结果
+enum Outcome {
+    File,
+    Dir,
+    Skipped,
+}
+
+/// 逐级创建根目录内的目录，返回最终目录的完整路径
+fn create_dirs(root_path: &Path, relative: &Path) -> io::Result<PathBuf> {
+    let mut current = PathBuf::new();
+    let mut resolved = root_path.to_path_buf();
+
+    for component in relative.components() {
+        current.push(component);
+        resolved = crate::validate_and_resolve_path(root_path, &current.to_string_lossy())?;
+        match fs::metadata(&resolved) {
+            Ok(metadata) if metadata.is_dir() => {}
+            Ok(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("{} 已存在且不是目录", current.display()),
+                ))
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => match fs::create_dir(&resolved) {
+                Err(e) if e.kind() != io::ErrorKind::AlreadyExists => return Err(e),
+                _ => {}
+            },
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(resolved)
+}
+
+/// 顺序遍历 tar 包中的条目，回调返回 `true` 时停止遍历
+fn for_each_tar_member(
+    archive: &Path,
+    compression: Compression,
+    mut f: impl FnMut(Member, &mut dyn Read) -> io::Result<bool>,
+) -> io::Result<()> {
+    let reader = compression::decoder(BufReader::with_capacity(64 * 1024, File::open(archive)?), compression)?;
+    let mut tar = tar::Archive::new(reader);
+
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.to_string_lossy().into_owned();
+        let Some(path) = sanitize(&path) else {
+            warn!("忽略路径不安全的条目: {}", path);
+            continue;
+        };
+
+        let header = entry.header();
+        let kind = match header.entry_type() {
+            tar::EntryType::Regular | tar::EntryType::Continuous => MemberKind::File,
+            tar::EntryType::Directory => MemberKind::Dir,
+            // GNU 长文件名、PAX 扩展头等由 tar 库处理，不会出现在这里
+            _ => MemberKind::Other,
+        };
+        let member = Member {
+            path,
+            kind,
+            size: header.size().unwrap_or(0),
+            modified: header
+                .mtime()
+                .ok()
+                .map(|t| SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(t)),
+            mode: header.mode().ok(),
+        };
+
+        if f(member, &mut entry)? {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// 读取 zip 条目信息，路径不安全时返回 `None`
+fn zip_member<R: Read>(file: &zip::read::ZipFile<'_, R>) -> Option<Member> {
+    let path = sanitize(&file.name().ok()?)?;
+    let kind = if file.is_dir() {
+        MemberKind::Dir
+    } else if file.is_file() {
+        MemberKind::File
+    } else {
+        MemberKind::Other
+    };
+
+    Some(Member {
+        path,
+        kind,
+        size: file.size(),
+        modified: file
+            .last_modified()
+            .and_then(|t| time::PrimitiveDateTime::try_from(t).ok())
+            .map(|t| t.assume_utc().into()),
+        mode: file.unix_mode(),
+    })
+}
+
+/// 规范化条目路径，包含 `..`、绝对路径等不安全成分时返回 `None`
+fn sanitize(name: &str) -> Option<String> {
+    let mut parts = Vec::new();
+    for component in Path::new(name).components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_str()?),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join("/"))
+    }
+}
+
+fn unsupported() -> io::Error {
+    io::Error::new(io::ErrorKind::Unsupported, "不支持的压缩包格式")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::temp_dir;
+
+    /// 生成包含若干个指定大小文件的 tar 包
+    fn write_tar(path: &Path, files: &[(&str, usize)]) {
+        let mut builder = tar::Builder::new(File::create(path).unwrap());
+        for (name, size) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(*size as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, name, &vec![b'x'; *size][..]).unwrap();
+        }
+        builder.finish().unwrap();
+    }
+
+    #[test]
+    fn sanitize_keeps_relative_paths() {
+        assert_eq!(sanitize("a/b/c.txt").as_deref(), Some("a/b/c.txt"));
+        assert_eq!(sanitize("./a/./b/").as_deref(), Some("a/b"));
+        assert_eq!(sanitize("dir/").as_deref(), Some("dir"));
+    }
+
+    #[test]
+    fn sanitize_rejects_zip_slip() {
+        assert_eq!(sanitize("../evil.txt"), None);
+        assert_eq!(sanitize("a/../../evil.txt"), None);
+        assert_eq!(sanitize("a/b/.."), None);
+        assert_eq!(sanitize("/etc/passwd"), None);
+    }
+
+    #[test]
+    fn sanitize_rejects_empty_paths() {
+        assert_eq!(sanitize(""), None);
+        assert_eq!(sanitize("."), None);
+        assert_eq!(sanitize("./"), None);
+    }
+
+    #[test]
+    fn extract_stops_at_byte_limit() {
+        let dir = temp_dir();
+        let root = dir.path();
+        let archive = root.join("bomb.tar");
+        write_tar(&archive, &[("a.txt", 600), ("b.txt", 600)]);
+
+        let limits = ExtractLimits { max_bytes: 1000, max_entries: 0 };
+        let err = extract(&archive, root, &root.join("out"), false, limits).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::FileTooLarge);
+        assert!(root.join("out/a.txt").is_file());
+        // 超限的文件不会残留
+        assert!(!root.join("out/b.txt").exists());
+    }
+
+    #[test]
+    fn extract_stops_at_entry_limit() {
+        let dir = temp_dir();
+        let root = dir.path();
+        let archive = root.join("many.tar");
+        write_tar(&archive, &[("a", 1), ("b", 1), ("c", 1)]);
+
+        let limits = ExtractLimits { max_bytes: 0, max_entries: 2 };
+        let err = extract(&archive, root, &root.join("out"), false, limits).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::QuotaExceeded);
+        assert!(!root.join("out/c").exists());
+
+        let limits = ExtractLimits { max_bytes: 3, max_entries: 3 };
+        let summary = extract(&archive, root, &root.join("all"), false, limits).unwrap();
+        assert_eq!(summary.files, 3);
+    }
+}
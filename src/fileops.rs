@@ -0,0 +1,212 @@
+use crate::trash;
+use serde::Deserialize;
+use std::{
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+};
+
+/// 目标已存在时的处理策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OverwritePolicy {
+    /// 返回冲突错误
+    #[default]
+    Fail,
+    /// 覆盖已存在的目标（仅限同类型）
+    Overwrite,
+    /// 自动重命名为 `name (1).ext` 形式
+    Rename,
+}
+
+/// 按策略确定最终的目标路径，覆盖模式下会先把已存在的目标移到 `root` 的回收站
+pub fn prepare_destination(
+    root: &Path,
+    source: &Path,
+    destination: &Path,
+    policy: OverwritePolicy,
+    deleted_by: Option<&str>,
+) -> io::Result<PathBuf> {
+    let existing = match fs::symlink_metadata(destination) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(destination.to_path_buf()),
+        Err(e) => return Err(e),
+    };
+
+    match policy {
+        OverwritePolicy::Fail => Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "目标已存在",
+        )),
+        OverwritePolicy::Rename => Ok(unique_path(destination)),
+        OverwritePolicy::Overwrite => {
+            // 不允许用文件覆盖目录，反之亦然
+            if existing.is_dir() != fs::symlink_metadata(source)?.is_dir() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    "目标已存在且类型不同",
+                ));
+            }
+            // 被覆盖的目标放进回收站，误操作时还能恢复
+            trash::move_to_trash(root, destination, deleted_by)?;
+            Ok(destination.to_path_buf())
+        }
+    }
+}
+
+/// 生成不冲突的路径：`name (1).ext`、`name (2).ext`……
+pub fn unique_path(path: &Path) -> PathBuf {
+    let parent = path.parent().unwrap_or_else(|| Path::new(""));
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+
+    (1u32..)
+        .map(|i| parent.join(format!("{} ({}){}", stem, i, extension)))
+        .find(|candidate| fs::symlink_metadata(candidate).is_err())
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+/// 删除文件或整个目录
+pub fn remove_path(path: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(path)?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// 移动文件或目录，跨文件系统时退化为复制后删除
+pub fn move_path(source: &Path, destination: &Path) -> io::Result<()> {
+    match fs::rename(source, destination) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            copy_recursive(source, destination)?;
+            remove_path(source)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// 复制过程的观察者，用于统计进度和决定出错时是否继续
+pub trait CopyObserver {
+    /// 写入了一段数据
+    fn on_bytes(&self, _bytes: u64) {}
+
+    /// 完成了一个文件
+    fn on_file(&self) {}
+
+    /// 复制某个条目失败，返回 `true` 表示记录错误后继续复制其余条目
+    fn on_error(&self, _path: &Path, _error: &io::Error) -> bool {
+        false
+    }
+}
+
+/// 遇到错误立即中止的默认观察者
+struct FailFast;
+
+impl CopyObserver for FailFast {}
+
+/// 复制时的读写缓冲区大小
+const COPY_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// 递归复制文件或目录，保留文件的修改时间
+pub fn copy_recursive(source: &Path, destination: &Path) -> io::Result<()> {
+    copy_tree(source, destination, &FailFast)
+}
+
+/// 递归复制文件或目录，并把进度和错误报告给观察者
+pub fn copy_tree(source: &Path, destination: &Path, observer: &dyn CopyObserver) -> io::Result<()> {
+    match copy_entry(source, destination, observer) {
+        Err(e) if observer.on_error(source, &e) => Ok(()),
+        result => result,
+    }
+}
+
+fn copy_entry(source: &Path, destination: &Path, observer: &dyn CopyObserver) -> io::Result<()> {
+    let metadata = fs::symlink_metadata(source)?;
+
+    if metadata.is_dir() {
+        fs::create_dir(destination)?;
+        for entry in fs::read_dir(source)? {
+            let entry = entry?;
+            copy_tree(&entry.path(), &destination.join(entry.file_name()), observer)?;
+        }
+    } else if metadata.file_type().is_symlink() {
+        copy_symlink(source, destination)?;
+        observer.on_file();
+    } else {
+        copy_file(source, destination, &metadata, observer)?;
+        observer.on_file();
+    }
+
+    Ok(())
+}
+
+/// 分块复制单个文件，保留权限和修改时间
+fn copy_file(
+    source: &Path,
+    destination: &Path,
+    metadata: &fs::Metadata,
+    observer: &dyn CopyObserver,
+) -> io::Result<()> {
+    let mut reader = File::open(source)?;
+    let mut writer = File::options()
+        .write(true)
+        .create_new(true)
+        .open(destination)?;
+
+    let mut buf = vec![0u8; COPY_BUFFER_SIZE];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        writer.write_all(&buf[..n])?;
+        observer.on_bytes(n as u64);
+    }
+
+    writer.set_permissions(metadata.permissions())?;
+    if let Ok(modified) = metadata.modified() {
+        writer.set_modified(modified)?;
+    }
+
+    Ok(())
+}
+
+/// 统计目录树中的文件数和总字节数
+pub fn tree_size(path: &Path) -> io::Result<(u64, u64)> {
+    let metadata = fs::symlink_metadata(path)?;
+    if !metadata.is_dir() {
+        return Ok((1, metadata.len()));
+    }
+
+    let mut files = 0;
+    let mut bytes = 0;
+    for entry in fs::read_dir(path)? {
+        let (f, b) = tree_size(&entry?.path())?;
+        files += f;
+        bytes += b;
+    }
+
+    Ok((files, bytes))
+}
+
+/// 复制符号链接本身，而不是链接指向的内容，避免把根目录外的数据复制进来
+#[cfg(unix)]
+fn copy_symlink(source: &Path, destination: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(source)?, destination)
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(source: &Path, _destination: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("不支持复制符号链接: {}", source.display()),
+    ))
+}
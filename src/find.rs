@@ -0,0 +1,223 @@
+use globset::{GlobBuilder, GlobMatcher};
+use ignore::WalkBuilder;
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+use tracing::debug;
+
+/// 默认最多返回的条目数
+const DEFAULT_MAX_RESULTS: usize = 500;
+
+/// 单次请求最多返回的条目数
+const MAX_RESULTS: usize = 5000;
+
+/// 文件名匹配方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NameMode {
+    /// 文件名包含搜索内容
+    #[default]
+    Substring,
+    /// 文件名匹配 glob，如 `*.conf`
+    Glob,
+    /// 文件名匹配正则表达式
+    Regex,
+}
+
+/// 条目类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryType {
+    File,
+    Dir,
+}
+
+fn default_max_results() -> usize {
+    DEFAULT_MAX_RESULTS
+}
+
+/// 文件名搜索查询参数
+#[derive(Debug, Deserialize)]
+pub struct FindQuery {
+    /// 开始搜索的目录，默认为根目录
+    #[serde(default)]
+    pub path: String,
+    /// 文件名搜索内容，为空时匹配所有条目
+    #[serde(default)]
+    pub q: String,
+    #[serde(default)]
+    pub mode: NameMode,
+    /// 区分大小写
+    #[serde(rename = "caseSensitive", default)]
+    pub case_sensitive: bool,
+    /// 只返回文件或目录
+    #[serde(rename = "type")]
+    pub entry_type: Option<EntryType>,
+    /// 最小文件大小（字节）
+    #[serde(rename = "minSize")]
+    pub min_size: Option<u64>,
+    /// 最大文件大小（字节）
+    #[serde(rename = "maxSize")]
+    pub max_size: Option<u64>,
+    /// 只返回在此时间之后修改的条目（RFC 3339）
+    #[serde(rename = "modifiedAfter")]
+    pub modified_after: Option<String>,
+    /// 只返回在此时间之前修改的条目（RFC 3339）
+    #[serde(rename = "modifiedBefore")]
+    pub modified_before: Option<String>,
+    /// 最多返回的条目数
+    #[serde(rename = "maxResults", default = "default_max_results")]
+    pub max_results: usize,
+}
+
+/// 编译好的文件名匹配规则
+enum NameMatcher {
+    Regex(Regex),
+    Glob(GlobMatcher),
+}
+
+impl NameMatcher {
+    fn is_match(&self, name: &str) -> bool {
+        match self {
+            NameMatcher::Regex(regex) => regex.is_match(name),
+            NameMatcher::Glob(glob) => glob.is_match(name),
+        }
+    }
+}
+
+/// 按名称、类型、大小和修改时间查找条目
+pub struct Finder {
+    name: NameMatcher,
+    entry_type: Option<EntryType>,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+    modified_after: Option<SystemTime>,
+    modified_before: Option<SystemTime>,
+    max_results: usize,
+}
+
+impl Finder {
+    /// 解析查询参数，匹配规则或时间格式无效时返回错误说明
+    pub fn new(query: &FindQuery) -> Result<Self, String> {
+        let name = match query.mode {
+            NameMode::Substring | NameMode::Regex => {
+                let pattern = if query.mode == NameMode::Substring {
+                    regex::escape(&query.q)
+                } else {
+                    query.q.clone()
+                };
+                RegexBuilder::new(&pattern)
+                    .case_insensitive(!query.case_sensitive)
+                    .build()
+                    .map(NameMatcher::Regex)
+                    .map_err(|e| format!("无效的正则表达式: {}", e))?
+            }
+            // 搜索内容为空时匹配所有条目
+            NameMode::Glob => GlobBuilder::new(if query.q.is_empty() { "*" } else { &query.q })
+                .case_insensitive(!query.case_sensitive)
+                .literal_separator(true)
+                .build()
+                .map(|glob| NameMatcher::Glob(glob.compile_matcher()))
+                .map_err(|e| format!("无效的 glob: {}", e))?,
+        };
+
+        let parse_time = |value: &Option<String>| -> Result<Option<SystemTime>, String> {
+            value
+                .as_deref()
+                .map(|v| humantime::parse_rfc3339_weak(v).map_err(|e| format!("无效的时间 {}: {}", v, e)))
+                .transpose()
+        };
+
+        Ok(Finder {
+            name,
+            entry_type: query.entry_type,
+            min_size: query.min_size,
+            max_size: query.max_size,
+            modified_after: parse_time(&query.modified_after)?,
+            modified_before: parse_time(&query.modified_before)?,
+            max_results: query.max_results.clamp(1, MAX_RESULTS),
+        })
+    }
+
+    /// 遍历目录树，返回匹配的条目和是否因为数量达到上限而提前结束
+    ///
+    /// 不跟随符号链接，跳过回收站等内部目录，隐藏文件和被 `.gitignore` 忽略的文件同样会被查找。
+    pub fn run(&self, root_path: &Path, dir: &Path) -> (Vec<(PathBuf, fs::Metadata)>, bool) {
+        let root = root_path.to_path_buf();
+        let walker = WalkBuilder::new(dir)
+            .standard_filters(false)
+            .follow_links(false)
+            .sort_by_file_name(|a, b| a.cmp(b))
+            .filter_entry(move |entry| !crate::is_reserved_path(&root, entry.path()))
+            .build();
+
+        let mut found = Vec::new();
+        for entry in walker {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    debug!("查找时跳过无法读取的条目: {}", e);
+                    continue;
+                }
+            };
+            // 不返回搜索目录本身
+            if entry.depth() == 0 {
+                continue;
+            }
+
+            let name = entry.file_name().to_string_lossy();
+            if !self.name.is_match(&name) {
+                continue;
+            }
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if !self.matches(&metadata) {
+                continue;
+            }
+
+            if found.len() == self.max_results {
+                return (found, true);
+            }
+            found.push((entry.into_path(), metadata));
+        }
+
+        (found, false)
+    }
+
+    /// 检查类型、大小和修改时间，指定了大小范围时只返回文件
+    fn matches(&self, metadata: &fs::Metadata) -> bool {
+        match self.entry_type {
+            Some(EntryType::File) if !metadata.is_file() => return false,
+            Some(EntryType::Dir) if !metadata.is_dir() => return false,
+            _ => {}
+        }
+
+        if self.min_size.is_some() || self.max_size.is_some() {
+            if !metadata.is_file() {
+                return false;
+            }
+            let size = metadata.len();
+            if self.min_size.is_some_and(|min| size < min) || self.max_size.is_some_and(|max| size > max) {
+                return false;
+            }
+        }
+
+        if self.modified_after.is_some() || self.modified_before.is_some() {
+            let Ok(modified) = metadata.modified() else {
+                return false;
+            };
+            if self.modified_after.is_some_and(|after| modified < after)
+                || self.modified_before.is_some_and(|before| modified > before)
+            {
+                return false;
+            }
+        }
+
+        true
+    }
+}
@@ -0,0 +1,167 @@
+use crate::scanner::LineScanner;
+use crate::search::{Matcher, ResultOptions, SearchResult};
+use ignore::{overrides::OverrideBuilder, WalkBuilder};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// 单个文件中的匹配结果
+#[derive(Debug, Serialize)]
+pub struct FileMatches {
+    /// 文件路径（相对于根目录）
+    pub path: String,
+    pub results: Vec<SearchResult>,
+    /// 文件中的匹配行总数
+    pub total: u64,
+}
+
+/// 目录搜索结果
+#[derive(Debug, Default, Serialize)]
+pub struct GrepOutcome {
+    /// 按文件分组的结果，只包含有返回结果的文件
+    pub files: Vec<FileMatches>,
+    /// 已搜索文件中的匹配行总数
+    pub total: u64,
+    /// 已搜索的文件数量（不含跳过的二进制文件）
+    #[serde(rename = "filesSearched")]
+    pub files_searched: u64,
+    /// 是否因为结果数量达到上限而提前结束
+    pub truncated: bool,
+    /// 下一页的 `offset`，没有更多结果时省略
+    #[serde(rename = "nextOffset", skip_serializing_if = "Option::is_none")]
+    pub next_offset: Option<u64>,
+}
+
+/// 在目录树中搜索文件内容
+///
+/// 遵循 `.gitignore`、`.ignore` 规则，跳过隐藏文件、符号链接、二进制文件以及回收站等内部目录。
+pub struct Grep {
+    root_path: PathBuf,
+    walker: WalkBuilder,
+}
+
+impl Grep {
+    /// 创建搜索，`include` / `exclude` 为相对于搜索目录的 glob，无效时返回错误说明
+    pub fn new(root_path: &Path, dir: &Path, include: &[String], exclude: &[String]) -> Result<Self, String> {
+        let mut overrides = OverrideBuilder::new(dir);
+        for glob in include {
+            overrides
+                .add(glob)
+                .map_err(|e| format!("无效的包含规则 {}: {}", glob, e))?;
+        }
+        for glob in exclude {
+            overrides
+                .add(&format!("!{}", glob))
+                .map_err(|e| format!("无效的排除规则 {}: {}", glob, e))?;
+        }
+        let overrides = overrides.build().map_err(|e| e.to_string())?;
+
+        let mut walker = WalkBuilder::new(dir);
+        let root = root_path.to_path_buf();
+        walker
+            .overrides(overrides)
+            // 不在 git 仓库中时同样遵循 .gitignore
+            .require_git(false)
+            .follow_links(false)
+            .filter_entry(move |entry| !crate::is_reserved_path(&root, entry.path()));
+
+        Ok(Grep {
+            root_path: root_path.to_path_buf(),
+            walker,
+        })
+    }
+
+    /// 按路径顺序逐个搜索文件，返回的匹配行总数达到 `max_results` 后停止
+    ///
+    /// 每个文件的结果页码按 `lines_per_page` 计算，与查看文件时一致。
+    pub fn run(
+        &self,
+        matcher: &Matcher,
+        options: ResultOptions,
+        lines_per_page: impl Fn(u64) -> Option<usize>,
+    ) -> GrepOutcome {
+        let options = options.clamped();
+        let mut outcome = GrepOutcome::default();
+        let mut skip = options.offset;
+        let mut remaining = options.max_results;
+
+        let mut walker = self.walker.clone();
+        walker.sort_by_file_path(|a, b| a.cmp(b));
+
+        for entry in walker.build() {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    debug!("搜索时跳过无法读取的条目: {}", e);
+                    continue;
+                }
+            };
+            if !entry.file_type().is_some_and(|t| t.is_file()) {
+                continue;
+            }
+
+            if remaining == 0 {
+                // 结果已满，但后面还有文件没有搜索
+                outcome.truncated = true;
+                break;
+            }
+
+            let file_options = ResultOptions {
+                max_results: remaining,
+                offset: skip,
+                ..options
+            };
+            let found = match search_file(entry.path(), matcher, file_options, &lines_per_page) {
+                Ok(Some(found)) => found,
+                Ok(None) => continue,
+                Err(e) => {
+                    debug!("搜索时跳过无法读取的文件 {}: {}", entry.path().display(), e);
+                    continue;
+                }
+            };
+
+            outcome.files_searched += 1;
+            outcome.total += found.total;
+            skip = skip.saturating_sub(found.total);
+            remaining -= found.results.len();
+            if found.truncated {
+                outcome.truncated = true;
+            }
+
+            if !found.results.is_empty() {
+                let path = pathdiff::diff_paths(entry.path(), &self.root_path)
+                    .unwrap_or_else(|| entry.path().to_path_buf());
+                outcome.files.push(FileMatches {
+                    path: path.to_string_lossy().into_owned(),
+                    results: found.results,
+                    total: found.total,
+                });
+            }
+
+            if outcome.truncated {
+                break;
+            }
+        }
+
+        if outcome.truncated {
+            let returned: usize = outcome.files.iter().map(|f| f.results.len()).sum();
+            outcome.next_offset = Some(options.offset + returned as u64);
+        }
+
+        outcome
+    }
+}
+
+/// 搜索单个文件，二进制文件返回 `None`
+fn search_file(
+    path: &Path,
+    matcher: &Matcher,
+    options: ResultOptions,
+    lines_per_page: impl Fn(u64) -> Option<usize>,
+) -> std::io::Result<Option<crate::search::SearchOutcome>> {
+    let mut scanner = LineScanner::open(path)?;
+    if scanner.is_binary()? {
+        return Ok(None);
+    }
+    crate::search::search_lines(&mut scanner, matcher, options, lines_per_page).map(Some)
+}
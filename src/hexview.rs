@@ -0,0 +1,277 @@
+use regex::bytes::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Write,
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+/// 每行显示的字节数
+const BYTES_PER_ROW: u64 = 16;
+
+/// 默认每次返回的字节数
+const DEFAULT_LENGTH: usize = 4096;
+
+/// 每次最多返回的字节数
+const MAX_LENGTH: usize = 64 * 1024;
+
+/// 默认最多返回的匹配数
+const DEFAULT_MAX_MATCHES: usize = 100;
+
+/// 单次请求最多返回的匹配数
+const MAX_MATCHES: usize = 1000;
+
+/// 字节序列最长的长度
+const MAX_PATTERN_LENGTH: usize = 1024;
+
+/// 搜索时每次读取的数据块大小
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+fn default_length() -> usize {
+    DEFAULT_LENGTH
+}
+
+fn default_max_matches() -> usize {
+    DEFAULT_MAX_MATCHES
+}
+
+/// 十六进制查看查询参数
+#[derive(Debug, Deserialize)]
+pub struct HexQuery {
+    pub path: String,
+    /// 要查看的字节偏移，返回的内容从该偏移所在行的开头开始
+    #[serde(default)]
+    pub offset: u64,
+    /// 返回的字节数
+    #[serde(default = "default_length")]
+    pub length: usize,
+}
+
+/// 一行十六进制内容
+#[derive(Debug, Serialize)]
+pub struct HexRow {
+    pub offset: u64,
+    /// 以空格分隔的十六进制字节
+    pub hex: String,
+    /// 可打印的 ASCII 字符，其他字节显示为 `.`
+    pub ascii: String,
+}
+
+/// 十六进制查看结果
+#[derive(Debug, Serialize)]
+pub struct HexView {
+    pub name: String,
+    pub path: String,
+    /// 文件大小
+    pub size: u64,
+    /// 返回内容的起始偏移
+    pub offset: u64,
+    /// 返回的字节数
+    pub length: usize,
+    pub rows: Vec<HexRow>,
+    /// 上一段内容的起始偏移，已在文件开头时省略
+    #[serde(rename = "prevOffset", skip_serializing_if = "Option::is_none")]
+    pub prev_offset: Option<u64>,
+    /// 下一段内容的起始偏移，已到文件末尾时省略
+    #[serde(rename = "nextOffset", skip_serializing_if = "Option::is_none")]
+    pub next_offset: Option<u64>,
+}
+
+/// 读取 `offset` 所在行开始的 `length` 个字节，不解压，`requested_path` 为请求中的路径
+pub fn view(path: &Path, requested_path: String, offset: u64, length: usize) -> io::Result<HexView> {
+    let mut file = File::open(path)?;
+    let size = file.metadata()?.len();
+    let length = length.clamp(BYTES_PER_ROW as usize, MAX_LENGTH) as u64;
+
+    // 偏移超出文件时显示最后一段
+    let offset = offset.min(size.saturating_sub(1));
+    let start = offset - offset % BYTES_PER_ROW;
+
+    let mut data = Vec::with_capacity(length as usize);
+    file.seek(SeekFrom::Start(start))?;
+    file.take(length).read_to_end(&mut data)?;
+
+    let end = start + data.len() as u64;
+    Ok(HexView {
+        name: path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        path: requested_path,
+        size,
+        offset: start,
+        length: data.len(),
+        rows: rows(start, &data),
+        prev_offset: (start > 0).then(|| start.saturating_sub(length)),
+        next_offset: (end < size).then_some(end),
+    })
+}
+
+/// 把数据按行格式化为十六进制和 ASCII
+fn rows(start: u64, data: &[u8]) -> Vec<HexRow> {
+    data.chunks(BYTES_PER_ROW as usize)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let mut hex = String::with_capacity(chunk.len() * 3);
+            for (j, b) in chunk.iter().enumerate() {
+                if j > 0 {
+                    hex.push(' ');
+                }
+                let _ = write!(hex, "{:02x}", b);
+            }
+            let ascii = chunk
+                .iter()
+                .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+                .collect();
+
+            HexRow {
+                offset: start + i as u64 * BYTES_PER_ROW,
+                hex,
+                ascii,
+            }
+        })
+        .collect()
+}
+
+/// 字节序列的写法
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PatternMode {
+    /// 十六进制，如 `7f 45 4c 46`，`??` 匹配任意字节
+    #[default]
+    Hex,
+    /// 按 UTF-8 编码的文本
+    Text,
+}
+
+/// 字节序列搜索查询参数
+#[derive(Debug, Deserialize)]
+pub struct HexSearchQuery {
+    pub path: String,
+    pub q: String,
+    #[serde(default)]
+    pub mode: PatternMode,
+    /// 从该偏移开始向后搜索
+    #[serde(default)]
+    pub from: u64,
+    /// 最多返回的匹配数
+    #[serde(rename = "maxResults", default = "default_max_matches")]
+    pub max_results: usize,
+}
+
+/// 字节序列搜索结果
+#[derive(Debug, Default, Serialize)]
+pub struct HexSearchOutcome {
+    /// 每处匹配的起始偏移
+    pub matches: Vec<u64>,
+    /// 匹配的字节数
+    pub length: usize,
+    /// 是否因为数量达到上限而提前结束
+    pub truncated: bool,
+    /// 继续搜索时的 `from`，没有更多结果时省略
+    #[serde(rename = "nextOffset", skip_serializing_if = "Option::is_none")]
+    pub next_offset: Option<u64>,
+}
+
+/// 编译好的字节序列
+pub struct BytePattern {
+    regex: Regex,
+    len: usize,
+}
+
+impl BytePattern {
+    /// 解析字节序列，格式无效时返回错误说明
+    pub fn new(q: &str, mode: PatternMode) -> Result<Self, String> {
+        let bytes: Vec<Option<u8>> = match mode {
+            PatternMode::Hex => parse_hex(q)?,
+            PatternMode::Text => q.bytes().map(Some).collect(),
+        };
+        if bytes.is_empty() {
+            return Err("搜索内容不能为空".to_string());
+        }
+        if bytes.len() > MAX_PATTERN_LENGTH {
+            return Err(format!("搜索内容不能超过 {} 个字节", MAX_PATTERN_LENGTH));
+        }
+
+        // 关闭 Unicode 后 `\xNN` 匹配单个字节，`.` 配合 `s` 匹配包括换行在内的任意字节
+        let mut pattern = String::from("(?s-u)");
+        for b in &bytes {
+            match b {
+                Some(b) => {
+                    let _ = write!(pattern, r"\x{:02x}", b);
+                }
+                None => pattern.push('.'),
+            }
+        }
+        let regex = RegexBuilder::new(&pattern)
+            .unicode(false)
+            .build()
+            .map_err(|e| e.to_string())?;
+
+        Ok(BytePattern {
+            regex,
+            len: bytes.len(),
+        })
+    }
+
+    /// 从 `from` 开始向后查找，最多返回 `max_results` 处匹配
+    pub fn find(&self, path: &Path, from: u64, max_results: usize) -> io::Result<HexSearchOutcome> {
+        let max_results = max_results.clamp(1, MAX_MATCHES);
+        let mut outcome = HexSearchOutcome {
+            length: self.len,
+            ..Default::default()
+        };
+
+        let mut file = File::open(path)?;
+        file.seek(SeekFrom::Start(from))?;
+
+        // 保留上一块末尾的 len - 1 个字节，跨越两块的匹配同样能找到
+        let mut buf: Vec<u8> = Vec::with_capacity(CHUNK_SIZE + self.len);
+        let mut buf_start = from;
+        loop {
+            let read = (&mut file).take(CHUNK_SIZE as u64).read_to_end(&mut buf)?;
+            if read == 0 {
+                return Ok(outcome);
+            }
+
+            for m in self.regex.find_iter(&buf) {
+                if outcome.matches.len() == max_results {
+                    outcome.truncated = true;
+                    outcome.next_offset = outcome.matches.last().map(|&offset| offset + 1);
+                    return Ok(outcome);
+                }
+                outcome.matches.push(buf_start + m.start() as u64);
+            }
+
+            let keep = (self.len - 1).min(buf.len());
+            let drop = buf.len() - keep;
+            buf.drain(..drop);
+            buf_start += drop as u64;
+        }
+    }
+}
+
+/// 解析十六进制字节序列，忽略空白和 `0x` 前缀，`??` 表示任意字节
+fn parse_hex(q: &str) -> Result<Vec<Option<u8>>, String> {
+    let digits: String = q
+        .split_whitespace()
+        .map(|token| token.trim_start_matches("0x").trim_start_matches("0X"))
+        .collect();
+    if !digits.len().is_multiple_of(2) {
+        return Err(format!("十六进制内容无效: {}", q));
+    }
+
+    digits
+        .as_bytes()
+        .chunks(2)
+        .map(|pair| match pair {
+            b"??" => Ok(None),
+            _ => std::str::from_utf8(pair)
+                .ok()
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .map(Some)
+                .ok_or_else(|| format!("十六进制内容无效: {}", q)),
+        })
+        .collect()
+}
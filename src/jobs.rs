@@ -0,0 +1,221 @@
+use crate::fileops::{self, CopyObserver};
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    time::{Duration, Instant},
+};
+use tracing::{error, info};
+
+/// 已结束的任务保留多久以供查询
+const FINISHED_JOB_RETENTION: Duration = Duration::from_secs(60 * 60);
+
+/// 每个任务最多记录的错误条数
+const MAX_JOB_ERRORS: usize = 100;
+
+/// 任务状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobState {
+    Running,
+    Completed,
+    Failed,
+}
+
+/// 后台复制任务
+pub struct CopyJob {
+    id: u64,
+    owner: Option<String>,
+    source: String,
+    destination: String,
+    /// 源根目录，用于把错误信息中的路径转换为相对路径
+    source_root: PathBuf,
+    total_files: AtomicU64,
+    total_bytes: AtomicU64,
+    files_copied: AtomicU64,
+    bytes_copied: AtomicU64,
+    progress: Mutex<JobProgress>,
+}
+
+/// 需要加锁更新的任务信息
+struct JobProgress {
+    state: JobState,
+    errors: Vec<String>,
+    error_count: u64,
+    finished_at: Option<Instant>,
+}
+
+/// 任务状态快照
+#[derive(Debug, Serialize)]
+pub struct JobStatus {
+    id: u64,
+    state: JobState,
+    source: String,
+    destination: String,
+    #[serde(rename = "totalFiles")]
+    total_files: u64,
+    #[serde(rename = "totalBytes")]
+    total_bytes: u64,
+    #[serde(rename = "filesCopied")]
+    files_copied: u64,
+    #[serde(rename = "bytesCopied")]
+    bytes_copied: u64,
+    #[serde(rename = "errorCount")]
+    error_count: u64,
+    errors: Vec<String>,
+}
+
+impl CopyJob {
+    /// 任务是否属于指定用户（未启用认证时所有任务都可见）
+    pub fn is_visible_to(&self, username: Option<&str>) -> bool {
+        self.owner.is_none() || self.owner.as_deref() == username
+    }
+
+    /// 生成当前状态快照
+    pub fn status(&self) -> JobStatus {
+        let progress = self.lock();
+        JobStatus {
+            id: self.id,
+            state: progress.state,
+            source: self.source.clone(),
+            destination: self.destination.clone(),
+            total_files: self.total_files.load(Ordering::Relaxed),
+            total_bytes: self.total_bytes.load(Ordering::Relaxed),
+            files_copied: self.files_copied.load(Ordering::Relaxed),
+            bytes_copied: self.bytes_copied.load(Ordering::Relaxed),
+            error_count: progress.error_count,
+            errors: progress.errors.clone(),
+        }
+    }
+
+    fn record_error(&self, message: String) {
+        let mut progress = self.lock();
+        progress.error_count += 1;
+        if progress.errors.len() < MAX_JOB_ERRORS {
+            progress.errors.push(message);
+        }
+    }
+
+    fn finish(&self, state: JobState) {
+        let mut progress = self.lock();
+        progress.state = state;
+        progress.finished_at = Some(Instant::now());
+    }
+
+    fn lock(&self) -> MutexGuard<'_, JobProgress> {
+        self.progress.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl CopyObserver for CopyJob {
+    fn on_bytes(&self, bytes: u64) {
+        self.bytes_copied.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    fn on_file(&self) {
+        self.files_copied.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn on_error(&self, path: &Path, error: &io::Error) -> bool {
+        let relative = pathdiff::diff_paths(path, &self.source_root).unwrap_or_default();
+        self.record_error(format!("{}: {}", relative.display(), error));
+        true
+    }
+}
+
+/// 后台任务列表
+#[derive(Default)]
+pub struct JobStore {
+    jobs: Mutex<HashMap<u64, Arc<CopyJob>>>,
+    next_id: AtomicU64,
+}
+
+impl JobStore {
+    /// 查找任务
+    pub fn get(&self, id: u64) -> Option<Arc<CopyJob>> {
+        self.lock().get(&id).cloned()
+    }
+
+    /// 启动后台复制任务并返回任务 ID
+    ///
+    /// `source_display` 和 `destination_display` 是返回给前端的相对路径。
+    pub fn spawn_copy(
+        &self,
+        owner: Option<String>,
+        source_root: PathBuf,
+        source: PathBuf,
+        destination: PathBuf,
+        source_display: String,
+        destination_display: String,
+    ) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let job = Arc::new(CopyJob {
+            id,
+            owner,
+            source: source_display,
+            destination: destination_display,
+            source_root,
+            total_files: AtomicU64::new(0),
+            total_bytes: AtomicU64::new(0),
+            files_copied: AtomicU64::new(0),
+            bytes_copied: AtomicU64::new(0),
+            progress: Mutex::new(JobProgress {
+                state: JobState::Running,
+                errors: Vec::new(),
+                error_count: 0,
+                finished_at: None,
+            }),
+        });
+
+        {
+            let mut jobs = self.lock();
+            // 清理过期的已结束任务
+            jobs.retain(|_, job| {
+                job.lock()
+                    .finished_at
+                    .is_none_or(|t| t.elapsed() < FINISHED_JOB_RETENTION)
+            });
+            jobs.insert(id, Arc::clone(&job));
+        }
+
+        tokio::task::spawn_blocking(move || run_copy(&job, &source, &destination));
+
+        id
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<u64, Arc<CopyJob>>> {
+        self.jobs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// 执行复制任务
+fn run_copy(job: &CopyJob, source: &Path, destination: &Path) {
+    info!("复制任务 #{} 开始: {} -> {}", job.id, job.source, job.destination);
+
+    // 先统计总量以便计算进度，统计失败不影响复制
+    if let Ok((files, bytes)) = fileops::tree_size(source) {
+        job.total_files.store(files, Ordering::Relaxed);
+        job.total_bytes.store(bytes, Ordering::Relaxed);
+    }
+
+    match fileops::copy_tree(source, destination, job) {
+        Ok(()) if job.lock().error_count == 0 => {
+            info!("复制任务 #{} 完成", job.id);
+            job.finish(JobState::Completed);
+        }
+        Ok(()) => {
+            error!("复制任务 #{} 完成，但有 {} 个错误", job.id, job.lock().error_count);
+            job.finish(JobState::Failed);
+        }
+        Err(e) => {
+            error!("复制任务 #{} 失败: {}", job.id, e);
+            job.record_error(e.to_string());
+            job.finish(JobState::Failed);
+        }
+    }
+}
@@ -0,0 +1,314 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Seek, SeekFrom},
+    path::Path,
+};
+use time::{
+    format_description::{self, OwnedFormatItem},
+    parsing::Parsed,
+    PrimitiveDateTime,
+};
+
+/// 未配置时使用的时间戳格式
+pub const DEFAULT_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// 解析时间戳时每行最多查看的字节数
+const HEAD_BYTES: usize = 256;
+
+/// 二分查找时每次探测最多向后读取的字节数，超出时当作后面没有时间戳
+const MAX_PROBE_BYTES: u64 = 1024 * 1024;
+
+/// 范围缩小到这个大小后改为逐行查找
+const LINEAR_SCAN_BYTES: u64 = 64 * 1024;
+
+/// 请求中的时间可以使用的格式
+const QUERY_FORMATS: [&str; 4] = [
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%dT%H:%M",
+];
+
+/// 行首时间戳的格式（strftime 语法，如 `%Y-%m-%d %H:%M:%S`、`[%d/%b/%Y:%H:%M:%S`）
+///
+/// 只需要匹配行的开头，后面的毫秒、时区等内容会被忽略。没有年份的格式（如 syslog 的 `%b %e %H:%M:%S`）
+/// 按要查找的时间所在的年份解析。
+pub struct TimestampFormat {
+    format: String,
+    item: OwnedFormatItem,
+}
+
+impl TimestampFormat {
+    /// 解析格式，无效时返回错误说明
+    pub fn new(format: &str) -> Result<Self, String> {
+        let item = format_description::parse_strftime_owned(format)
+            .map_err(|e| format!("无效的时间戳格式 {}: {}", format, e))?;
+        Ok(TimestampFormat {
+            format: format.to_string(),
+            item,
+        })
+    }
+
+    /// 解析行首的时间戳，没有年份时使用 `default_year`
+    fn parse(&self, line: &[u8], default_year: i32) -> Option<PrimitiveDateTime> {
+        let line = line.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(line);
+        let mut parsed = Parsed::new();
+        parsed.parse_item(line, &self.item).ok()?;
+        if parsed.year().is_none() {
+            parsed.set_year(default_year)?;
+        }
+        PrimitiveDateTime::try_from(parsed).ok()
+    }
+}
+
+/// 解析请求中的时间，如 `2024-03-01 14:02:00`，不做时区转换，与日志中的时间按字面比较
+pub fn parse_query(value: &str) -> Result<PrimitiveDateTime, String> {
+    let value = value.trim();
+    QUERY_FORMATS
+        .iter()
+        .find_map(|format| {
+            let item = format_description::parse_strftime_borrowed(format).ok()?;
+            let mut parsed = Parsed::new();
+            let rest = parsed.parse_items(value.as_bytes(), &item).ok()?;
+            if !rest.is_empty() {
+                return None;
+            }
+            PrimitiveDateTime::try_from(parsed).ok()
+        })
+        .ok_or_else(|| format!("无效的时间 {}，格式应为 YYYY-MM-DD HH:MM[:SS]", value))
+}
+
+/// 按字节偏移二分查找第一个时间不早于 `target` 的行，返回该行的起始偏移，没有这样的行时返回 `None`
+///
+/// 要求日志按时间顺序写入。没有时间戳的行（如异常堆栈）跟随前面有时间戳的行。
+/// 文件开头没有符合格式的时间戳时返回 `InvalidData` 错误，避免格式配置错误时扫描整个文件。
+/// 只读取每行的开头，超长的行不会占用过多内存。
+pub fn find_line(path: &Path, format: &TimestampFormat, target: PrimitiveDateTime) -> io::Result<Option<u64>> {
+    let mut reader = LineReader::open(path)?;
+    let size = reader.size;
+    let year = target.year();
+
+    reader.seek_line(0)?;
+    if reader.next_timestamp(format, year, Some(MAX_PROBE_BYTES))?.is_none() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("文件开头没有格式为 {} 的时间戳", format.format),
+        ));
+    }
+
+    // 不变式：`lo` 之前的行都早于目标时间
+    let (mut lo, mut hi) = (0u64, size);
+    while hi.saturating_sub(lo) > LINEAR_SCAN_BYTES {
+        let mid = lo + (hi - lo) / 2;
+        reader.seek_line(mid)?;
+        match reader.next_timestamp(format, year, Some(MAX_PROBE_BYTES))? {
+            Some((start, time)) if time < target => lo = start + 1,
+            _ => hi = mid,
+        }
+    }
+
+    reader.seek_line(lo)?;
+    while let Some((start, time)) = reader.next_timestamp(format, year, None)? {
+        if time >= target {
+            return Ok(Some(start));
+        }
+    }
+    Ok(None)
+}
+
+/// 只保留每行开头的按行读取器
+struct LineReader {
+    reader: BufReader<File>,
+    size: u64,
+    /// 下一行的起始偏移
+    position: u64,
+    head: Vec<u8>,
+}
+
+impl LineReader {
+    fn open(path: &Path) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let size = file.metadata()?.len();
+        Ok(LineReader {
+            reader: BufReader::with_capacity(64 * 1024, file),
+            size,
+            position: 0,
+            head: Vec::with_capacity(HEAD_BYTES),
+        })
+    }
+
+    /// 定位到第一个起始偏移不小于 `offset` 的行
+    fn seek_line(&mut self, offset: u64) -> io::Result<()> {
+        if offset == 0 {
+            self.reader.seek(SeekFrom::Start(0))?;
+            self.position = 0;
+            return Ok(());
+        }
+        // 从前一个字节开始，它是换行符时 `offset` 正好是行首
+        self.reader.seek(SeekFrom::Start(offset - 1))?;
+        self.position = offset - 1;
+        self.read_line()?;
+        Ok(())
+    }
+
+    /// 读取一行，只把开头的 `HEAD_BYTES` 个字节保存到 `head`，返回该行的起始偏移，已到末尾时返回 `None`
+    fn read_line(&mut self) -> io::Result<Option<u64>> {
+        let start = self.position;
+        self.head.clear();
+        let mut read_any = false;
+        loop {
+            let buf = self.reader.fill_buf()?;
+            if buf.is_empty() {
+                return Ok(read_any.then_some(start));
+            }
+            read_any = true;
+
+            let (len, done) = match buf.iter().position(|&b| b == b'\n') {
+                Some(i) => (i + 1, true),
+                None => (buf.len(), false),
+            };
+            let wanted = HEAD_BYTES.saturating_sub(self.head.len()).min(len);
+            self.head.extend_from_slice(&buf[..wanted]);
+            self.reader.consume(len);
+            self.position += len as u64;
+            if done {
+                return Ok(Some(start));
+            }
+        }
+    }
+
+    /// 向后查找下一个带有时间戳的行，最多读取 `limit` 个字节
+    fn next_timestamp(
+        &mut self,
+        format: &TimestampFormat,
+        year: i32,
+        limit: Option<u64>,
+    ) -> io::Result<Option<(u64, PrimitiveDateTime)>> {
+        let from = self.position;
+        while let Some(start) = self.read_line()? {
+            if let Some(time) = format.parse(&self.head, year) {
+                return Ok(Some((start, time)));
+            }
+            if limit.is_some_and(|limit| self.position - from > limit) {
+                break;
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::temp_file;
+
+    fn find(path: &Path, format: &str, target: &str) -> io::Result<Option<u64>> {
+        find_line(path, &TimestampFormat::new(format).unwrap(), parse_query(target).unwrap())
+    }
+
+    const LOG: &str = "\
+2024-03-01 10:00:00 INFO start
+2024-03-01 10:05:00 ERROR failed
+    at foo
+    at bar
+2024-03-01 10:05:00 INFO retry
+2024-03-01 11:00:00 INFO done
+";
+
+    /// 第 `n` 行（从 1 开始）的起始偏移
+    fn offset_of_line(content: &str, n: usize) -> u64 {
+        content.split_inclusive('\n').take(n - 1).map(str::len).sum::<usize>() as u64
+    }
+
+    #[test]
+    fn finds_first_line_not_before_target() {
+        let log = temp_file(".log", LOG);
+        assert_eq!(find(log.path(), DEFAULT_FORMAT, "2024-03-01 10:00").unwrap(), Some(0));
+        // 相同时间的多行返回第一行
+        assert_eq!(find(log.path(), DEFAULT_FORMAT, "2024-03-01 10:05:00").unwrap(), Some(offset_of_line(LOG, 2)));
+        // 没有时间戳的行跟随前一行，不会被当作匹配
+        assert_eq!(find(log.path(), DEFAULT_FORMAT, "2024-03-01 10:05:01").unwrap(), Some(offset_of_line(LOG, 6)));
+    }
+
+    #[test]
+    fn target_before_or_after_all_lines() {
+        let log = temp_file(".log", LOG);
+        assert_eq!(find(log.path(), DEFAULT_FORMAT, "2023-12-31T23:59").unwrap(), Some(0));
+        assert_eq!(find(log.path(), DEFAULT_FORMAT, "2024-03-01 11:00:01").unwrap(), None);
+        assert_eq!(find(log.path(), DEFAULT_FORMAT, "2025-01-01 00:00").unwrap(), None);
+    }
+
+    #[test]
+    fn file_without_timestamps_is_invalid() {
+        let log = temp_file(".log", "no timestamps here\nat all\n");
+        let err = find(log.path(), DEFAULT_FORMAT, "2024-03-01 10:00").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // 格式配置错误时同样报错
+        let log2 = temp_file(".log", LOG);
+        let err = find(log2.path(), "[%d/%b/%Y:%H:%M:%S", "2024-03-01 10:00").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let log3 = temp_file(".log", "");
+        assert_eq!(find(log3.path(), DEFAULT_FORMAT, "2024-03-01 10:00").unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn leading_lines_without_timestamps_are_skipped() {
+        let content = format!("header\n{}", LOG);
+        let log = temp_file(".log", &content);
+        assert_eq!(find(log.path(), DEFAULT_FORMAT, "2024-03-01 09:00").unwrap(), Some(offset_of_line(&content, 2)));
+    }
+
+    #[test]
+    fn uses_target_year_for_formats_without_year() {
+        let content = "Mar  1 10:00:00 host a\nMar  1 12:00:00 host b\n";
+        let log = temp_file(".log", content);
+        assert_eq!(find(log.path(), "%b %e %H:%M:%S", "2024-03-01 11:00").unwrap(), Some(offset_of_line(content, 2)));
+    }
+
+    #[test]
+    fn binary_search_matches_linear_scan() {
+        // 足够大，触发二分查找
+        let mut content = String::new();
+        for i in 0..20_000u32 {
+            content.push_str(&format!("2024-03-01 {:02}:{:02}:{:02} line {}\n", i / 3600, i / 60 % 60, i % 60, i));
+            if i % 7 == 0 {
+                content.push_str("    continuation without timestamp\n");
+            }
+        }
+        assert!(content.len() as u64 > LINEAR_SCAN_BYTES * 4);
+        let log = temp_file(".log", &content);
+
+        let starts: Vec<(u64, u32)> = content
+            .split_inclusive('\n')
+            .scan(0u64, |offset, line| {
+                let start = *offset;
+                *offset += line.len() as u64;
+                Some((start, line))
+            })
+            .filter(|(_, line)| line.starts_with("2024"))
+            .map(|(start, line)| (start, line.rsplit(' ').next().unwrap().trim().parse().unwrap()))
+            .collect();
+
+        for second in [0u32, 1, 59, 3600, 9999, 15_000, 19_999] {
+            let target = format!("2024-03-01 {:02}:{:02}:{:02}", second / 3600, second / 60 % 60, second % 60);
+            let expected = starts.iter().find(|(_, i)| *i >= second).map(|(start, _)| *start);
+            assert_eq!(find(log.path(), DEFAULT_FORMAT, &target).unwrap(), expected, "{}", target);
+        }
+        assert_eq!(find(log.path(), DEFAULT_FORMAT, "2024-03-02 00:00").unwrap(), None);
+
+    }
+
+    #[test]
+    fn parses_query_formats() {
+        let expected = parse_query("2024-03-01 14:02:00").unwrap();
+        assert_eq!(parse_query(" 2024-03-01T14:02:00 ").unwrap(), expected);
+        assert_eq!(parse_query("2024-03-01 14:02").unwrap(), expected);
+        assert_eq!(parse_query("2024-03-01T14:02").unwrap(), expected);
+
+        for value in ["", "2024-03-01", "2024-03-01 14:02:00 UTC", "2024-13-01 00:00", "yesterday"] {
+            assert!(parse_query(value).is_err(), "{}", value);
+        }
+    }
+}
@@ -1,14 +1,35 @@
+mod archive;
+mod auth;
+mod compression;
 mod config;
+mod download;
+mod encoding;
+mod extract;
+mod fileops;
+mod find;
+mod grep;
+mod hexview;
+mod jobs;
+mod logtime;
 mod scanner;
+mod search;
+mod security;
+mod tail;
+#[cfg(test)]
+mod testutil;
+mod trash;
+mod upload;
 
 use axum::{
-    extract::{Path as AxumPath, Query, State, Multipart},
+    extract::{DefaultBodyLimit, Path as AxumPath, Query, State, Multipart},
     http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware,
     response::{IntoResponse, Json, Response},
-    routing::get,
+    routing::{delete, get, head, post},
     Router,
 };
-use config::Config;
+use auth::CurrentUser;
+use config::{Config, Permission};
 use path_clean::PathClean;
 use serde::{Deserialize, Serialize};
 use std::{
@@ -16,20 +37,19 @@ use std::{
     io,
     path::{Component, Path},
     sync::Arc,
+    time::Duration,
 };
-use tokio::net::TcpListener;
-use tower_http::cors::CorsLayer;
+use tokio::{io::AsyncWriteExt, net::TcpListener};
 use tower_http::services::ServeDir;
 use tower_http::set_header::SetResponseHeaderLayer;
 use tower::Layer;
-use tracing::{error, info};
-use tracing_subscriber;
+use tracing::{error, info, warn};
 
-/// 每页显示的行数
-const LINES_PER_PAGE: usize = 1000;
+/// 按字节查看时默认读取的字节数
+const DEFAULT_VIEW_LENGTH: usize = 256 * 1024;
 
-/// 大文件阈值（10MB）
-const LARGE_FILE_THRESHOLD: u64 = 10 * 1024 * 1024;
+/// 按字节查看时最多读取的字节数
+const MAX_VIEW_LENGTH: usize = 1024 * 1024;
 
 /// 文件信息
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -42,6 +62,9 @@ struct FileInfo {
     #[serde(rename = "modTime")]
     mod_time: String,
     extension: Option<String>,
+    /// 是否为压缩包中的条目
+    #[serde(rename = "inArchive")]
+    in_archive: bool,
 }
 
 /// 文件查看响应
@@ -58,15 +81,26 @@ struct FileViewResponse {
     total_pages: u32,
     #[serde(rename = "isPartial")]
     is_partial: bool,
-}
-
-/// 搜索结果
-#[derive(Debug, Clone, Serialize)]
-struct SearchResult {
-    #[serde(rename = "lineNumber")]
-    line_number: u64,
-    page: u32,
-    line: String,
+    /// 分页时每页的行数
+    #[serde(rename = "pageSize", skip_serializing_if = "Option::is_none")]
+    page_size: Option<usize>,
+    /// 按行号或时间跳转时定位到的行号，按时间没有找到时省略
+    #[serde(rename = "highlightLine", skip_serializing_if = "Option::is_none")]
+    highlight_line: Option<u64>,
+    /// 显示内容时使用的编码，如 `UTF-8`、`GBK`
+    encoding: String,
+    /// 按字节查看时返回内容的起始偏移
+    #[serde(rename = "byteStart", skip_serializing_if = "Option::is_none")]
+    byte_start: Option<u64>,
+    /// 按字节查看时返回内容的结束偏移（不含），下一段从这里开始
+    #[serde(rename = "byteEnd", skip_serializing_if = "Option::is_none")]
+    byte_end: Option<u64>,
+    /// 按字节查看时的内容大小，压缩文件为解压后的大小
+    #[serde(rename = "contentSize", skip_serializing_if = "Option::is_none")]
+    content_size: Option<u64>,
+    /// 按字节查看时每行是否接续上一行，超长的行会拆成多段
+    #[serde(skip_serializing_if = "Option::is_none")]
+    continued: Option<Vec<bool>>,
 }
 
 /// 保存文件请求
@@ -74,6 +108,8 @@ struct SearchResult {
 struct SaveRequest {
     path: String,
     content: String,
+    /// 写入时使用的编码，默认为 UTF-8
+    encoding: Option<String>,
 }
 
 /// 创建请求
@@ -83,6 +119,90 @@ struct CreateRequest {
     name: String,
 }
 
+/// 移动/复制请求
+#[derive(Debug, Deserialize)]
+struct TransferRequest {
+    source: String,
+    destination: String,
+    /// 目标根目录索引，默认与源根目录相同
+    #[serde(rename = "destRoot")]
+    dest_root: Option<usize>,
+    #[serde(default)]
+    overwrite: fileops::OverwritePolicy,
+}
+
+/// 移动/重命名响应
+#[derive(Debug, Serialize)]
+struct MoveResponse {
+    success: bool,
+    message: String,
+    /// 最终的目标路径（相对于目标根目录）
+    path: String,
+}
+
+/// 复制响应
+#[derive(Debug, Serialize)]
+struct CopyResponse {
+    success: bool,
+    message: String,
+    /// 后台复制任务 ID，通过 /api/jobs/:id 查询进度
+    #[serde(rename = "jobId")]
+    job_id: u64,
+    /// 最终的目标路径（相对于目标根目录）
+    path: String,
+}
+
+/// 解压请求
+#[derive(Debug, Deserialize)]
+struct ExtractRequest {
+    /// 压缩包路径
+    path: String,
+    /// 目标目录，不存在时自动创建
+    destination: String,
+    /// 是否覆盖已存在的文件
+    #[serde(default)]
+    overwrite: bool,
+}
+
+/// 解压响应
+#[derive(Debug, Serialize)]
+struct ExtractResponse {
+    success: bool,
+    message: String,
+    /// 目标目录（相对于根目录）
+    path: String,
+    /// 解压的文件数
+    files: u64,
+    /// 跳过的条目数（已存在的文件、符号链接等）
+    skipped: u64,
+}
+
+/// 回收站操作请求
+#[derive(Debug, Deserialize)]
+struct TrashRequest {
+    /// 回收站条目 ID，清空回收站时可省略
+    id: Option<String>,
+    #[serde(default)]
+    overwrite: fileops::OverwritePolicy,
+}
+
+/// 单个上传文件的处理结果
+#[derive(Debug, Serialize)]
+struct UploadResult {
+    /// 文件路径（相对于根目录），失败时为提交的文件名
+    path: String,
+    success: bool,
+    message: String,
+}
+
+/// 上传响应
+#[derive(Debug, Serialize)]
+struct UploadResponse {
+    success: bool,
+    message: String,
+    files: Vec<UploadResult>,
+}
+
 /// 成功响应
 #[derive(Debug, Serialize)]
 struct SuccessResponse {
@@ -90,11 +210,19 @@ struct SuccessResponse {
     message: String,
 }
 
-/// 搜索查询参数
+/// 错误响应
+#[derive(Debug, Serialize)]
+struct ErrorResponse {
+    success: bool,
+    message: String,
+}
+
+/// 搜索查询参数，搜索内容和匹配方式见 `search::SearchOptions`
 #[derive(Debug, Deserialize)]
 struct SearchQuery {
     path: String,
-    q: String,
+    /// 指定文件编码，默认自动检测
+    encoding: Option<String>,
 }
 
 /// 文件查看查询参数
@@ -103,12 +231,62 @@ struct FileQuery {
     path: String,
     #[serde(default = "default_page")]
     page: u32,
+    /// 指定文件编码，默认自动检测
+    encoding: Option<String>,
+    /// 跳转到指定行（从 1 开始），返回该行所在的页，指定后忽略 `page`
+    line: Option<u64>,
+    /// 跳转到第一个时间不早于该时间的行，如 `2024-03-01 14:02:00`，指定后忽略 `page`
+    time: Option<String>,
+    /// 按字节查看的起始偏移，指定后忽略 `page`、`line` 和 `time`
+    offset: Option<u64>,
+    /// 按字节查看时读取的字节数
+    #[serde(default = "default_view_length")]
+    length: usize,
 }
 
 fn default_page() -> u32 {
     1
 }
 
+/// 每页行数查询参数，用于文件查看和搜索结果的页码
+#[derive(Debug, Deserialize)]
+struct PageSizeQuery {
+    /// 指定每页的行数，不超过 `maxPageSize`；指定后不论文件大小都分页
+    #[serde(rename = "pageSize")]
+    page_size: Option<usize>,
+}
+
+fn default_view_length() -> usize {
+    DEFAULT_VIEW_LENGTH
+}
+
+/// 打包下载查询参数，`path` 可以重复出现
+#[derive(Debug, Deserialize)]
+struct ArchiveQuery {
+    #[serde(default)]
+    path: Vec<String>,
+    #[serde(default)]
+    format: archive::ArchiveFormat,
+}
+
+/// 目录搜索查询参数，`include` 和 `exclude` 可以重复出现
+#[derive(Debug, Deserialize)]
+struct GrepQuery {
+    path: String,
+    #[serde(default)]
+    include: Vec<String>,
+    #[serde(default)]
+    exclude: Vec<String>,
+}
+
+/// 文件名查找结果
+#[derive(Debug, Serialize)]
+struct FindResponse {
+    results: Vec<FileInfo>,
+    /// 是否因为数量达到上限而提前结束
+    truncated: bool,
+}
+
 /// 列表查询参数
 #[derive(Debug, Deserialize)]
 struct ListQuery {
@@ -130,11 +308,25 @@ fn default_root_index() -> usize {
 #[derive(Clone)]
 struct AppState {
     config: Arc<Config>,
+    line_indexes: Arc<scanner::LineIndexCache>,
+    sessions: Arc<auth::SessionStore>,
+    jobs: Arc<jobs::JobStore>,
+    uploads: Arc<upload::UploadStore>,
+    /// 限制同时进行的目录搜索数量
+    search_permits: Arc<tokio::sync::Semaphore>,
 }
 
 /// 主函数
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // 生成密码哈希：filebrowser hash-password <password>
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("hash-password") {
+        let password = args.get(2).ok_or("用法: filebrowser hash-password <password>")?;
+        println!("{}", auth::hash_password(password).map_err(|e| e.to_string())?);
+        return Ok(());
+    }
+
     // 初始化日志
     tracing_subscriber::fmt()
         .with_env_filter(
@@ -166,34 +358,82 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         });
         info!("  [{}] {} -> /static/{}/", i, static_dir.name, static_dir.name);
     }
+    if config.anonymous_access() {
+        warn!("未配置用户且已开启 allowAnonymous，认证已禁用，所有人都可以访问 API");
+    } else if config.users.is_empty() {
+        return Err("未配置用户：请在 config.json 的 users 中添加用户，或设置 allowAnonymous 为 true 允许匿名访问".into());
+    } else {
+        info!("已启用认证，用户数量: {}", config.users.len());
+    }
     info!("端口: {}", config.port);
 
     let port = config.port;
+    let search_concurrency = config.search_concurrency.max(1);
 
     let state = AppState {
         config: Arc::new(config),
+        line_indexes: Arc::new(scanner::LineIndexCache::default()),
+        sessions: Arc::new(auth::SessionStore::default()),
+        jobs: Arc::new(jobs::JobStore::default()),
+        uploads: Arc::new(upload::UploadStore::default()),
+        search_permits: Arc::new(tokio::sync::Semaphore::new(search_concurrency)),
     };
 
-    // 构建路由
-    let mut app = Router::new()
-        // API 路由
+    // 需要登录的 API 路由
+    let api = Router::new()
         .route("/api/list", get(handle_list))
         .route("/api/search", get(handle_search))
+        .route("/api/search/stream", get(handle_search_stream))
+        .route("/api/grep", get(handle_grep))
+        .route("/api/find", get(handle_find))
         .route("/api/view", get(handle_view))
+        .route("/api/tail", get(handle_tail))
+        .route("/api/hexview", get(handle_hexview))
+        .route("/api/hexview/search", get(handle_hexview_search))
         .route("/api/download", get(handle_download))
+        .route("/api/archive", get(handle_archive))
+        .route("/api/extract", post(handle_extract))
         .route("/api/roots", get(handle_roots))
-        .route("/api/save", axum::routing::post(handle_save))
-        .route("/api/delete", axum::routing::get(handle_delete))
-        .route("/api/create", axum::routing::post(handle_create))
-        .route("/api/createDir", axum::routing::post(handle_create_dir))
-        .route("/api/upload", axum::routing::post(handle_upload))
+        .route("/api/save", post(handle_save))
+        .route("/api/delete", delete(handle_delete))
+        .route("/api/create", post(handle_create))
+        .route("/api/createDir", post(handle_create_dir))
+        .route("/api/upload", post(handle_upload).layer(DefaultBodyLimit::disable()))
+        .route("/api/tus", post(upload::handle_create))
+        .route(
+            "/api/tus/:id",
+            head(upload::handle_head)
+                .patch(upload::handle_patch)
+                .delete(upload::handle_terminate),
+        )
+        .route("/api/move", post(handle_move))
+        .route("/api/copy", post(handle_copy))
+        .route("/api/jobs/:id", get(handle_job_status))
+        .route("/api/trash", get(handle_trash_list))
+        .route("/api/trash/restore", post(handle_trash_restore))
+        .route("/api/trash/purge", post(handle_trash_purge))
+        .route_layer(middleware::from_fn_with_state(state.clone(), auth::require_auth))
+        // 认证路由
+        .route("/api/login", post(auth::handle_login))
+        .route("/api/logout", post(auth::handle_logout))
+        .route("/api/session", get(auth::handle_session))
+        .layer(middleware::from_fn_with_state(state.clone(), security::check_origin))
+        .layer(middleware::map_response(json_error_body));
+
+    // 构建路由
+    let mut app = Router::new()
+        .merge(api)
         .route("/view/*path", get(handle_view_redirect))
-        // 首页
+        // 首页和前端脚本、样式，登录前也需要加载
         .route("/", get(handle_index))
-        .layer(CorsLayer::permissive())
+        .route("/app.js", get(handle_app_js))
+        .route("/style.css", get(handle_style_css))
+        .layer(security::cors_layer(&state.config))
+        .layer(middleware::from_fn_with_state(state.clone(), upload::handle_discovery))
         .with_state(state.clone());
 
-    // 为每个静态目录创建服务
+    // 为每个静态目录创建服务，静态目录可能与根目录重叠，同样需要登录
+    let mut static_routes = Router::new();
     for static_dir in &state.config.static_dirs {
         let abs_path = fs::canonicalize(&static_dir.path).unwrap_or_else(|_| {
             eprintln!("警告: 无法解析静态目录路径: {}", static_dir.path);
@@ -211,8 +451,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         );
 
         let serve_dir = ServeDir::new(abs_path);
-        app = app.nest_service(&mount_path, no_cache_layer.layer(serve_dir));
+        static_routes = static_routes.nest_service(&mount_path, no_cache_layer.layer(serve_dir));
     }
+    app = app.merge(static_routes.layer(middleware::from_fn_with_state(state.clone(), auth::require_auth)));
+
+    // 定期清理过期的回收站条目和未完成的上传
+    tokio::spawn(cleanup_periodically(state.config.clone()));
 
     // 启动服务器
     let addr = format!("0.0.0.0:{}", port);
@@ -225,12 +469,25 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
 /// 处理首页
 async fn handle_index() -> Response {
-    let html = include_str!("../static/index.html");
+    embedded_asset("text/html; charset=utf-8", include_str!("../static/index.html"))
+}
+
+/// 前端脚本
+async fn handle_app_js() -> Response {
+    embedded_asset("text/javascript; charset=utf-8", include_str!("../static/app.js"))
+}
 
+/// 前端样式
+async fn handle_style_css() -> Response {
+    embedded_asset("text/css; charset=utf-8", include_str!("../static/style.css"))
+}
+
+/// 返回编译进程序的前端文件，禁用缓存
+fn embedded_asset(content_type: &'static str, body: &'static str) -> Response {
     let mut headers = HeaderMap::new();
     headers.insert(
         header::CONTENT_TYPE,
-        HeaderValue::from_static("text/html; charset=utf-8"),
+        HeaderValue::from_static(content_type),
     );
     headers.insert(
         header::CACHE_CONTROL,
@@ -245,7 +502,60 @@ async fn handle_index() -> Response {
         HeaderValue::from_static("0"),
     );
 
-    (headers, html).into_response()
+    (headers, body).into_response()
+}
+
+/// 为没有响应体的 API 错误补充 JSON 错误信息
+async fn json_error_body(response: Response) -> Response {
+    let status = response.status();
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_string();
+
+    if !(status.is_client_error() || status.is_server_error())
+        || content_type.starts_with("application/json")
+    {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    parts.headers.remove(header::CONTENT_TYPE);
+    parts.headers.remove(header::CONTENT_LENGTH);
+
+    // 提取器拒绝请求时会返回纯文本说明，直接作为错误信息
+    if content_type.starts_with("text/plain") {
+        if let Ok(bytes) = axum::body::to_bytes(body, 4096).await {
+            if !bytes.is_empty() {
+                let body = Json(ErrorResponse {
+                    success: false,
+                    message: String::from_utf8_lossy(&bytes).into_owned(),
+                });
+                return (parts, body).into_response();
+            }
+        }
+    }
+
+    let message = match status {
+        StatusCode::BAD_REQUEST => "请求参数错误",
+        StatusCode::UNAUTHORIZED => "未登录或会话已过期",
+        StatusCode::FORBIDDEN => "没有权限执行该操作",
+        StatusCode::NOT_FOUND => "文件或目录不存在",
+        StatusCode::METHOD_NOT_ALLOWED => "不支持的请求方法",
+        StatusCode::CONFLICT => "目标已存在",
+        StatusCode::PAYLOAD_TOO_LARGE => "请求体过大",
+        StatusCode::RANGE_NOT_SATISFIABLE => "请求的范围无效",
+        _ => status.canonical_reason().unwrap_or("请求失败"),
+    };
+
+    let body = Json(ErrorResponse {
+        success: false,
+        message: message.to_string(),
+    });
+
+    (parts, body).into_response()
 }
 
 /// HTML 响应包装器
@@ -270,26 +580,63 @@ fn get_root_index_from_query(params: &RootQuery) -> usize {
     params.root
 }
 
-/// 获取根目录路径（返回绝对路径）
-fn get_root_path(state: &AppState, root_index: usize) -> std::path::PathBuf {
-    if root_index < state.config.root_dirs.len() {
-        let path = &state.config.root_dirs[root_index].path;
-        // 尝试转换为绝对路径
-        fs::canonicalize(path).unwrap_or_else(|_| std::path::PathBuf::from(path))
-    } else {
-        let path = &state.config.root_dirs[0].path;
-        fs::canonicalize(path).unwrap_or_else(|_| std::path::PathBuf::from(path))
+/// 获取根目录路径（返回绝对路径），同时检查当前用户对该根目录是否拥有该操作权限
+///
+/// 根目录索引基于当前用户可见的根目录列表解析，隐藏的根目录无法通过猜测索引访问，
+/// 索引超出范围时返回 404。所有处理函数在访问文件系统前都必须经过这里，以保证权限检查集中生效。
+fn get_root_path(
+    state: &AppState,
+    user: &CurrentUser,
+    root_index: usize,
+    permission: Permission,
+) -> Result<std::path::PathBuf, StatusCode> {
+    resolve_root(state, user, root_index, permission).map(|(_, path)| path)
+}
+
+/// 与 `get_root_path` 相同，额外返回根目录在 `config.root_dirs` 中的下标
+///
+/// 读取根目录的配置（如分页、时间戳格式）时必须使用这个下标，请求中的 `root` 是可见列表中的下标。
+fn resolve_root(
+    state: &AppState,
+    user: &CurrentUser,
+    root_index: usize,
+    permission: Permission,
+) -> Result<(usize, std::path::PathBuf), StatusCode> {
+    let visible = state.config.visible_roots(user.name());
+    // 索引超出范围时不能退回到其他根目录，否则过期的 `root` 会在错误的根目录中执行删除、移动等操作
+    let (index, permissions) = match visible.get(root_index) {
+        Some(&root) => root,
+        None if visible.is_empty() => return Err(StatusCode::FORBIDDEN),
+        None => return Err(StatusCode::NOT_FOUND),
+    };
+
+    if !permissions.allows(permission) {
+        return Err(StatusCode::FORBIDDEN);
     }
+
+    // 尝试转换为绝对路径
+    let path = &state.config.root_dirs[index].path;
+    Ok((index, fs::canonicalize(path).unwrap_or_else(|_| std::path::PathBuf::from(path))))
 }
 
 /// 处理 /view/ 路径的重定向
 async fn handle_view_redirect(
     State(state): State<AppState>,
+    user: Option<CurrentUser>,
     AxumPath(path): AxumPath<String>,
     Query(params): Query<RootQuery>,
 ) -> Response {
+    // 未登录时无法验证路径，直接跳转到前端页面，由前端登录后再加载文件
+    let user = match user {
+        Some(user) => user,
+        None => return view_redirect_page(&path),
+    };
+
     let root_index = get_root_index_from_query(&params);
-    let root_path = get_root_path(&state, root_index);
+    let root_path = match get_root_path(&state, &user, root_index, Permission::Read) {
+        Ok(root_path) => root_path,
+        Err(status) => return status.into_response(),
+    };
 
     // 解码路径
     let decoded_path = percent_encoding::percent_decode_str(&path)
@@ -299,10 +646,28 @@ async fn handle_view_redirect(
     // 验证路径
     match validate_and_resolve_path(&root_path, decoded_path.as_ref()) {
         Ok(full_path) => {
-            if full_path.is_file() {
-                // 返回带有 JavaScript 重定向的 HTML
-                let html = format!(
-                    r#"<!DOCTYPE html>
+            if full_path.is_file() || is_archive_member(&root_path, decoded_path.as_ref()) {
+                view_redirect_page(&path)
+            } else {
+                (StatusCode::NOT_FOUND, "不是文件").into_response()
+            }
+        }
+        Err(e) => {
+            error!("路径验证失败: {}", e);
+            (StatusCode::NOT_FOUND, "文件不存在").into_response()
+        }
+    }
+}
+
+/// 判断路径是否指向压缩包中的条目
+fn is_archive_member(root_path: &Path, requested_path: &str) -> bool {
+    extract::locate(root_path, requested_path).is_some_and(|(_, inner)| !inner.is_empty())
+}
+
+/// 返回带有 JavaScript 重定向的 HTML
+fn view_redirect_page(path: &str) -> Response {
+    let html = format!(
+        r#"<!DOCTYPE html>
 <html>
 <head>
     <meta charset="UTF-8">
@@ -314,35 +679,35 @@ async fn handle_view_redirect(
     </script>
 </body>
 </html>"#,
-                    urlencoding::encode(&path)
-                );
-                Html(html).into_response()
-            } else {
-                (StatusCode::NOT_FOUND, "不是文件").into_response()
-            }
-        }
-        Err(e) => {
-            error!("路径验证失败: {}", e);
-            (StatusCode::NOT_FOUND, "文件不存在").into_response()
-        }
-    }
+        urlencoding::encode(path)
+    );
+    Html(html).into_response()
 }
 
 /// 处理目录列表请求
 async fn handle_list(
     State(state): State<AppState>,
+    user: CurrentUser,
     Query(params): Query<ListQuery>,
     Query(root_params): Query<RootQuery>,
 ) -> Result<Json<Vec<FileInfo>>, StatusCode> {
     let root_index = get_root_index_from_query(&root_params);
-    let root_path = get_root_path(&state, root_index);
-
-    let path = validate_and_resolve_path(&root_path, &params.path)
-        .map_err(|_| StatusCode::NOT_FOUND)?;
-
-    if !path.is_dir() {
-        return Err(StatusCode::BAD_REQUEST);
-    }
+    let root_path = get_root_path(&state, &user, root_index, Permission::Read)?;
+
+    let path = match validate_and_resolve_path(&root_path, &params.path) {
+        Ok(path) if path.is_dir() => path,
+        resolved => {
+            // 不是目录时按压缩包浏览
+            if let Some((archive, inner)) = extract::locate(&root_path, &params.path) {
+                return list_archive(archive, inner, &params.path).await;
+            }
+            return Err(if resolved.is_ok() {
+                StatusCode::BAD_REQUEST
+            } else {
+                StatusCode::NOT_FOUND
+            });
+        }
+    };
 
     let mut files = Vec::new();
 
@@ -359,59 +724,178 @@ async fn handle_list(
             .unwrap_or("")
             .to_string();
 
-        let relative_path = pathdiff::diff_paths(&file_path, &root_path)
-            .unwrap_or_else(|| file_path.clone());
-        let relative_path_str = relative_path
-            .to_str()
-            .unwrap_or("")
-            .to_string();
+        // 隐藏根目录下的回收站和上传临时目录
+        if path == root_path && (name == trash::TRASH_DIR || name == upload::UPLOAD_DIR) {
+            continue;
+        }
 
-        let is_dir = metadata.as_ref().map(|m| m.is_dir()).unwrap_or(false);
-        let size = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
-        let mod_time = metadata
-            .as_ref()
-            .and_then(|m| m.modified().ok())
-            .map(|t| format!("{}", humantime::format_rfc3339_seconds(t)))
-            .unwrap_or_default();
-
-        let extension = if is_dir {
-            None
-        } else {
-            file_path.extension().and_then(|e| e.to_str()).map(|s| s.to_string())
-        };
+        files.push(file_info(&root_path, &file_path, name, metadata.as_ref()));
+    }
 
-        files.push(FileInfo {
-            name,
-            path: relative_path_str,
-            is_dir,
-            size,
-            mod_time,
-            extension,
-        });
+    Ok(Json(files))
+}
+
+/// 根据文件元数据生成列表条目
+fn file_info(root_path: &Path, file_path: &Path, name: String, metadata: Option<&fs::Metadata>) -> FileInfo {
+    let relative_path = pathdiff::diff_paths(file_path, root_path)
+        .unwrap_or_else(|| file_path.to_path_buf());
+    let relative_path_str = relative_path
+        .to_str()
+        .unwrap_or("")
+        .to_string();
+
+    let is_dir = metadata.map(|m| m.is_dir()).unwrap_or(false);
+    let size = metadata.map(|m| m.len()).unwrap_or(0);
+    let mod_time = metadata
+        .and_then(|m| m.modified().ok())
+        .map(|t| format!("{}", humantime::format_rfc3339_seconds(t)))
+        .unwrap_or_default();
+
+    let extension = if is_dir {
+        None
+    } else {
+        file_path.extension().and_then(|e| e.to_str()).map(|s| s.to_string())
+    };
+
+    FileInfo {
+        name,
+        path: relative_path_str,
+        is_dir,
+        size,
+        mod_time,
+        extension,
+        in_archive: false,
     }
+}
+
+/// 列出压缩包中的目录
+async fn list_archive(
+    archive: std::path::PathBuf,
+    inner: String,
+    requested_path: &str,
+) -> Result<Json<Vec<FileInfo>>, StatusCode> {
+    let members = tokio::task::spawn_blocking(move || extract::list_dir(&archive, &inner))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map_err(|e| archive_error_status(&e))?;
+
+    let base_path = requested_path.trim_matches('/');
+    let files = members
+        .iter()
+        .map(|member| {
+            let name = member.name().to_string();
+            let extension = if member.is_dir() {
+                None
+            } else {
+                Path::new(&name).extension().and_then(|e| e.to_str()).map(|s| s.to_string())
+            };
+
+            FileInfo {
+                path: format!("{}/{}", base_path, name),
+                name,
+                is_dir: member.is_dir(),
+                size: member.size,
+                mod_time: member
+                    .modified
+                    .map(|t| format!("{}", humantime::format_rfc3339_seconds(t)))
+                    .unwrap_or_default(),
+                extension,
+                in_archive: true,
+            }
+        })
+        .collect();
 
     Ok(Json(files))
 }
 
+/// 把读取压缩包时的错误转换为响应状态码
+fn archive_error_status(e: &io::Error) -> StatusCode {
+    match e.kind() {
+        io::ErrorKind::NotFound => StatusCode::NOT_FOUND,
+        io::ErrorKind::InvalidInput => StatusCode::BAD_REQUEST,
+        io::ErrorKind::PermissionDenied => StatusCode::FORBIDDEN,
+        _ => {
+            error!("读取压缩包失败: {}", e);
+            StatusCode::UNPROCESSABLE_ENTITY
+        }
+    }
+}
+
+/// 解析请求中指定的编码，为空时自动检测，不支持时返回错误说明
+fn requested_encoding(label: Option<&str>) -> Result<Option<&'static encoding_rs::Encoding>, String> {
+    label
+        .filter(|label| !label.is_empty())
+        .map(encoding::from_label)
+        .transpose()
+}
+
+/// 带错误说明的 400 响应
+fn bad_request_response(message: String) -> Response {
+    let body = Json(ErrorResponse {
+        success: false,
+        message,
+    });
+    (StatusCode::BAD_REQUEST, body).into_response()
+}
+
 /// 处理文件查看请求
+///
+/// 默认根据文件开头检测编码（BOM、UTF-8 校验、字节分布），也可以用 `encoding` 参数指定。
 async fn handle_view(
     State(state): State<AppState>,
+    user: CurrentUser,
     Query(params): Query<FileQuery>,
+    Query(page_params): Query<PageSizeQuery>,
     Query(root_params): Query<RootQuery>,
-) -> Result<Json<FileViewResponse>, StatusCode> {
-    let root_index = get_root_index_from_query(&root_params);
-    let root_path = get_root_path(&state, root_index);
-
-    let path = validate_and_resolve_path(&root_path, &params.path)
-        .map_err(|_| StatusCode::NOT_FOUND)?;
+) -> Result<Response, StatusCode> {
+    let forced_encoding = match requested_encoding(params.encoding.as_deref()) {
+        Ok(encoding) => encoding,
+        Err(message) => return Ok(bad_request_response(message)),
+    };
+    let target_time = match params.time.as_deref().map(logtime::parse_query).transpose() {
+        Ok(time) => time,
+        Err(message) => return Ok(bad_request_response(message)),
+    };
 
-    if !path.is_file() {
-        return Err(StatusCode::BAD_REQUEST);
-    }
+    let root_index = get_root_index_from_query(&root_params);
+    let (config_index, root_path) = resolve_root(&state, &user, root_index, Permission::Read)?;
+    let paging = state.config.paging(config_index);
+
+    let path = match validate_and_resolve_path(&root_path, &params.path) {
+        Ok(path) if path.is_file() => path,
+        resolved => {
+            // 压缩包中的文件直接从压缩包读取
+            if let Some((archive, inner)) = extract::locate(&root_path, &params.path) {
+                if !inner.is_empty() {
+                    if target_time.is_some() && params.offset.is_none() {
+                        return Ok(bad_request_response("压缩包中的文件不支持按时间跳转".to_string()));
+                    }
+                    let lines_per_page = move |size| paging.lines_per_page(size, page_params.page_size);
+                    return view_archive_member(archive, inner, params, forced_encoding, lines_per_page)
+                        .await
+                        .map(IntoResponse::into_response);
+                }
+            }
+            return Err(if resolved.is_ok() {
+                StatusCode::BAD_REQUEST
+            } else {
+                StatusCode::NOT_FOUND
+            });
+        }
+    };
 
     let metadata = fs::metadata(&path).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     let file_size = metadata.len();
-    let total_lines = scanner::count_lines(&path).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    // 未缓存时需要扫描整个文件建立索引，在阻塞线程中执行避免占用异步运行时
+    let index = {
+        let (line_indexes, path) = (Arc::clone(&state.line_indexes), path.clone());
+        tokio::task::spawn_blocking(move || line_indexes.get(&path))
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    };
+    let total_lines = index.total_lines();
+    let encoding = forced_encoding.unwrap_or(index.encoding());
 
     let file_name = path
         .file_name()
@@ -419,24 +903,80 @@ async fn handle_view(
         .unwrap_or("")
         .to_string();
 
-    let (lines, page, total_pages, is_partial) = if file_size >= LARGE_FILE_THRESHOLD {
-        // 大文件：分页读取
-        let total_pages = ((total_lines as usize + LINES_PER_PAGE - 1) / LINES_PER_PAGE) as u32;
-        let page = params.page.min(total_pages).max(1);
-        let start_line = ((page - 1) * LINES_PER_PAGE as u32) as usize + 1;
+    if let Some(offset) = params.offset {
+        let length = params.length.clamp(1, MAX_VIEW_LENGTH);
+        let window = {
+            let (path, index) = (path.clone(), Arc::clone(&index));
+            tokio::task::spawn_blocking(move || scanner::read_window_indexed(&path, &index, encoding, offset, length))
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        };
+        let response = byte_view_response(file_name, params.path, file_size, total_lines, index.content_size(), window, encoding);
+        return Ok(Json(response).into_response());
+    }
 
-        let lines = scanner::read_lines(&path, start_line, LINES_PER_PAGE)
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    // 按时间跳转时二分查找目标行，按行号跳转时限制在文件范围内
+    let highlight_line = if let Some(target) = target_time {
+        if index.compression() != compression::Compression::None {
+            return Ok(bad_request_response("压缩文件不支持按时间跳转".to_string()));
+        }
+        let format = state.config.root_dirs[config_index]
+            .timestamp_format
+            .as_deref()
+            .unwrap_or(logtime::DEFAULT_FORMAT);
+        let format = match logtime::TimestampFormat::new(format) {
+            Ok(format) => format,
+            Err(message) => return Ok(bad_request_response(message)),
+        };
 
-        (lines, page, total_pages, true)
+        let (path, index) = (path.clone(), Arc::clone(&index));
+        let found = tokio::task::spawn_blocking(move || match logtime::find_line(&path, &format, target)? {
+            Some(offset) => scanner::line_at(&path, &index, offset).map(Some),
+            None => Ok(None),
+        })
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        match found {
+            Ok(line) => line,
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => return Ok(bad_request_response(e.to_string())),
+            Err(e) => {
+                error!("按时间查找失败 {}: {}", params.path, e);
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        }
     } else {
-        // 小文件：一次性读取所有行
-        let lines = scanner::read_lines(&path, 1, total_lines as usize)
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        params.line.map(|line| line.clamp(1, total_lines.max(1)))
+    };
 
-        (lines, 1, 1, false)
+    // 压缩文件按解压后的大小决定是否分页
+    let page_size = paging.lines_per_page(index.content_size(), page_params.page_size);
+    let (page, total_pages, is_partial) = if let Some(page_size) = page_size {
+        // 大文件：分页读取
+        let total_pages = (total_lines as usize).div_ceil(page_size).max(1) as u32;
+        let page = match highlight_line {
+            Some(line) => ((line - 1) / page_size as u64 + 1) as u32,
+            // 没有不早于该时间的行时显示最后一页
+            None if target_time.is_some() => total_pages,
+            None => params.page,
+        };
+        (page.min(total_pages).max(1), total_pages, true)
+    } else {
+        (1, 1, false)
     };
 
+    let lines = tokio::task::spawn_blocking(move || match page_size {
+        Some(page_size) => {
+            let start_line = (page as usize - 1) * page_size + 1;
+            scanner::read_lines_indexed(&path, &index, encoding, start_line, page_size)
+        }
+        // 小文件：一次性读取所有行
+        None => scanner::read_lines(&path, encoding, 1, total_lines as usize),
+    })
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
     Ok(Json(FileViewResponse {
         name: file_name,
         path: params.path,
@@ -446,17 +986,157 @@ async fn handle_view(
         page,
         total_pages,
         is_partial,
+        page_size,
+        highlight_line,
+        encoding: encoding.name().to_string(),
+        byte_start: None,
+        byte_end: None,
+        content_size: None,
+        continued: None,
+    })
+    .into_response())
+}
+
+/// 按字节查看的响应，一次只返回一段内容
+fn byte_view_response(
+    name: String,
+    path: String,
+    size: u64,
+    total_lines: u64,
+    content_size: u64,
+    window: scanner::ByteWindow,
+    encoding: &'static encoding_rs::Encoding,
+) -> FileViewResponse {
+    FileViewResponse {
+        name,
+        path,
+        size,
+        total_lines,
+        lines: window.lines,
+        page: 1,
+        total_pages: 1,
+        is_partial: window.start > 0 || window.end < content_size,
+        page_size: None,
+        highlight_line: None,
+        encoding: encoding.name().to_string(),
+        byte_start: Some(window.start),
+        byte_end: Some(window.end),
+        content_size: Some(content_size),
+        continued: Some(window.continued),
+    }
+}
+
+/// 查看压缩包中的文件，大文件同样按页返回
+async fn view_archive_member(
+    archive: std::path::PathBuf,
+    inner: String,
+    params: FileQuery,
+    forced_encoding: Option<&'static encoding_rs::Encoding>,
+    lines_per_page: impl Fn(u64) -> Option<usize> + Copy + Send + 'static,
+) -> Result<Json<FileViewResponse>, StatusCode> {
+    let name = params.path.rsplit('/').next().unwrap_or("").to_string();
+
+    if let Some(offset) = params.offset {
+        let length = params.length.clamp(1, MAX_VIEW_LENGTH);
+        let (window, total_lines, size, encoding) = tokio::task::spawn_blocking(move || {
+            let (window, size, encoding) = extract::with_member(&archive, &inner, |reader, size| {
+                let mut reader = io::BufReader::with_capacity(64 * 1024, reader);
+                let encoding = match forced_encoding {
+                    Some(encoding) => encoding,
+                    None => encoding::detect(io::BufRead::fill_buf(&mut reader)?),
+                };
+                Ok((scanner::read_window(reader, encoding, offset.min(size), length)?, size, encoding))
+            })?;
+            // 压缩包中的文件无法定位，总行数需要再读一遍
+            let (_, total_lines) = extract::with_member(&archive, &inner, |reader, _| {
+                scanner::read_page(io::BufReader::with_capacity(64 * 1024, reader), encoding, 1, 0)
+            })?;
+            Ok::<_, io::Error>((window, total_lines, size, encoding))
+        })
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map_err(|e| archive_error_status(&e))?;
+
+        return Ok(Json(byte_view_response(name, params.path, size, total_lines, size, window, encoding)));
+    }
+
+    // 按行号跳转时页码要在知道内容大小后才能确定
+    let read_page = move |page: u32, line: Option<u64>| {
+        let archive = archive.clone();
+        let inner = inner.clone();
+        tokio::task::spawn_blocking(move || {
+            extract::with_member(&archive, &inner, |reader, size| {
+                let mut reader = io::BufReader::with_capacity(64 * 1024, reader);
+                let encoding = match forced_encoding {
+                    Some(encoding) => encoding,
+                    None => encoding::detect(io::BufRead::fill_buf(&mut reader)?),
+                };
+                let page_size = lines_per_page(size);
+                let page = match (line, page_size) {
+                    (Some(line), Some(page_size)) => ((line.max(1) - 1) / page_size as u64 + 1) as u32,
+                    _ => page,
+                };
+                let (start_line, count) = match page_size {
+                    Some(page_size) => ((page as usize - 1) * page_size + 1, page_size),
+                    None => (1, usize::MAX),
+                };
+                let (lines, total_lines) = scanner::read_page(reader, encoding, start_line, count)?;
+                Ok((lines, page, total_lines, size, encoding))
+            })
+        })
+    };
+
+    let (mut lines, page, total_lines, size, encoding) = read_page.clone()(params.page.max(1), params.line)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map_err(|e| archive_error_status(&e))?;
+
+    let page_size = lines_per_page(size);
+    let (page, total_pages, is_partial) = if let Some(page_size) = page_size {
+        let total_pages = (total_lines as usize).div_ceil(page_size).max(1) as u32;
+        // 页码超出范围时读取最后一页
+        if page > total_pages {
+            lines = read_page(total_pages, None)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+                .map_err(|e| archive_error_status(&e))?
+                .0;
+        }
+        (page.min(total_pages), total_pages, true)
+    } else {
+        (1, 1, false)
+    };
+    let highlight_line = params.line.map(|line| line.clamp(1, total_lines.max(1)));
+
+    Ok(Json(FileViewResponse {
+        name,
+        path: params.path,
+        size,
+        total_lines,
+        lines,
+        page,
+        total_pages,
+        is_partial,
+        page_size,
+        highlight_line,
+        encoding: encoding.name().to_string(),
+        byte_start: None,
+        byte_end: None,
+        content_size: None,
+        continued: None,
     }))
 }
 
 /// 处理文件下载请求
 async fn handle_download(
     State(state): State<AppState>,
+    user: CurrentUser,
     Query(params): Query<FileQuery>,
     Query(root_params): Query<RootQuery>,
+    headers: HeaderMap,
 ) -> Result<Response, StatusCode> {
     let root_index = get_root_index_from_query(&root_params);
-    let root_path = get_root_path(&state, root_index);
+    let root_path = get_root_path(&state, &user, root_index, Permission::Download)?;
 
     let path = validate_and_resolve_path(&root_path, &params.path)
         .map_err(|_| StatusCode::NOT_FOUND)?;
@@ -465,101 +1145,512 @@ async fn handle_download(
         return Err(StatusCode::BAD_REQUEST);
     }
 
-    // 读取文件内容
-    let file_content = fs::read(&path).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    // 获取文件名
-    let file_name = path
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("download")
-        .to_string();
-
-    // 根据文件扩展名确定 Content-Type
-    let content_type = mime_guess::from_path(&path)
-        .first_or_octet_stream()
-        .to_string();
-
-    // 构建响应
-    let response = Response::builder()
-        .status(StatusCode::OK)
-        .header(header::CONTENT_TYPE, content_type)
-        .header(header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", file_name))
-        .header(header::CONTENT_LENGTH, file_content.len())
-        .body(axum::body::Body::from(file_content))
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let metadata = fs::metadata(&path).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let disposition = download::attachment_disposition(&path);
 
-    Ok(response)
+    // 流式返回文件内容，支持断点续传
+    download::serve_file(path, &metadata, &headers, &disposition).await
 }
 
-/// 处理搜索请求
-async fn handle_search(
+/// 处理打包下载请求，把多个文件或目录打包为 zip 或 tar.gz 流式返回
+async fn handle_archive(
     State(state): State<AppState>,
-    Query(params): Query<SearchQuery>,
+    user: CurrentUser,
+    axum_extra::extract::Query(params): axum_extra::extract::Query<ArchiveQuery>,
     Query(root_params): Query<RootQuery>,
-) -> Result<Json<Vec<SearchResult>>, StatusCode> {
+) -> Result<Response, StatusCode> {
     let root_index = get_root_index_from_query(&root_params);
-    let root_path = get_root_path(&state, root_index);
-
-    let path = validate_and_resolve_path(&root_path, &params.path)
-        .map_err(|_| StatusCode::NOT_FOUND)?;
+    let root_path = get_root_path(&state, &user, root_index, Permission::Download)?;
 
-    if !path.is_file() {
+    if params.path.is_empty() {
         return Err(StatusCode::BAD_REQUEST);
     }
 
-    let metadata = fs::metadata(&path).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let file_size = metadata.len();
-    let total_lines = scanner::count_lines(&path).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    let query = params.q.to_lowercase();
-    let mut results = Vec::new();
+    let mut entries = Vec::new();
+    for requested in &params.path {
+        let path = validate_and_resolve_path(&root_path, requested)
+            .map_err(|_| StatusCode::NOT_FOUND)?;
+        if !path.exists() {
+            return Err(StatusCode::NOT_FOUND);
+        }
+        if is_reserved_path(&root_path, &path) {
+            return Err(StatusCode::BAD_REQUEST);
+        }
 
-    let _total_pages = if file_size >= LARGE_FILE_THRESHOLD {
-        ((total_lines as usize + LINES_PER_PAGE - 1) / LINES_PER_PAGE) as u32
+        // 条目以所选文件或目录自身的名称为顶层，保留其下的相对路径
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "root".to_string());
+        entries.push(archive::ArchiveEntry { path, name });
+    }
+
+    // 单个条目以其名称命名，多个条目以所在目录命名
+    let base_name = if let [entry] = entries.as_slice() {
+        entry.name.clone()
     } else {
-        1
+        entries[0]
+            .path
+            .parent()
+            .filter(|parent| entries.iter().all(|e| e.path.parent() == Some(*parent)))
+            .and_then(|parent| parent.file_name())
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "archive".to_string())
     };
+    let file_name = format!("{}.{}", base_name, params.format.extension());
+    info!("打包下载 {} 个条目: {}", entries.len(), file_name);
 
-    // 使用扫描器逐行读取并搜索
-    let file = fs::File::open(&path).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let mut scanner = scanner::LineScanner::new(file);
-    let mut line_number = 0u64;
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static(params.format.content_type()),
+    );
+    headers.insert(
+        header::CONTENT_DISPOSITION,
+        HeaderValue::from_str(&download::attachment_disposition(Path::new(&file_name)))
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?,
+    );
 
-    while let Some(line) = scanner.read_line().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)? {
-        line_number += 1;
+    let body = archive::stream(root_path, entries, params.format);
+    Ok((headers, body).into_response())
+}
 
-        if line.to_lowercase().contains(&query) {
-            let page = if file_size >= LARGE_FILE_THRESHOLD {
-                ((line_number as usize - 1) / LINES_PER_PAGE) as u32 + 1
-            } else {
-                1
-            };
+/// 处理解压请求
+///
+/// 在后台线程中把压缩包解压到根目录内的目标目录，目标目录不存在时自动创建。
+async fn handle_extract(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Query(root_params): Query<RootQuery>,
+    Json(req): Json<ExtractRequest>,
+) -> Result<Response, StatusCode> {
+    let root_index = get_root_index_from_query(&root_params);
+    get_root_path(&state, &user, root_index, Permission::Read)?;
+    let root_path = get_root_path(&state, &user, root_index, Permission::Create)?;
+    if req.overwrite {
+        get_root_path(&state, &user, root_index, Permission::Write)?;
+    }
+
+    let archive = validate_and_resolve_path(&root_path, &req.path)
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+    if !archive.is_file() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    if extract::archive_kind(&archive).is_none() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let destination = match validate_and_resolve_path(&root_path, &req.destination) {
+        Ok(path) if path.is_dir() => path,
+        Ok(path) if path.exists() => return Err(StatusCode::CONFLICT),
+        _ => {
+            let path = resolve_new_path(&root_path, &req.destination)?;
+            if is_reserved_path(&root_path, &path) {
+                return Err(StatusCode::FORBIDDEN);
+            }
+            fs::create_dir(&path).map_err(|e| {
+                error!("创建目录失败 {}: {}", path.display(), e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+            path
+        }
+    };
+    if is_reserved_path(&root_path, &destination) {
+        return Err(StatusCode::FORBIDDEN);
+    }
 
-            results.push(SearchResult {
-                line_number,
-                page,
-                line: line.to_string(),
+    let limits = extract::ExtractLimits {
+        max_bytes: state.config.max_extract_size,
+        max_entries: state.config.max_extract_entries,
+    };
+    let summary = {
+        let (archive, root_path, destination) = (archive.clone(), root_path.clone(), destination.clone());
+        tokio::task::spawn_blocking(move || {
+            extract::extract(&archive, &root_path, &destination, req.overwrite, limits)
+        })
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    };
+    let summary = match summary {
+        Ok(summary) => summary,
+        // 超过解压上限时返回具体的上限说明
+        Err(e) if matches!(e.kind(), io::ErrorKind::FileTooLarge | io::ErrorKind::QuotaExceeded) => {
+            warn!("解压中止 {}: {}", archive.display(), e);
+            let body = Json(ErrorResponse {
+                success: false,
+                message: e.to_string(),
             });
+            return Ok((StatusCode::PAYLOAD_TOO_LARGE, body).into_response());
+        }
+        Err(e) => {
+            error!("解压失败 {}: {}", archive.display(), e);
+            return Err(match e.kind() {
+                io::ErrorKind::PermissionDenied => StatusCode::FORBIDDEN,
+                io::ErrorKind::AlreadyExists => StatusCode::CONFLICT,
+                io::ErrorKind::InvalidData | io::ErrorKind::Unsupported => StatusCode::UNPROCESSABLE_ENTITY,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            });
+        }
+    };
+
+    let relative_path = pathdiff::diff_paths(&destination, &root_path)
+        .unwrap_or_else(|| destination.clone());
+
+    Ok(Json(ExtractResponse {
+        success: true,
+        message: format!("解压完成，共 {} 个文件，跳过 {} 项", summary.files, summary.skipped),
+        path: relative_path.to_str().unwrap_or("").to_string(),
+        files: summary.files,
+        skipped: summary.skipped,
+    })
+    .into_response())
+}
+
+/// 处理搜索请求
+async fn handle_search(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Query(params): Query<SearchQuery>,
+    Query(options): Query<search::SearchOptions>,
+    Query(result_options): Query<search::ResultOptions>,
+    Query(page_params): Query<PageSizeQuery>,
+    Query(root_params): Query<RootQuery>,
+) -> Result<Response, StatusCode> {
+    let matcher = match search::Matcher::new(&options) {
+        Ok(matcher) => matcher,
+        Err(message) => return Ok(bad_request_response(message)),
+    };
+    let forced_encoding = match requested_encoding(params.encoding.as_deref()) {
+        Ok(encoding) => encoding,
+        Err(message) => return Ok(bad_request_response(message)),
+    };
+
+    let root_index = get_root_index_from_query(&root_params);
+    let (config_index, root_path) = resolve_root(&state, &user, root_index, Permission::Read)?;
+
+    let path = validate_and_resolve_path(&root_path, &params.path)
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+
+    if !path.is_file() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    // 结果的页码与查看文件时使用相同的每页行数
+    let paging = state.config.paging(config_index);
+    let lines_per_page = move |size| paging.lines_per_page(size, page_params.page_size);
+
+    // 使用扫描器逐行读取并搜索，在阻塞线程中执行避免占用异步运行时
+    let outcome = tokio::task::spawn_blocking(move || {
+        let mut scanner = scanner::LineScanner::open(&path)?.with_encoding(forced_encoding);
+        search::search_lines(&mut scanner, &matcher, result_options, lines_per_page)
+    })
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(outcome).into_response())
+}
+
+/// 处理流式搜索请求
+///
+/// 参数与 `/api/search` 相同，通过 Server-Sent Events 边搜索边返回结果和进度，不必等整个大文件扫描完。
+/// 客户端断开连接后停止搜索。
+async fn handle_search_stream(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Query(params): Query<SearchQuery>,
+    Query(options): Query<search::SearchOptions>,
+    Query(result_options): Query<search::ResultOptions>,
+    Query(page_params): Query<PageSizeQuery>,
+    Query(root_params): Query<RootQuery>,
+) -> Result<Response, StatusCode> {
+    let matcher = match search::Matcher::new(&options) {
+        Ok(matcher) => matcher,
+        Err(message) => return Ok(bad_request_response(message)),
+    };
+    let forced_encoding = match requested_encoding(params.encoding.as_deref()) {
+        Ok(encoding) => encoding,
+        Err(message) => return Ok(bad_request_response(message)),
+    };
+
+    let root_index = get_root_index_from_query(&root_params);
+    let (config_index, root_path) = resolve_root(&state, &user, root_index, Permission::Read)?;
+
+    let path = validate_and_resolve_path(&root_path, &params.path)
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+
+    let metadata = fs::metadata(&path).map_err(|_| StatusCode::NOT_FOUND)?;
+    if !metadata.is_file() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let scanner = scanner::LineScanner::open(&path)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .with_encoding(forced_encoding);
+    // 压缩文件解压后的大小要读完才知道
+    let total_bytes = (scanner.compression() == compression::Compression::None).then_some(metadata.len());
+    let paging = state.config.paging(config_index);
+    let lines_per_page = move |size| paging.lines_per_page(size, page_params.page_size);
+
+    // 许可随搜索线程一直持有到扫描结束，限制同时进行的流式搜索数量
+    let permit = Arc::clone(&state.search_permits)
+        .acquire_owned()
+        .await
+        .map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+    Ok(search::stream(scanner, matcher, result_options, total_bytes, lines_per_page, permit).into_response())
+}
+
+/// 处理实时跟踪请求
+///
+/// 先发送文件末尾的若干行，之后通过 Server-Sent Events 推送新增的行。指定 `q` 时只发送匹配的行，
+/// 过滤参数与 `/api/search` 相同。
+async fn handle_tail(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Query(params): Query<tail::TailQuery>,
+    Query(options): Query<search::SearchOptions>,
+    Query(root_params): Query<RootQuery>,
+) -> Result<Response, StatusCode> {
+    let matcher = if options.q.is_empty() {
+        None
+    } else {
+        match search::Matcher::new(&options) {
+            Ok(matcher) => Some(matcher),
+            Err(message) => return Ok(bad_request_response(message)),
         }
+    };
+    let forced_encoding = match requested_encoding(params.encoding.as_deref()) {
+        Ok(encoding) => encoding,
+        Err(message) => return Ok(bad_request_response(message)),
+    };
+
+    let root_index = get_root_index_from_query(&root_params);
+    let root_path = get_root_path(&state, &user, root_index, Permission::Read)?;
+
+    let path = validate_and_resolve_path(&root_path, &params.path)
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+    if !path.is_file() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let compression = compression::detect(&path).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if compression != compression::Compression::None {
+        return Ok(bad_request_response("压缩文件不支持实时跟踪".to_string()));
+    }
+
+    Ok(tail::follow(path, params.lines, matcher, forced_encoding).into_response())
+}
+
+/// 处理十六进制查看请求
+///
+/// 按字节偏移返回固定大小的一段原始内容（压缩文件不解压），用于查看二进制文件。
+async fn handle_hexview(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Query(params): Query<hexview::HexQuery>,
+    Query(root_params): Query<RootQuery>,
+) -> Result<Json<hexview::HexView>, StatusCode> {
+    let root_index = get_root_index_from_query(&root_params);
+    let root_path = get_root_path(&state, &user, root_index, Permission::Read)?;
+
+    let path = validate_and_resolve_path(&root_path, &params.path)
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+    if !path.is_file() {
+        return Err(StatusCode::BAD_REQUEST);
     }
 
-    Ok(Json(results))
+    let view = tokio::task::spawn_blocking(move || {
+        hexview::view(&path, params.path, params.offset, params.length)
+    })
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .map_err(|e| {
+        error!("读取文件失败: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(view))
+}
+
+/// 处理字节序列搜索请求
+///
+/// 从 `from` 开始向后查找十六进制或文本形式的字节序列，返回匹配处的偏移。
+/// 与目录内容搜索共用 `searchConcurrency` 限制。
+async fn handle_hexview_search(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Query(params): Query<hexview::HexSearchQuery>,
+    Query(root_params): Query<RootQuery>,
+) -> Result<Response, StatusCode> {
+    let pattern = match hexview::BytePattern::new(&params.q, params.mode) {
+        Ok(pattern) => pattern,
+        Err(message) => return Ok(bad_request_response(message)),
+    };
+
+    let root_index = get_root_index_from_query(&root_params);
+    let root_path = get_root_path(&state, &user, root_index, Permission::Read)?;
+
+    let path = validate_and_resolve_path(&root_path, &params.path)
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+    if !path.is_file() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let permit = Arc::clone(&state.search_permits)
+        .acquire_owned()
+        .await
+        .map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+    let outcome = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        pattern.find(&path, params.from, params.max_results)
+    })
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .map_err(|e| {
+        error!("搜索文件失败: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(outcome).into_response())
+}
+
+/// 处理目录内容搜索请求
+///
+/// 递归搜索目录下的所有文本文件，结果按文件分组。同时进行的目录搜索数量受 `searchConcurrency` 限制，
+/// 超出时排队等待。
+async fn handle_grep(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    axum_extra::extract::Query(params): axum_extra::extract::Query<GrepQuery>,
+    Query(options): Query<search::SearchOptions>,
+    Query(result_options): Query<search::ResultOptions>,
+    Query(page_params): Query<PageSizeQuery>,
+    Query(root_params): Query<RootQuery>,
+) -> Result<Response, StatusCode> {
+    let matcher = match search::Matcher::new(&options) {
+        Ok(matcher) => matcher,
+        Err(message) => return Ok(bad_request_response(message)),
+    };
+
+    let root_index = get_root_index_from_query(&root_params);
+    let (config_index, root_path) = resolve_root(&state, &user, root_index, Permission::Read)?;
+
+    let dir = validate_and_resolve_path(&root_path, &params.path)
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+    if !dir.is_dir() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let grep = match grep::Grep::new(&root_path, &dir, &params.include, &params.exclude) {
+        Ok(grep) => grep,
+        Err(message) => return Ok(bad_request_response(message)),
+    };
+
+    let permit = Arc::clone(&state.search_permits)
+        .acquire_owned()
+        .await
+        .map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+    let paging = state.config.paging(config_index);
+    let lines_per_page = move |size| paging.lines_per_page(size, page_params.page_size);
+    let outcome = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        grep.run(&matcher, result_options, lines_per_page)
+    })
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(outcome).into_response())
+}
+
+/// 处理文件名查找请求
+///
+/// 递归查找名称、类型、大小和修改时间符合条件的文件和目录，与目录内容搜索共用 `searchConcurrency` 限制。
+async fn handle_find(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Query(params): Query<find::FindQuery>,
+    Query(root_params): Query<RootQuery>,
+) -> Result<Response, StatusCode> {
+    let finder = match find::Finder::new(&params) {
+        Ok(finder) => finder,
+        Err(message) => return Ok(bad_request_response(message)),
+    };
+
+    let root_index = get_root_index_from_query(&root_params);
+    let root_path = get_root_path(&state, &user, root_index, Permission::Read)?;
+
+    let dir = validate_and_resolve_path(&root_path, &params.path)
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+    if !dir.is_dir() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let permit = Arc::clone(&state.search_permits)
+        .acquire_owned()
+        .await
+        .map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+    let walk_root = root_path.clone();
+    let (found, truncated) = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        finder.run(&walk_root, &dir)
+    })
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let results = found
+        .into_iter()
+        .map(|(path, metadata)| {
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            file_info(&root_path, &path, name, Some(&metadata))
+        })
+        .collect();
+
+    Ok(Json(FindResponse { results, truncated }).into_response())
 }
 
 /// 处理根目录列表请求
-async fn handle_roots(State(state): State<AppState>) -> Json<Vec<config::RootDirConfig>> {
-    Json(state.config.root_dirs.clone())
+///
+/// 只返回当前用户可见的根目录，`permissions` 为该用户在根目录上的生效权限，
+/// 返回列表的下标即其他接口中 `root` 参数的取值。
+async fn handle_roots(
+    State(state): State<AppState>,
+    user: CurrentUser,
+) -> Json<Vec<config::RootDirConfig>> {
+    let roots = state
+        .config
+        .visible_roots(user.name())
+        .into_iter()
+        .map(|(index, permissions)| config::RootDirConfig {
+            permissions,
+            paging: state.config.paging(index).into(),
+            ..state.config.root_dirs[index].clone()
+        })
+        .collect();
+
+    Json(roots)
 }
 
 /// 处理保存文件请求
 async fn handle_save(
     State(state): State<AppState>,
+    user: CurrentUser,
     Query(root_params): Query<RootQuery>,
     Json(req): Json<SaveRequest>,
-) -> Result<Json<SuccessResponse>, StatusCode> {
+) -> Result<Response, StatusCode> {
+    // 按查看时使用的编码写回，保持文件原来的编码
+    let content = match requested_encoding(req.encoding.as_deref()) {
+        Ok(None) => req.content.into_bytes(),
+        Ok(Some(encoding)) => match encoding::encode(encoding, &req.content) {
+            Ok(content) => content,
+            Err(message) => return Ok(bad_request_response(message)),
+        },
+        Err(message) => return Ok(bad_request_response(message)),
+    };
+
     let root_index = get_root_index_from_query(&root_params);
-    let root_path = get_root_path(&state, root_index);
+    let root_path = get_root_path(&state, &user, root_index, Permission::Write)?;
 
     let path = validate_and_resolve_path(&root_path, &req.path)
         .map_err(|_| StatusCode::NOT_FOUND)?;
@@ -568,47 +1659,184 @@ async fn handle_save(
     if path.is_dir() {
         return Err(StatusCode::BAD_REQUEST);
     }
+    // 回收站信息和未完成的上传只能通过对应的接口修改
+    if is_reserved_path(&root_path, &path) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    // 保存到不存在的文件相当于新建
+    if !path.exists() {
+        get_root_path(&state, &user, root_index, Permission::Create)?;
+    }
 
     // 写入文件
-    fs::write(&path, &req.content).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    fs::write(&path, &content).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     Ok(Json(SuccessResponse {
         success: true,
         message: "文件保存成功".to_string(),
-    }))
+    })
+    .into_response())
 }
 
-/// 处理删除文件请求
+/// 处理删除请求，文件和目录都会被移动到根目录的回收站
 async fn handle_delete(
     State(state): State<AppState>,
+    user: CurrentUser,
     Query(params): Query<std::collections::HashMap<String, String>>,
     Query(root_params): Query<RootQuery>,
 ) -> Result<Json<SuccessResponse>, StatusCode> {
     let path = params.get("path").ok_or(StatusCode::BAD_REQUEST)?;
 
     let root_index = get_root_index_from_query(&root_params);
-    let root_path = get_root_path(&state, root_index);
+    let root_path = get_root_path(&state, &user, root_index, Permission::Delete)?;
 
     let full_path = validate_and_resolve_path(&root_path, path)
         .map_err(|_| StatusCode::NOT_FOUND)?;
 
-    // 确保不是目录
-    if full_path.is_dir() {
+    if !full_path.exists() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    // 不能删除根目录本身，回收站中的条目通过 /api/trash/purge 删除，未完成的上传通过 tus 接口取消
+    if full_path == root_path || is_reserved_path(&root_path, &full_path) {
         return Err(StatusCode::BAD_REQUEST);
     }
 
-    // 删除文件
-    fs::remove_file(&full_path).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    // 跨文件系统时需要复制整个目录树，在阻塞线程中执行避免占用异步运行时
+    let deleted_by = user.name().map(str::to_string);
+    let trashed_path = full_path.clone();
+    tokio::task::spawn_blocking(move || trash::move_to_trash(&root_path, &trashed_path, deleted_by.as_deref()))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map_err(|e| {
+            error!("移动到回收站失败 {}: {}", full_path.display(), e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
 
     Ok(Json(SuccessResponse {
         success: true,
-        message: "文件删除成功".to_string(),
+        message: "已移至回收站".to_string(),
+    }))
+}
+
+/// 处理回收站列表请求
+async fn handle_trash_list(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Query(root_params): Query<RootQuery>,
+) -> Result<Json<Vec<trash::TrashItem>>, StatusCode> {
+    let root_index = get_root_index_from_query(&root_params);
+    let root_path = get_root_path(&state, &user, root_index, Permission::Read)?;
+
+    let items = trash::list(&root_path).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(items))
+}
+
+/// 处理回收站恢复请求
+async fn handle_trash_restore(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Query(root_params): Query<RootQuery>,
+    Json(req): Json<TrashRequest>,
+) -> Result<Json<MoveResponse>, StatusCode> {
+    let root_index = get_root_index_from_query(&root_params);
+    let root_path = get_root_path(&state, &user, root_index, Permission::Create)?;
+    if req.overwrite == fileops::OverwritePolicy::Overwrite {
+        get_root_path(&state, &user, root_index, Permission::Delete)?;
+    }
+
+    let id = req.id.ok_or(StatusCode::BAD_REQUEST)?;
+    let restored = {
+        let (root_path, restored_by) = (root_path.clone(), user.name().map(str::to_string));
+        tokio::task::spawn_blocking(move || trash::restore(&root_path, &id, req.overwrite, restored_by.as_deref()))
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .map_err(trash_error_status)?
+    };
+
+    let relative_path = pathdiff::diff_paths(&restored, &root_path)
+        .unwrap_or_else(|| restored.clone());
+
+    Ok(Json(MoveResponse {
+        success: true,
+        message: "恢复成功".to_string(),
+        path: relative_path.to_str().unwrap_or("").to_string(),
     }))
 }
 
+/// 处理回收站清理请求，未指定 ID 时清空回收站
+async fn handle_trash_purge(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Query(root_params): Query<RootQuery>,
+    Json(req): Json<TrashRequest>,
+) -> Result<Json<SuccessResponse>, StatusCode> {
+    let root_index = get_root_index_from_query(&root_params);
+    let root_path = get_root_path(&state, &user, root_index, Permission::Delete)?;
+
+    let purged = tokio::task::spawn_blocking(move || trash::purge(&root_path, req.id.as_deref()))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map_err(trash_error_status)?;
+
+    Ok(Json(SuccessResponse {
+        success: true,
+        message: format!("已永久删除 {} 项", purged),
+    }))
+}
+
+/// 把回收站操作的 I/O 错误转换为状态码
+fn trash_error_status(e: io::Error) -> StatusCode {
+    match e.kind() {
+        io::ErrorKind::NotFound => StatusCode::NOT_FOUND,
+        io::ErrorKind::InvalidInput | io::ErrorKind::InvalidData => StatusCode::BAD_REQUEST,
+        io::ErrorKind::AlreadyExists => StatusCode::CONFLICT,
+        io::ErrorKind::PermissionDenied => StatusCode::FORBIDDEN,
+        _ => {
+            error!("回收站操作失败: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+/// 每小时清理一次所有根目录中超过保留期的回收站条目和过期的未完成上传
+async fn cleanup_periodically(config: Arc<Config>) {
+    let retention = Duration::from_secs(config.trash_retention_days * 24 * 60 * 60);
+    let mut interval = tokio::time::interval(Duration::from_secs(60 * 60));
+
+    loop {
+        interval.tick().await;
+
+        let config = config.clone();
+        let _ = tokio::task::spawn_blocking(move || {
+            for root_dir in &config.root_dirs {
+                let root_path = match fs::canonicalize(&root_dir.path) {
+                    Ok(path) => path,
+                    Err(_) => continue,
+                };
+                if !retention.is_zero() {
+                    match trash::purge_expired(&root_path, retention) {
+                        Ok(0) => {}
+                        Ok(n) => info!("已清理根目录 '{}' 回收站中过期的 {} 项", root_dir.name, n),
+                        Err(e) => error!("清理根目录 '{}' 的回收站失败: {}", root_dir.name, e),
+                    }
+                }
+                match upload::purge_expired(&root_path) {
+                    Ok(0) => {}
+                    Ok(n) => info!("已清理根目录 '{}' 中过期的 {} 个未完成上传", root_dir.name, n),
+                    Err(e) => error!("清理根目录 '{}' 的未完成上传失败: {}", root_dir.name, e),
+                }
+            }
+        })
+        .await;
+    }
+}
+
 /// 处理创建文件请求
 async fn handle_create(
     State(state): State<AppState>,
+    user: CurrentUser,
     Query(root_params): Query<RootQuery>,
     Json(req): Json<CreateRequest>,
 ) -> Result<Json<SuccessResponse>, StatusCode> {
@@ -617,7 +1845,7 @@ async fn handle_create(
     }
 
     let root_index = get_root_index_from_query(&root_params);
-    let root_path = get_root_path(&state, root_index);
+    let root_path = get_root_path(&state, &user, root_index, Permission::Create)?;
 
     let dir_path = validate_and_resolve_path(&root_path, &req.path)
         .map_err(|_| StatusCode::NOT_FOUND)?;
@@ -630,7 +1858,9 @@ async fn handle_create(
     let root_path_canonical = fs::canonicalize(&root_path)
         .unwrap_or_else(|_| root_path.clone());
 
-    if !full_path_canonical.starts_with(&root_path_canonical) {
+    if !full_path_canonical.starts_with(&root_path_canonical)
+        || is_reserved_path(&root_path, &full_path.clean())
+    {
         return Err(StatusCode::FORBIDDEN);
     }
 
@@ -651,6 +1881,7 @@ async fn handle_create(
 /// 处理创建目录请求
 async fn handle_create_dir(
     State(state): State<AppState>,
+    user: CurrentUser,
     Query(root_params): Query<RootQuery>,
     Json(req): Json<CreateRequest>,
 ) -> Result<Json<SuccessResponse>, StatusCode> {
@@ -659,7 +1890,7 @@ async fn handle_create_dir(
     }
 
     let root_index = get_root_index_from_query(&root_params);
-    let root_path = get_root_path(&state, root_index);
+    let root_path = get_root_path(&state, &user, root_index, Permission::Create)?;
 
     let dir_path = validate_and_resolve_path(&root_path, &req.path)
         .map_err(|_| StatusCode::NOT_FOUND)?;
@@ -672,7 +1903,9 @@ async fn handle_create_dir(
     let root_path_canonical = fs::canonicalize(&root_path)
         .unwrap_or_else(|_| root_path.clone());
 
-    if !full_path_canonical.starts_with(&root_path_canonical) {
+    if !full_path_canonical.starts_with(&root_path_canonical)
+        || is_reserved_path(&root_path, &full_path.clean())
+    {
         return Err(StatusCode::FORBIDDEN);
     }
 
@@ -691,68 +1924,334 @@ async fn handle_create_dir(
 }
 
 /// 处理文件上传请求
+///
+/// 表单中可以包含多个 `file` 字段，逐个边接收边写入磁盘；`path` 字段指定目标目录，需要放在文件字段之前。
+/// 文件名可以是文件夹上传时的相对路径（`webkitRelativePath`），缺少的中间目录会自动创建。
 async fn handle_upload(
     State(state): State<AppState>,
+    user: CurrentUser,
     Query(root_params): Query<RootQuery>,
     mut multipart: Multipart,
-) -> Result<Json<SuccessResponse>, StatusCode> {
+) -> Result<Json<UploadResponse>, StatusCode> {
     let root_index = get_root_index_from_query(&root_params);
-    let root_path = get_root_path(&state, root_index);
+    let root_path = get_root_path(&state, &user, root_index, Permission::Upload)?;
 
     let mut target_path = String::from("/");
-    let mut file_name = String::new();
-    let mut file_data: Vec<u8> = Vec::new();
+    let mut results = Vec::new();
 
     // 处理 multipart 表单数据
-    while let Some(field) = multipart.next_field().await.map_err(|_| StatusCode::BAD_REQUEST)? {
-        let field_name = field.name().unwrap_or("").to_string();
-
-        if field_name == "path" {
-            // 读取路径数据
-            let data = field.bytes().await.map_err(|_| StatusCode::BAD_REQUEST)?;
-            target_path = String::from_utf8(data.to_vec())
-                .map_err(|_| StatusCode::BAD_REQUEST)?;
-        } else if field_name == "file" {
-            file_name = field.file_name().unwrap_or("").to_string();
-            // 读取文件数据
-            file_data = field.bytes().await.map_err(|_| StatusCode::BAD_REQUEST)?.to_vec();
+    while let Some(mut field) = multipart.next_field().await.map_err(|_| StatusCode::BAD_REQUEST)? {
+        match field.name().unwrap_or("") {
+            "path" => {
+                target_path = field.text().await.map_err(|_| StatusCode::BAD_REQUEST)?;
+            }
+            "file" => {
+                let relative_path = field.file_name().unwrap_or("").to_string();
+                // 未选择文件时浏览器也会提交一个空文件名的字段
+                if relative_path.is_empty() {
+                    continue;
+                }
+
+                let result = receive_upload(
+                    &root_path,
+                    &target_path,
+                    &relative_path,
+                    &mut field,
+                    state.config.max_upload_size,
+                )
+                .await;
+                results.push(match result {
+                    Ok(path) => UploadResult {
+                        path,
+                        success: true,
+                        message: "上传成功".to_string(),
+                    },
+                    Err(message) => {
+                        warn!("上传文件失败: {}: {}", relative_path, message);
+                        UploadResult {
+                            path: relative_path,
+                            success: false,
+                            message,
+                        }
+                    }
+                });
+            }
+            _ => {}
         }
     }
 
-    if file_name.is_empty() {
+    if results.is_empty() {
         return Err(StatusCode::BAD_REQUEST);
     }
 
-    // 构建目标目录的完整路径
-    let dir_path = validate_and_resolve_path(&root_path, &target_path)
+    let failed = results.iter().filter(|r| !r.success).count();
+    let message = if failed == 0 {
+        format!("成功上传 {} 个文件", results.len())
+    } else {
+        format!("成功 {} 个，失败 {} 个", results.len() - failed, failed)
+    };
+
+    Ok(Json(UploadResponse {
+        success: failed == 0,
+        message,
+        files: results,
+    }))
+}
+
+/// 接收单个上传文件，返回保存后的路径（相对于根目录）
+///
+/// 数据先写入根目录下的临时文件，接收完毕后再重命名到目标位置。
+async fn receive_upload(
+    root_path: &Path,
+    target_path: &str,
+    relative_path: &str,
+    field: &mut axum::extract::multipart::Field<'_>,
+    max_size: u64,
+) -> Result<String, String> {
+    let relative = Path::new(relative_path).clean();
+    if !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+        return Err("非法的文件路径".to_string());
+    }
+
+    // 逐级创建相对路径中的目录，每一级都经过根目录校验
+    let mut dir = std::path::PathBuf::from(target_path);
+    for component in relative.parent().into_iter().flat_map(Path::components) {
+        dir.push(component);
+        let dir_str = dir.to_str().ok_or("非法的文件路径")?;
+        let full_dir = validate_and_resolve_path(root_path, dir_str)
+            .map_err(|_| "访问被拒绝：路径超出根目录".to_string())?;
+        if is_reserved_path(root_path, &full_dir) {
+            return Err("不能上传到该目录".to_string());
+        }
+        match fs::metadata(&full_dir) {
+            Ok(metadata) if metadata.is_dir() => {}
+            Ok(_) => return Err(format!("{} 已存在且不是目录", dir.display())),
+            Err(_) => {
+                fs::create_dir(&full_dir).map_err(|e| format!("创建目录失败: {}", e))?;
+                validate_and_resolve_path(root_path, dir_str)
+                    .map_err(|_| "访问被拒绝：路径超出根目录".to_string())?;
+            }
+        }
+    }
+
+    let name = relative.file_name().ok_or("非法的文件路径")?;
+    let requested = dir.join(name);
+    let full_path = resolve_new_path(root_path, requested.to_str().ok_or("非法的文件路径")?)
+        .map_err(|_| "目标目录不存在".to_string())?;
+    if is_reserved_path(root_path, &full_path) {
+        return Err("不能上传到该目录".to_string());
+    }
+    if fs::symlink_metadata(&full_path).is_ok() {
+        return Err("文件已存在".to_string());
+    }
+
+    let temp_path = upload::temp_path(root_path).map_err(|e| format!("创建临时文件失败: {}", e))?;
+    let written = write_field(field, &temp_path, max_size).await;
+    let result = written.and_then(|()| {
+        // 接收期间可能有同名文件被创建
+        if fs::symlink_metadata(&full_path).is_ok() {
+            return Err("文件已存在".to_string());
+        }
+        fileops::move_path(&temp_path, &full_path).map_err(|e| format!("保存文件失败: {}", e))
+    });
+    if let Err(message) = result {
+        let _ = fs::remove_file(&temp_path);
+        return Err(message);
+    }
+
+    let relative_path = pathdiff::diff_paths(&full_path, root_path).unwrap_or(full_path);
+    Ok(relative_path.to_string_lossy().into_owned())
+}
+
+/// 判断路径是否位于回收站或上传临时目录等内部目录中
+fn is_reserved_path(root_path: &Path, path: &Path) -> bool {
+    trash::is_in_trash(root_path, path) || upload::is_in_uploads(root_path, path)
+}
+
+/// 把表单字段的数据分块写入文件
+async fn write_field(
+    field: &mut axum::extract::multipart::Field<'_>,
+    path: &Path,
+    max_size: u64,
+) -> Result<(), String> {
+    let mut file = tokio::fs::File::create(path)
+        .await
+        .map_err(|e| format!("创建临时文件失败: {}", e))?;
+
+    let mut size = 0u64;
+    while let Some(chunk) = field.chunk().await.map_err(|_| "上传中断".to_string())? {
+        size += chunk.len() as u64;
+        if max_size > 0 && size > max_size {
+            return Err("文件过大".to_string());
+        }
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| format!("写入文件失败: {}", e))?;
+    }
+
+    file.flush().await.map_err(|e| format!("写入文件失败: {}", e))
+}
+
+/// 处理移动/重命名请求
+async fn handle_move(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Query(root_params): Query<RootQuery>,
+    Json(req): Json<TransferRequest>,
+) -> Result<Json<MoveResponse>, StatusCode> {
+    let root_index = get_root_index_from_query(&root_params);
+    let dest_index = req.dest_root.unwrap_or(root_index);
+
+    // 源文件从原位置消失，目标位置新增文件
+    let src_root = get_root_path(&state, &user, root_index, Permission::Delete)?;
+    let dest_root = get_root_path(&state, &user, dest_index, Permission::Create)?;
+    if req.overwrite == fileops::OverwritePolicy::Overwrite {
+        get_root_path(&state, &user, dest_index, Permission::Delete)?;
+    }
+    // 移动到其他根目录与复制一样会把内容带出源根目录，需要读取和下载权限
+    if dest_root != src_root {
+        get_root_path(&state, &user, root_index, Permission::Read)?;
+        get_root_path(&state, &user, root_index, Permission::Download)?;
+    }
+
+    let source = validate_and_resolve_path(&src_root, &req.source)
         .map_err(|_| StatusCode::NOT_FOUND)?;
+    if !source.exists() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    if source == src_root {
+        return Err(StatusCode::BAD_REQUEST);
+    }
 
-    let full_path = dir_path.join(&file_name);
+    let destination = resolve_new_path(&dest_root, &req.destination)?;
+    if is_reserved_path(&src_root, &source) || is_reserved_path(&dest_root, &destination) {
+        return Err(StatusCode::FORBIDDEN);
+    }
 
-    // 再次检查路径是否在根目录内
-    let full_path_canonical = fs::canonicalize(&full_path)
-        .unwrap_or_else(|_| full_path.clone());
-    let root_path_canonical = fs::canonicalize(&root_path)
-        .unwrap_or_else(|_| root_path.clone());
+    // 不能把目录移动到它自身或其子目录中，也不能覆盖源路径的上级目录（覆盖时会先移走目标，源也随之消失）
+    if destination.starts_with(&source) || source.starts_with(&destination) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let destination = fileops::prepare_destination(&dest_root, &source, &destination, req.overwrite, user.name())
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::AlreadyExists => StatusCode::CONFLICT,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        })?;
+
+    fileops::move_path(&source, &destination).map_err(|e| {
+        error!("移动失败 {} -> {}: {}", source.display(), destination.display(), e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let relative_path = pathdiff::diff_paths(&destination, &dest_root)
+        .unwrap_or_else(|| destination.clone());
+
+    Ok(Json(MoveResponse {
+        success: true,
+        message: "移动成功".to_string(),
+        path: relative_path.to_str().unwrap_or("").to_string(),
+    }))
+}
+
+/// 处理复制请求，复制在后台任务中执行
+async fn handle_copy(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Query(root_params): Query<RootQuery>,
+    Json(req): Json<TransferRequest>,
+) -> Result<Json<CopyResponse>, StatusCode> {
+    let root_index = get_root_index_from_query(&root_params);
+    let dest_index = req.dest_root.unwrap_or(root_index);
+
+    // 复制到其他根目录相当于把内容下载出去，源根目录需要下载权限
+    get_root_path(&state, &user, root_index, Permission::Read)?;
+    let src_root = get_root_path(&state, &user, root_index, Permission::Download)?;
+    let dest_root = get_root_path(&state, &user, dest_index, Permission::Create)?;
+    if req.overwrite == fileops::OverwritePolicy::Overwrite {
+        get_root_path(&state, &user, dest_index, Permission::Delete)?;
+    }
 
-    if !full_path_canonical.starts_with(&root_path_canonical) {
+    let source = validate_and_resolve_path(&src_root, &req.source)
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+    if !source.exists() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let destination = resolve_new_path(&dest_root, &req.destination)?;
+    if is_reserved_path(&src_root, &source) || is_reserved_path(&dest_root, &destination) {
         return Err(StatusCode::FORBIDDEN);
     }
 
-    // 检查文件是否已存在
-    if full_path.exists() {
-        return Err(StatusCode::CONFLICT);
+    // 不能把目录复制到它自身或其子目录中，也不能用文件覆盖自身或源路径的上级目录
+    if (source.is_dir() && destination.starts_with(&source))
+        || (source.starts_with(&destination) && req.overwrite == fileops::OverwritePolicy::Overwrite)
+    {
+        return Err(StatusCode::BAD_REQUEST);
     }
 
-    // 写入文件
-    fs::write(&full_path, file_data).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let destination = fileops::prepare_destination(&dest_root, &source, &destination, req.overwrite, user.name())
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::AlreadyExists => StatusCode::CONFLICT,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        })?;
 
-    Ok(Json(SuccessResponse {
+    let relative_path = pathdiff::diff_paths(&destination, &dest_root)
+        .unwrap_or_else(|| destination.clone())
+        .to_str()
+        .unwrap_or("")
+        .to_string();
+
+    let job_id = state.jobs.spawn_copy(
+        user.0.clone(),
+        src_root,
+        source,
+        destination,
+        req.source,
+        relative_path.clone(),
+    );
+
+    Ok(Json(CopyResponse {
         success: true,
-        message: "文件上传成功".to_string(),
+        message: "复制任务已创建".to_string(),
+        job_id,
+        path: relative_path,
     }))
 }
 
+/// 处理后台任务状态查询
+async fn handle_job_status(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<u64>,
+) -> Result<Json<jobs::JobStatus>, StatusCode> {
+    let job = state
+        .jobs
+        .get(id)
+        .filter(|job| job.is_visible_to(user.name()))
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(job.status()))
+}
+
+/// 解析一个尚不存在的目标路径：父目录必须存在并位于根目录内
+fn resolve_new_path(root_path: &Path, requested_path: &str) -> Result<std::path::PathBuf, StatusCode> {
+    let clean_path = Path::new(requested_path).clean();
+    let name = clean_path.file_name().ok_or(StatusCode::BAD_REQUEST)?;
+    let parent = clean_path
+        .parent()
+        .and_then(|p| p.to_str())
+        .unwrap_or("/");
+
+    let parent_path = validate_and_resolve_path(root_path, parent)
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+    if !parent_path.is_dir() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(parent_path.join(name))
+}
+
 /// 验证并解析路径，防止目录遍历攻击
 fn validate_and_resolve_path(
     root_path: &Path,
@@ -772,7 +2271,7 @@ fn validate_and_resolve_path(
 
     // 构建完整路径
     // 注意：如果 clean_path 是绝对路径，join() 会直接返回它，所以需要先去掉前导斜杠
-    let full_path = if requested_path == "/" || requested_path == "" || requested_path == "." {
+    let full_path = if requested_path == "/" || requested_path.is_empty() || requested_path == "." {
         root_path.to_path_buf()
     } else {
         // 去掉 clean_path 的前导斜杠（如果有），确保正确拼接
@@ -789,7 +2288,7 @@ fn validate_and_resolve_path(
 
     // 确保路径在根目录内
     // 使用 pathdiff 来检查相对路径，而不是字符串比较
-    let rel_path = pathdiff::diff_paths(&normalized, &root_path)
+    let rel_path = pathdiff::diff_paths(&normalized, root_path)
         .ok_or_else(|| io::Error::new(
             io::ErrorKind::PermissionDenied,
             "无法计算相对路径",
@@ -805,3 +2304,62 @@ fn validate_and_resolve_path(
 
     Ok(normalized)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::temp_dir;
+
+    /// 两个根目录：第一个不允许下载，第二个允许全部操作
+    fn state(locked: &Path, open: &Path) -> AppState {
+        let config: Config = serde_json::from_value(serde_json::json!({
+            "rootDirs": [
+                { "name": "locked", "path": locked, "permissions": { "download": false } },
+                { "name": "open", "path": open }
+            ],
+            "allowAnonymous": true
+        }))
+        .unwrap();
+
+        AppState {
+            config: Arc::new(config),
+            line_indexes: Arc::new(scanner::LineIndexCache::default()),
+            sessions: Arc::new(auth::SessionStore::default()),
+            jobs: Arc::new(jobs::JobStore::default()),
+            uploads: Arc::new(upload::UploadStore::default()),
+            search_permits: Arc::new(tokio::sync::Semaphore::new(1)),
+        }
+    }
+
+    async fn move_file(state: &AppState, root: usize, source: &str, destination: &str, dest_root: usize) -> Result<(), StatusCode> {
+        let req = TransferRequest {
+            source: source.to_string(),
+            destination: destination.to_string(),
+            dest_root: Some(dest_root),
+            overwrite: fileops::OverwritePolicy::Fail,
+        };
+        handle_move(State(state.clone()), CurrentUser(None), Query(RootQuery { root }), Json(req))
+            .await
+            .map(|_| ())
+    }
+
+    #[tokio::test]
+    async fn move_across_roots_requires_download_on_source() {
+        let (locked, open) = (temp_dir(), temp_dir());
+        fs::write(locked.path().join("secret.txt"), "secret").unwrap();
+        fs::write(open.path().join("public.txt"), "public").unwrap();
+        let state = state(locked.path(), open.path());
+
+        assert_eq!(move_file(&state, 0, "secret.txt", "secret.txt", 1).await, Err(StatusCode::FORBIDDEN));
+        assert!(locked.path().join("secret.txt").is_file());
+        assert!(!open.path().join("secret.txt").exists());
+
+        // 同一根目录内移动不会把内容带出去
+        move_file(&state, 0, "secret.txt", "renamed.txt", 0).await.unwrap();
+        assert!(locked.path().join("renamed.txt").is_file());
+
+        // 目标根目录不需要下载权限
+        move_file(&state, 1, "public.txt", "public.txt", 0).await.unwrap();
+        assert!(locked.path().join("public.txt").is_file());
+    }
+}
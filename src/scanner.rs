@@ -1,82 +1,650 @@
-use std::fs::File;
-use std::io::{self, BufRead, BufReader};
-use std::path::Path;
+use crate::compression::{self, Compression};
+use crate::encoding;
+use encoding_rs::Encoding;
+use std::collections::HashMap;
+use std::fs::{self, File, Metadata};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::SystemTime;
 
 /// 优化的行扫描器，用于高效读取大文件
+///
+/// gzip 和 zstd 压缩的文件会边读边解压，按解压后的内容逐行返回。
+/// 按检测到的编码转换为 UTF-8，无效的字节按替换字符显示。
 pub struct LineScanner {
-    reader: BufReader<File>,
+    reader: Box<dyn BufRead + Send>,
+    compression: Compression,
+    encoding: &'static Encoding,
+    buf: Vec<u8>,
     line: String,
+    bytes_read: u64,
 }
 
 impl LineScanner {
-    /// 创建新的行扫描器
-    pub fn new(file: File) -> Self {
-        // 使用 64KB 缓冲区
-        let reader = BufReader::with_capacity(64 * 1024, file);
-        LineScanner {
+    /// 打开文件并创建行扫描器，根据扩展名或文件开头的魔数判断是否需要解压，根据开头的内容判断编码
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let (mut reader, compression) = compression::open(path.as_ref())?;
+        let encoding = encoding::detect(reader.fill_buf()?);
+        Ok(LineScanner {
             reader,
+            compression,
+            encoding,
+            buf: Vec::new(),
             line: String::new(),
+            bytes_read: 0,
+        })
+    }
+
+    /// 指定了编码时用它代替检测结果
+    pub fn with_encoding(mut self, encoding: Option<&'static Encoding>) -> Self {
+        if let Some(encoding) = encoding {
+            self.encoding = encoding;
         }
+        self
+    }
+
+    /// 文件的压缩格式
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    /// 根据开头的内容判断是否为二进制文件（包含 NUL 字节），不消耗数据
+    pub fn is_binary(&mut self) -> io::Result<bool> {
+        Ok(self.reader.fill_buf()?.contains(&0))
+    }
+
+    /// 已读取的字节数，压缩文件为解压后的字节数
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
     }
 
     /// 读取下一行
     pub fn read_line(&mut self) -> io::Result<Option<&str>> {
-        self.line.clear();
-        let bytes_read = self.reader.read_line(&mut self.line)?;
+        let first = self.bytes_read == 0;
+        let bytes_read = read_raw_line(&mut self.reader, &mut self.buf)?;
+        self.bytes_read += bytes_read as u64;
 
         if bytes_read == 0 {
             Ok(None)
         } else {
-            // 移除换行符
-            if self.line.ends_with('\n') {
-                self.line.pop();
-                if self.line.ends_with('\r') {
-                    self.line.pop();
-                }
-            }
+            encoding::decode_line(self.encoding, &self.buf, first, &mut self.line);
             Ok(Some(&self.line))
         }
     }
 }
 
-/// 快速计算文件行数
-pub fn count_lines<P: AsRef<Path>>(path: P) -> io::Result<u64> {
-    let file = File::open(path)?;
-    let reader = BufReader::with_capacity(64 * 1024, file);
+/// 读取一行原始字节到 `buf` 并移除换行符，返回读取的字节数，0 表示已到末尾
+fn read_raw_line<R: BufRead + ?Sized>(reader: &mut R, buf: &mut Vec<u8>) -> io::Result<usize> {
+    buf.clear();
+    let bytes_read = reader.read_until(b'\n', buf)?;
+    if buf.ends_with(b"\n") {
+        buf.pop();
+        if buf.ends_with(b"\r") {
+            buf.pop();
+        }
+    }
+    Ok(bytes_read)
+}
+
+/// 从读取器中按编码读取最多 `count` 行，`first_line` 为读取器当前所在的行号
+fn collect_lines<R: BufRead + ?Sized>(
+    reader: &mut R,
+    encoding: &'static Encoding,
+    first_line: usize,
+    count: usize,
+) -> io::Result<Vec<String>> {
+    let mut lines = Vec::with_capacity(count.min(64 * 1024));
+    let mut buf = Vec::new();
+    let mut line_number = first_line;
 
-    let mut count = 0u64;
-    for line in reader.lines() {
-        let _ = line?;
-        count += 1;
+    while lines.len() < count && read_raw_line(reader, &mut buf)? > 0 {
+        let mut line = String::new();
+        encoding::decode_line(encoding, &buf, line_number == 1, &mut line);
+        lines.push(line);
+        line_number += 1;
     }
 
-    Ok(count)
+    Ok(lines)
 }
 
 /// 读取指定范围内的行
 pub fn read_lines<P: AsRef<Path>>(
     path: P,
+    encoding: &'static Encoding,
     start_line: usize,
     count: usize,
 ) -> io::Result<Vec<String>> {
-    let file = File::open(path)?;
-    let reader = BufReader::with_capacity(64 * 1024, file);
+    let (mut reader, _) = compression::open(path.as_ref())?;
 
-    let mut lines = Vec::with_capacity(count);
-    let mut current_line = 0;
+    let mut buf = Vec::new();
+    for _ in 1..start_line.max(1) {
+        if read_raw_line(&mut reader, &mut buf)? == 0 {
+            return Ok(Vec::new());
+        }
+    }
 
-    for line in reader.lines() {
-        let line = line?;
-        current_line += 1;
+    collect_lines(&mut reader, encoding, start_line.max(1), count)
+}
 
-        if current_line >= start_line && current_line < start_line + count {
+/// 从任意读取器中读取指定范围内的行，同时统计总行数
+///
+/// 用于无法建立行偏移索引的数据（如压缩包中的文件），需要读完全部内容。无效的字节按替换字符显示。
+pub fn read_page<R: BufRead>(
+    mut reader: R,
+    encoding: &'static Encoding,
+    start_line: usize,
+    count: usize,
+) -> io::Result<(Vec<String>, u64)> {
+    let mut lines = Vec::with_capacity(count.min(64 * 1024));
+    let mut buf = Vec::new();
+    let mut total_lines = 0u64;
+
+    while read_raw_line(&mut reader, &mut buf)? > 0 {
+        total_lines += 1;
+
+        let line_number = total_lines as usize;
+        if line_number >= start_line && line_number < start_line.saturating_add(count) {
+            let mut line = String::new();
+            encoding::decode_line(encoding, &buf, line_number == 1, &mut line);
             lines.push(line);
         }
+    }
+
+    Ok((lines, total_lines))
+}
+
+/// 行偏移索引的采样间隔（每隔多少行记录一次字节偏移）
+const INDEX_INTERVAL: u64 = 1000;
+
+/// 行偏移索引缓存的最大条目数
+const INDEX_CACHE_CAPACITY: usize = 64;
+
+/// 稀疏行偏移索引：每 `INDEX_INTERVAL` 行记录一次该行起始的字节偏移
+///
+/// 压缩文件的偏移是解压后内容中的偏移。
+#[derive(Debug)]
+pub struct LineIndex {
+    size: u64,
+    modified: Option<SystemTime>,
+    compression: Compression,
+    encoding: &'static Encoding,
+    content_size: u64,
+    total_lines: u64,
+    offsets: Vec<u64>,
+}
+
+impl LineIndex {
+    /// 扫描整个文件构建索引
+    pub fn build<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        let metadata = fs::metadata(path)?;
+        let (mut reader, compression) = compression::open(path)?;
+        let encoding = encoding::detect(reader.fill_buf()?);
+
+        let mut offsets = vec![0u64];
+        let mut newlines = 0u64;
+        let mut position = 0u64;
+        let mut last_byte = b'\n';
+
+        loop {
+            let buf = reader.fill_buf()?;
+            if buf.is_empty() {
+                break;
+            }
+
+            for (i, &b) in buf.iter().enumerate() {
+                if b == b'\n' {
+                    newlines += 1;
+                    if newlines.is_multiple_of(INDEX_INTERVAL) {
+                        offsets.push(position + i as u64 + 1);
+                    }
+                }
+            }
+
+            last_byte = buf[buf.len() - 1];
+            let len = buf.len();
+            position += len as u64;
+            reader.consume(len);
+        }
+
+        // 最后一行没有换行符时也算作一行
+        let total_lines = if last_byte == b'\n' { newlines } else { newlines + 1 };
+
+        Ok(LineIndex {
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+            compression,
+            encoding,
+            content_size: position,
+            total_lines,
+            offsets,
+        })
+    }
+
+    /// 文件总行数
+    pub fn total_lines(&self) -> u64 {
+        self.total_lines
+    }
+
+    /// 内容大小，压缩文件为解压后的大小
+    pub fn content_size(&self) -> u64 {
+        self.content_size
+    }
+
+    /// 文件的压缩格式
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    /// 根据文件开头检测到的编码
+    pub fn encoding(&self) -> &'static Encoding {
+        self.encoding
+    }
+
+    /// 返回不晚于 `line`（从 1 开始）的最近索引点：(字节偏移, 该偏移处的行号)
+    fn seek_point(&self, line: usize) -> (u64, usize) {
+        let slot = ((line.max(1) - 1) as u64 / INDEX_INTERVAL) as usize;
+        let slot = slot.min(self.offsets.len() - 1);
+        (self.offsets[slot], slot * INDEX_INTERVAL as usize + 1)
+    }
+
+    /// 判断索引是否仍与文件元数据一致
+    fn is_fresh(&self, metadata: &Metadata) -> bool {
+        self.size == metadata.len() && self.modified == metadata.modified().ok()
+    }
+}
+
+/// 按路径缓存的行偏移索引，文件大小或修改时间变化时自动失效
+#[derive(Default)]
+pub struct LineIndexCache {
+    entries: Mutex<HashMap<PathBuf, Arc<LineIndex>>>,
+}
+
+impl LineIndexCache {
+    /// 获取文件的行偏移索引，必要时重新构建
+    pub fn get<P: AsRef<Path>>(&self, path: P) -> io::Result<Arc<LineIndex>> {
+        let path = path.as_ref();
+        let metadata = fs::metadata(path)?;
+
+        if let Some(index) = self.lock().get(path) {
+            if index.is_fresh(&metadata) {
+                return Ok(Arc::clone(index));
+            }
+        }
+
+        // 在锁外构建，避免阻塞其他文件的查询
+        let index = Arc::new(LineIndex::build(path)?);
+
+        let mut entries = self.lock();
+        if entries.len() >= INDEX_CACHE_CAPACITY && !entries.contains_key(path) {
+            // 缓存已满时淘汰任意一项
+            if let Some(evicted) = entries.keys().next().cloned() {
+                entries.remove(&evicted);
+            }
+        }
+        entries.insert(path.to_path_buf(), Arc::clone(&index));
+
+        Ok(index)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<PathBuf, Arc<LineIndex>>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// 借助行偏移索引读取指定范围内的行，直接定位到最近的索引点
+///
+/// 压缩文件无法随机访问，需要从头解压并丢弃索引点之前的内容，但不用再逐行解析。
+pub fn read_lines_indexed<P: AsRef<Path>>(
+    path: P,
+    index: &LineIndex,
+    encoding: &'static Encoding,
+    start_line: usize,
+    count: usize,
+) -> io::Result<Vec<String>> {
+    let (offset, first_line) = index.seek_point(start_line);
+
+    let mut file = File::open(path)?;
+    let mut reader: Box<dyn BufRead> = if index.compression == Compression::None {
+        file.seek(SeekFrom::Start(offset))?;
+        Box::new(BufReader::with_capacity(64 * 1024, file))
+    } else {
+        let mut reader = compression::buffered(BufReader::with_capacity(64 * 1024, file), index.compression)?;
+        io::copy(&mut (&mut reader).take(offset), &mut io::sink())?;
+        reader
+    };
 
-        if current_line >= start_line + count {
+    let mut buf = Vec::new();
+    for _ in first_line..start_line.max(1) {
+        if read_raw_line(&mut reader, &mut buf)? == 0 {
+            return Ok(Vec::new());
+        }
+    }
+
+    collect_lines(&mut reader, encoding, start_line.max(1), count)
+}
+
+/// 按字节查看时单行最多返回的字符数，超出的部分拆成多段
+const MAX_LINE_CHARS: usize = 8192;
+
+/// 按字节偏移读取的一段内容
+#[derive(Debug, Default)]
+pub struct ByteWindow {
+    /// 按行拆分的内容，超长的行拆成多段
+    pub lines: Vec<String>,
+    /// 每段是否接续上一段（中间没有换行符），第一段接续的是上一段内容
+    pub continued: Vec<bool>,
+    /// 实际读取的起始偏移
+    pub start: u64,
+    /// 实际读取的结束偏移（不含）
+    pub end: u64,
+}
+
+/// 从 `offset` 开始读取大约 `length` 个字节，借助行偏移索引判断压缩格式
+///
+/// 未压缩的文件直接定位，压缩文件需要从头解压并丢弃之前的内容。
+pub fn read_window_indexed<P: AsRef<Path>>(
+    path: P,
+    index: &LineIndex,
+    encoding: &'static Encoding,
+    offset: u64,
+    length: usize,
+) -> io::Result<ByteWindow> {
+    let offset = offset.min(index.content_size);
+    if index.compression != Compression::None {
+        let (reader, _) = compression::open(path.as_ref())?;
+        return read_window(reader, encoding, offset, length);
+    }
+
+    // 多读前一个字节，用于判断起始位置是否在行首
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(offset.saturating_sub(1)))?;
+    window_from(BufReader::with_capacity(64 * 1024, file), encoding, offset, length)
+}
+
+/// 从读取器开头跳过 `offset` 个字节后读取大约 `length` 个字节
+pub fn read_window<R: BufRead>(
+    mut reader: R,
+    encoding: &'static Encoding,
+    offset: u64,
+    length: usize,
+) -> io::Result<ByteWindow> {
+    io::copy(&mut (&mut reader).take(offset.saturating_sub(1)), &mut io::sink())?;
+    window_from(reader, encoding, offset, length)
+}
+
+/// 读取器位于 `offset` 的前一个字节（`offset` 为 0 时位于开头）
+///
+/// 内容在最后一个换行符处结束，使下一段从行首开始；整段都没有换行符时在字符边界处截断。
+/// 只有 UTF-8 能可靠地找到字符边界，其他编码在超长的行中截断时可能显示替换字符。
+fn window_from<R: BufRead>(
+    mut reader: R,
+    encoding: &'static Encoding,
+    offset: u64,
+    length: usize,
+) -> io::Result<ByteWindow> {
+    let mut prev = [b'\n'];
+    if offset > 0 && reader.read(&mut prev)? == 0 {
+        return Ok(ByteWindow {
+            start: offset,
+            end: offset,
+            ..Default::default()
+        });
+    }
+    let line_start = prev[0] == b'\n';
+
+    let mut buf = Vec::with_capacity(length.min(1024 * 1024));
+    (&mut reader).take(length as u64).read_to_end(&mut buf)?;
+    let eof = reader.fill_buf()?.is_empty();
+    let utf8 = encoding == encoding_rs::UTF_8;
+
+    // 起始偏移落在多字节字符中间时跳到下一个字符
+    let start = if utf8 && !line_start {
+        buf.iter().take(3).take_while(|&&b| is_utf8_continuation(b)).count()
+    } else {
+        0
+    };
+    let end = if eof {
+        buf.len()
+    } else if let Some(newline) = buf[start..].iter().rposition(|&b| b == b'\n') {
+        start + newline + 1
+    } else if utf8 {
+        let back = buf.iter().rev().take(3).take_while(|&&b| is_utf8_continuation(b)).count();
+        // 回退到字符的首字节之前；首字节也在末尾时说明字符完整
+        let cut = buf.len() - back;
+        match buf.get(cut.wrapping_sub(1)) {
+            Some(&lead) if back < utf8_char_len(lead).saturating_sub(1) => cut - 1,
+            _ => buf.len(),
+        }
+        .max(start + 1)
+        .min(buf.len())
+    } else {
+        buf.len()
+    };
+
+    let mut window = ByteWindow {
+        start: offset + start as u64,
+        end: offset + end as u64,
+        ..Default::default()
+    };
+    let body = &buf[start..end];
+    let body = body.strip_suffix(b"\n").unwrap_or(body);
+    if body.is_empty() && end == start {
+        return Ok(window);
+    }
+
+    for (i, raw) in body.split(|&b| b == b'\n').enumerate() {
+        let raw = raw.strip_suffix(b"\r").unwrap_or(raw);
+        let mut line = String::new();
+        encoding::decode_line(encoding, raw, offset == 0 && i == 0, &mut line);
+
+        let mut continued = i == 0 && !line_start;
+        let mut rest = line.as_str();
+        loop {
+            let split = rest.char_indices().nth(MAX_LINE_CHARS).map_or(rest.len(), |(i, _)| i);
+            window.lines.push(rest[..split].to_string());
+            window.continued.push(continued);
+            rest = &rest[split..];
+            if rest.is_empty() {
+                break;
+            }
+            continued = true;
+        }
+    }
+
+    Ok(window)
+}
+
+fn is_utf8_continuation(b: u8) -> bool {
+    b & 0xC0 == 0x80
+}
+
+/// 根据首字节判断 UTF-8 字符的字节数
+fn utf8_char_len(lead: u8) -> usize {
+    match lead {
+        0xF0..=0xF7 => 4,
+        0xE0..=0xEF => 3,
+        0xC0..=0xDF => 2,
+        _ => 1,
+    }
+}
+
+/// 借助行偏移索引计算字节偏移所在的行号（从 1 开始），只支持未压缩的文件
+pub fn line_at<P: AsRef<Path>>(path: P, index: &LineIndex, offset: u64) -> io::Result<u64> {
+    let slot = index.offsets.partition_point(|&o| o <= offset).saturating_sub(1);
+    let base = index.offsets[slot];
+
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(base))?;
+    let mut reader = BufReader::with_capacity(64 * 1024, file.take(offset - base));
+    let mut newlines = 0u64;
+    loop {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
             break;
         }
+        newlines += buf.iter().filter(|&&b| b == b'\n').count() as u64;
+        let len = buf.len();
+        reader.consume(len);
     }
 
-    Ok(lines)
+    Ok(slot as u64 * INDEX_INTERVAL + newlines + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::temp_file;
+
+    /// `count` 行内容为 `line N` 的文本，`trailing_newline` 控制最后一行是否带换行符
+    fn numbered_lines(count: usize, trailing_newline: bool) -> String {
+        let mut content: String = (1..=count).map(|i| format!("line {}\n", i)).collect();
+        if !trailing_newline {
+            content.pop();
+        }
+        content
+    }
+
+    fn indexed(path: &Path, index: &LineIndex, start_line: usize, count: usize) -> Vec<String> {
+        read_lines_indexed(path, index, index.encoding(), start_line, count).unwrap()
+    }
+
+    #[test]
+    fn reads_across_index_checkpoints() {
+        let file = temp_file(".log", numbered_lines(2500, true));
+        let index = LineIndex::build(file.path()).unwrap();
+        assert_eq!(index.total_lines(), 2500);
+        assert_eq!(index.offsets.len(), 3);
+
+        assert_eq!(indexed(file.path(), &index, 999, 3), ["line 999", "line 1000", "line 1001"]);
+        assert_eq!(indexed(file.path(), &index, 1000, 1), ["line 1000"]);
+        assert_eq!(indexed(file.path(), &index, 1001, 1), ["line 1001"]);
+        assert_eq!(indexed(file.path(), &index, 0, 2), ["line 1", "line 2"]);
+
+        // 最后一页不满
+        let last_page = indexed(file.path(), &index, 2001, 1000);
+        assert_eq!(last_page.len(), 500);
+        assert_eq!(last_page.last().map(String::as_str), Some("line 2500"));
+        assert!(indexed(file.path(), &index, 2501, 10).is_empty());
+        assert!(indexed(file.path(), &index, 5000, 10).is_empty());
+
+        // 与逐行读取的结果一致
+        for start in [1, 998, 1999, 2000, 2001, 2499] {
+            assert_eq!(indexed(file.path(), &index, start, 5), read_lines(file.path(), encoding_rs::UTF_8, start, 5).unwrap());
+        }
+    }
+
+    #[test]
+    fn counts_last_line_without_newline() {
+        let file = temp_file(".log", numbered_lines(2000, false));
+        let index = LineIndex::build(file.path()).unwrap();
+        assert_eq!(index.total_lines(), 2000);
+        // 没有换行符的最后一行也能读到
+        assert_eq!(indexed(file.path(), &index, 1999, 10), ["line 1999", "line 2000"]);
+
+        let file = temp_file(".log", "a\r\nb");
+        let index = LineIndex::build(file.path()).unwrap();
+        assert_eq!(index.total_lines(), 2);
+        assert_eq!(indexed(file.path(), &index, 1, 10), ["a", "b"]);
+
+        let file = temp_file(".log", "");
+        assert_eq!(LineIndex::build(file.path()).unwrap().total_lines(), 0);
+    }
+
+    #[test]
+    fn cache_reuses_index_until_file_changes() {
+        let file = temp_file(".log", numbered_lines(10, true));
+        let cache = LineIndexCache::default();
+
+        let first = cache.get(file.path()).unwrap();
+        assert!(Arc::ptr_eq(&first, &cache.get(file.path()).unwrap()));
+
+        // 大小变化
+        fs::write(file.path(), numbered_lines(20, true)).unwrap();
+        let resized = cache.get(file.path()).unwrap();
+        assert!(!Arc::ptr_eq(&first, &resized));
+        assert_eq!(resized.total_lines(), 20);
+
+        // 大小不变，只有修改时间变化
+        fs::write(file.path(), numbered_lines(20, true).replace("line 1\n", "LINE 1\n")).unwrap();
+        let modified = resized.modified.unwrap() + std::time::Duration::from_secs(10);
+        File::options().write(true).open(file.path()).unwrap().set_modified(modified).unwrap();
+        let touched = cache.get(file.path()).unwrap();
+        assert!(!Arc::ptr_eq(&resized, &touched));
+        assert_eq!(read_lines_indexed(file.path(), &touched, touched.encoding(), 1, 1).unwrap(), ["LINE 1"]);
+    }
+
+    fn window(data: &[u8], offset: u64, length: usize) -> ByteWindow {
+        read_window(data, encoding_rs::UTF_8, offset, length).unwrap()
+    }
+
+    #[test]
+    fn window_ends_at_last_newline() {
+        let w = window(b"aaa\nbbb\nccc\n", 0, 6);
+        assert_eq!(w.lines, ["aaa"]);
+        assert_eq!(w.continued, [false]);
+        assert_eq!((w.start, w.end), (0, 4));
+
+        // 下一段从上一段的结束位置开始
+        let w = window(b"aaa\nbbb\nccc\n", w.end, 100);
+        assert_eq!(w.lines, ["bbb", "ccc"]);
+        assert_eq!(w.continued, [false, false]);
+        assert_eq!((w.start, w.end), (4, 12));
+    }
+
+    #[test]
+    fn window_in_middle_of_line_is_continued() {
+        let w = window(b"hello\r\nworld", 2, 100);
+        assert_eq!(w.lines, ["llo", "world"]);
+        assert_eq!(w.continued, [true, false]);
+        assert_eq!((w.start, w.end), (2, 12));
+    }
+
+    #[test]
+    fn window_skips_partial_utf8_char_at_start() {
+        let data = "中文\n".as_bytes();
+        let w = window(data, 1, 100);
+        assert_eq!(w.lines, ["文"]);
+        assert_eq!(w.continued, [true]);
+        assert_eq!((w.start, w.end), (3, 7));
+    }
+
+    #[test]
+    fn window_cuts_long_line_at_char_boundary() {
+        let data = "中文字\n".as_bytes();
+        let w = window(data, 0, 4);
+        assert_eq!(w.lines, ["中"]);
+        assert_eq!((w.start, w.end), (0, 3));
+
+        let w = window(data, w.end, 4);
+        assert_eq!(w.lines, ["文"]);
+        assert_eq!(w.continued, [true]);
+        assert_eq!((w.start, w.end), (3, 6));
+
+        // 末尾刚好是完整字符时不回退
+        let w = window(data, 0, 6);
+        assert_eq!(w.lines, ["中文"]);
+        assert_eq!(w.end, 6);
+    }
+
+    #[test]
+    fn window_splits_overlong_lines() {
+        let mut data = vec![b'x'; MAX_LINE_CHARS * 2 + 10];
+        data.push(b'\n');
+        let w = window(&data, 0, data.len());
+        assert_eq!(w.lines.iter().map(String::len).collect::<Vec<_>>(), [MAX_LINE_CHARS, MAX_LINE_CHARS, 10]);
+        assert_eq!(w.continued, [false, true, true]);
+    }
+
+    #[test]
+    fn window_past_end_is_empty() {
+        let w = window(b"abc\n", 100, 10);
+        assert!(w.lines.is_empty());
+        assert_eq!((w.start, w.end), (100, 100));
+
+        let w = window(b"abc\n", 4, 10);
+        assert!(w.lines.is_empty());
+        assert_eq!((w.start, w.end), (4, 4));
+    }
 }
@@ -0,0 +1,577 @@
+use crate::scanner::LineScanner;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use futures::Stream;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    convert::Infallible,
+    io,
+    time::{Duration, Instant},
+};
+use tokio::sync::{mpsc, OwnedSemaphorePermit};
+use tracing::{debug, error};
+
+/// 匹配方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchMode {
+    /// 按字面文本匹配
+    #[default]
+    Literal,
+    /// 按正则表达式匹配
+    Regex,
+}
+
+/// 搜索选项，作为查询参数传入
+#[derive(Debug, Clone, Deserialize)]
+pub struct SearchOptions {
+    /// 搜索内容
+    #[serde(default)]
+    pub q: String,
+    #[serde(default)]
+    pub mode: SearchMode,
+    /// 区分大小写
+    #[serde(rename = "caseSensitive", default)]
+    pub case_sensitive: bool,
+    /// 全词匹配
+    #[serde(rename = "wholeWord", default)]
+    pub whole_word: bool,
+    /// 反向匹配，返回不包含搜索内容的行
+    #[serde(default)]
+    pub invert: bool,
+}
+
+/// 匹配内容在行中的字节范围 `[start, end)`
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct MatchRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// 按搜索选项编译好的匹配器
+#[derive(Debug, Clone)]
+pub struct Matcher {
+    regex: Regex,
+    invert: bool,
+}
+
+impl Matcher {
+    /// 编译搜索选项，正则表达式无效时返回错误说明
+    pub fn new(options: &SearchOptions) -> Result<Self, String> {
+        if options.q.is_empty() {
+            return Err("搜索内容不能为空".to_string());
+        }
+
+        let pattern = match options.mode {
+            SearchMode::Literal => regex::escape(&options.q),
+            SearchMode::Regex => options.q.clone(),
+        };
+        let pattern = if options.whole_word {
+            format!(r"\b(?:{})\b", pattern)
+        } else {
+            pattern
+        };
+
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(!options.case_sensitive)
+            .build()
+            .map_err(|e| format!("无效的正则表达式: {}", e))?;
+
+        Ok(Matcher {
+            regex,
+            invert: options.invert,
+        })
+    }
+
+    /// 判断一行是否符合条件
+    pub fn is_match(&self, line: &str) -> bool {
+        self.regex.is_match(line) != self.invert
+    }
+
+    /// 判断一行是否符合条件，符合时返回匹配内容的字节范围（反向匹配时为空）
+    pub fn matches(&self, line: &str) -> Option<Vec<MatchRange>> {
+        if self.invert {
+            return (!self.regex.is_match(line)).then(Vec::new);
+        }
+
+        let ranges: Vec<MatchRange> = self
+            .regex
+            .find_iter(line)
+            // 空匹配（如 `^`、`a*`）无法高亮
+            .filter(|m| !m.is_empty())
+            .map(|m| MatchRange {
+                start: m.start(),
+                end: m.end(),
+            })
+            .collect();
+
+        if ranges.is_empty() && !self.regex.is_match(line) {
+            None
+        } else {
+            Some(ranges)
+        }
+    }
+}
+
+/// 默认最多返回的匹配行数
+const DEFAULT_MAX_RESULTS: usize = 1000;
+
+/// 单次请求最多返回的匹配行数
+const MAX_RESULTS: usize = 10000;
+
+/// 上下文最多包含的行数
+const MAX_CONTEXT_LINES: usize = 100;
+
+fn default_max_results() -> usize {
+    DEFAULT_MAX_RESULTS
+}
+
+/// 结果分页和上下文选项，作为查询参数传入
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct ResultOptions {
+    /// 最多返回的匹配行数
+    #[serde(rename = "maxResults", default = "default_max_results")]
+    pub max_results: usize,
+    /// 跳过前面的匹配行数，用于翻页
+    #[serde(default)]
+    pub offset: u64,
+    /// 每个匹配行之前的上下文行数（类似 grep -B）
+    #[serde(default)]
+    pub before: usize,
+    /// 每个匹配行之后的上下文行数（类似 grep -A）
+    #[serde(default)]
+    pub after: usize,
+}
+
+impl ResultOptions {
+    /// 把超出上限的参数限制到上限
+    pub fn clamped(self) -> Self {
+        ResultOptions {
+            max_results: self.max_results.clamp(1, MAX_RESULTS),
+            offset: self.offset,
+            before: self.before.min(MAX_CONTEXT_LINES),
+            after: self.after.min(MAX_CONTEXT_LINES),
+        }
+    }
+}
+
+/// 单个匹配行
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchResult {
+    #[serde(rename = "lineNumber")]
+    pub line_number: u64,
+    pub page: u32,
+    pub line: String,
+    /// 匹配内容在行中的字节范围
+    pub matches: Vec<MatchRange>,
+    /// 匹配行之前的上下文
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub before: Vec<String>,
+    /// 匹配行之后的上下文
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub after: Vec<String>,
+}
+
+/// 一个文件的搜索结果
+#[derive(Debug, Default, Serialize)]
+pub struct SearchOutcome {
+    pub results: Vec<SearchResult>,
+    /// 文件中的匹配行总数
+    pub total: u64,
+    /// 是否还有未返回的匹配行
+    pub truncated: bool,
+    /// 下一页的 `offset`，没有更多结果时省略
+    #[serde(rename = "nextOffset", skip_serializing_if = "Option::is_none")]
+    pub next_offset: Option<u64>,
+}
+
+/// 搜索结束时的统计
+#[derive(Debug, Default, Serialize)]
+pub struct SearchSummary {
+    /// 文件中的匹配行总数
+    pub total: u64,
+    /// 是否还有未返回的匹配行
+    pub truncated: bool,
+    /// 下一页的 `offset`，没有更多结果时省略
+    #[serde(rename = "nextOffset", skip_serializing_if = "Option::is_none")]
+    pub next_offset: Option<u64>,
+    /// 已扫描的字节数，压缩文件为解压后的字节数
+    #[serde(rename = "bytesScanned")]
+    pub bytes_scanned: u64,
+}
+
+/// 搜索过程中依次产生的事件
+#[derive(Debug)]
+pub enum SearchEvent {
+    /// 一个匹配行，上下文和所在页都已确定
+    Result(SearchResult),
+    /// 已扫描的字节数
+    Progress(u64),
+}
+
+/// 每扫描多少字节检查一次是否需要报告进度
+const PROGRESS_CHECK_BYTES: u64 = 1024 * 1024;
+
+/// 两次进度事件之间的最短间隔
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// 逐行搜索，只保留 `offset` 之后的 `max_results` 个匹配行，其余匹配只计数
+///
+/// 读完后用内容大小调用 `lines_per_page` 计算结果所在的页，返回 `None` 时所有结果都在第 1 页。
+pub fn search_lines(
+    scanner: &mut LineScanner,
+    matcher: &Matcher,
+    options: ResultOptions,
+    lines_per_page: impl Fn(u64) -> Option<usize>,
+) -> io::Result<SearchOutcome> {
+    let mut results = Vec::new();
+    let summary = scan_lines(scanner, matcher, options, lines_per_page, |event| {
+        if let SearchEvent::Result(result) = event {
+            results.push(result);
+        }
+        Ok(())
+    })?;
+
+    Ok(SearchOutcome {
+        results,
+        total: summary.total,
+        truncated: summary.truncated,
+        next_offset: summary.next_offset,
+    })
+}
+
+/// 逐行搜索，结果一确定就交给 `emit`，并定期报告进度
+///
+/// 结果要等后续上下文读完、所在页确定后才会发出。`lines_per_page` 用已读取的字节数调用，
+/// 返回 `Some` 后不再改变，读到文件末尾仍为 `None` 时所有结果都在第 1 页。
+/// `emit` 返回错误时停止搜索并返回该错误。
+pub fn scan_lines(
+    scanner: &mut LineScanner,
+    matcher: &Matcher,
+    options: ResultOptions,
+    lines_per_page: impl Fn(u64) -> Option<usize>,
+    mut emit: impl FnMut(SearchEvent) -> io::Result<()>,
+) -> io::Result<SearchSummary> {
+    let options = options.clamped();
+    let mut summary = SearchSummary::default();
+    let mut recent: VecDeque<String> = VecDeque::with_capacity(options.before);
+    // 还没有发出的结果
+    let mut pending: VecDeque<SearchResult> = VecDeque::new();
+    // 其中仍在收集后续上下文的结果数量，总是位于末尾
+    let mut pending_after = 0usize;
+    // 已收集的结果数量
+    let mut collected = 0usize;
+    // 确定是否分页后为 `Some`
+    let mut page_size: Option<Option<usize>> = None;
+    let mut line_number = 0u64;
+    let mut next_check = PROGRESS_CHECK_BYTES;
+    let mut last_progress = Instant::now();
+
+    while let Some(line) = scanner.read_line()? {
+        line_number += 1;
+
+        if pending_after > 0 {
+            let start = pending.len() - pending_after;
+            for result in pending.range_mut(start..) {
+                if result.after.len() < options.after {
+                    result.after.push(line.to_string());
+                }
+            }
+            let first_open = pending
+                .range(start..)
+                .position(|r| r.after.len() < options.after)
+                .unwrap_or(pending_after);
+            pending_after -= first_open;
+        }
+
+        let collecting = collected < options.max_results;
+        if !collecting || summary.total < options.offset {
+            // 不需要返回的匹配行只计数
+            if matcher.is_match(line) {
+                summary.total += 1;
+            }
+        } else if let Some(matches) = matcher.matches(line) {
+            summary.total += 1;
+            collected += 1;
+            pending.push_back(SearchResult {
+                line_number,
+                page: 1,
+                line: line.to_string(),
+                matches,
+                before: recent.iter().cloned().collect(),
+                after: Vec::new(),
+            });
+            if options.after > 0 {
+                pending_after += 1;
+            }
+        }
+
+        // 结果已满后不再需要前文
+        if options.before > 0 && collecting {
+            if recent.len() == options.before {
+                recent.pop_front();
+            }
+            recent.push_back(line.to_string());
+        }
+
+        let bytes_read = scanner.bytes_read();
+        if page_size.is_none() {
+            if let Some(n) = lines_per_page(bytes_read) {
+                page_size = Some(Some(n));
+            }
+        }
+        if let Some(page_size) = page_size {
+            let ready = pending.len() - pending_after;
+            for result in pending.drain(..ready) {
+                emit(SearchEvent::Result(with_page(result, page_size)))?;
+            }
+        }
+
+        if bytes_read >= next_check {
+            next_check = bytes_read + PROGRESS_CHECK_BYTES;
+            if last_progress.elapsed() >= PROGRESS_INTERVAL {
+                last_progress = Instant::now();
+                emit(SearchEvent::Progress(bytes_read))?;
+            }
+        }
+    }
+
+    summary.bytes_scanned = scanner.bytes_read();
+    let page_size = page_size.unwrap_or_else(|| lines_per_page(summary.bytes_scanned));
+    for result in pending {
+        emit(SearchEvent::Result(with_page(result, page_size)))?;
+    }
+
+    let returned = options.offset.min(summary.total) + collected as u64;
+    summary.truncated = returned < summary.total;
+    summary.next_offset = summary.truncated.then_some(returned);
+
+    Ok(summary)
+}
+
+/// 按每页行数计算结果所在的页
+fn with_page(mut result: SearchResult, page_size: Option<usize>) -> SearchResult {
+    if let Some(n) = page_size {
+        result.page = ((result.line_number - 1) / n as u64) as u32 + 1;
+    }
+    result
+}
+
+/// 进度事件的内容
+#[derive(Debug, Serialize)]
+struct Progress {
+    #[serde(rename = "bytesScanned")]
+    bytes_scanned: u64,
+    /// 文件大小，压缩文件解压后的大小未知时省略
+    #[serde(rename = "totalBytes", skip_serializing_if = "Option::is_none")]
+    total_bytes: Option<u64>,
+}
+
+/// 错误事件的内容
+#[derive(Debug, Serialize)]
+struct Failure {
+    message: String,
+}
+
+/// 搜索线程与响应之间最多缓存的事件数量
+const CHANNEL_CAPACITY: usize = 64;
+
+/// 在后台线程中搜索，通过 Server-Sent Events 边搜索边发送结果
+///
+/// 依次发送 `result` 和 `progress` 事件，结束时发送 `done`（内容为 `SearchSummary`）或 `error`。
+/// 客户端断开连接后发送失败，搜索随之停止。`permit` 在搜索线程结束前一直持有。
+pub fn stream(
+    mut scanner: LineScanner,
+    matcher: Matcher,
+    options: ResultOptions,
+    total_bytes: Option<u64>,
+    lines_per_page: impl Fn(u64) -> Option<usize> + Send + 'static,
+    permit: OwnedSemaphorePermit,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let (tx, rx) = mpsc::channel::<Event>(CHANNEL_CAPACITY);
+
+    tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        let send = |event: Event| {
+            tx.blocking_send(event)
+                .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "客户端已断开连接"))
+        };
+        let progress = |bytes_scanned| {
+            json_event("progress", &Progress {
+                bytes_scanned,
+                total_bytes,
+            })
+        };
+
+        let result = send(progress(0)).and_then(|_| {
+            scan_lines(&mut scanner, &matcher, options, lines_per_page, |event| match event {
+                SearchEvent::Result(result) => send(json_event("result", &result)),
+                SearchEvent::Progress(bytes) => send(progress(bytes)),
+            })
+        });
+
+        let _ = match result {
+            Ok(summary) => send(json_event("done", &summary)),
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {
+                debug!("客户端已断开连接，停止搜索");
+                Ok(())
+            }
+            Err(e) => {
+                error!("搜索失败: {}", e);
+                send(json_event("error", &Failure { message: e.to_string() }))
+            }
+        };
+    });
+
+    Sse::new(futures::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|event| (Ok(event), rx))
+    }))
+    .keep_alive(KeepAlive::default())
+}
+
+/// 生成内容为 JSON 的 Server-Sent Events 事件
+pub fn json_event(name: &str, data: &impl Serialize) -> Event {
+    let data = serde_json::to_string(data).unwrap_or_default();
+    Event::default().event(name).data(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::response::IntoResponse;
+    use crate::testutil::temp_file;
+    use std::{path::Path, sync::Arc};
+    use tokio::sync::Semaphore;
+
+    fn matcher(q: &str) -> Matcher {
+        Matcher::new(&SearchOptions {
+            q: q.to_string(),
+            mode: SearchMode::Literal,
+            case_sensitive: false,
+            whole_word: false,
+            invert: false,
+        })
+        .unwrap()
+    }
+
+    fn options(max_results: usize, offset: u64, before: usize, after: usize) -> ResultOptions {
+        ResultOptions { max_results, offset, before, after }
+    }
+
+    /// 搜索文件，返回发出的结果和统计
+    fn scan(
+        path: &Path,
+        q: &str,
+        options: ResultOptions,
+        lines_per_page: impl Fn(u64) -> Option<usize>,
+    ) -> (Vec<SearchResult>, SearchSummary) {
+        let mut scanner = LineScanner::open(path).unwrap();
+        let mut results = Vec::new();
+        let summary = scan_lines(&mut scanner, &matcher(q), options, lines_per_page, |event| {
+            if let SearchEvent::Result(result) = event {
+                results.push(result);
+            }
+            Ok(())
+        })
+        .unwrap();
+        (results, summary)
+    }
+
+    const LOG: &str = "a\nERROR 1\nb\nc\nERROR 2\nERROR 3\nd\ne\nf\nERROR 4\n";
+
+    #[test]
+    fn collects_context_around_matches() {
+        let file = temp_file(".log", LOG);
+        let (results, summary) = scan(file.path(), "error", options(10, 0, 1, 2), |_| None);
+
+        let lines: Vec<u64> = results.iter().map(|r| r.line_number).collect();
+        assert_eq!(lines, [2, 5, 6, 10]);
+        assert_eq!(results[0].before, ["a"]);
+        assert_eq!(results[0].after, ["b", "c"]);
+        // 相邻的匹配行互为上下文
+        assert_eq!(results[1].after, ["ERROR 3", "d"]);
+        assert_eq!(results[2].before, ["ERROR 2"]);
+        // 文件末尾的上下文不足
+        assert!(results[3].after.is_empty());
+        assert_eq!(results[3].matches.len(), 1);
+        assert_eq!((results[3].matches[0].start, results[3].matches[0].end), (0, 5));
+
+        assert_eq!(summary.total, 4);
+        assert!(!summary.truncated);
+        assert_eq!(summary.next_offset, None);
+        assert_eq!(summary.bytes_scanned, LOG.len() as u64);
+    }
+
+    #[test]
+    fn pages_through_results() {
+        let file = temp_file(".log", LOG);
+        let (results, summary) = scan(file.path(), "error", options(2, 0, 0, 0), |_| None);
+        assert_eq!(results.iter().map(|r| r.line_number).collect::<Vec<_>>(), [2, 5]);
+        assert_eq!(summary.total, 4);
+        assert!(summary.truncated);
+        assert_eq!(summary.next_offset, Some(2));
+
+        let (results, summary) = scan(file.path(), "error", options(2, 2, 0, 0), |_| None);
+        assert_eq!(results.iter().map(|r| r.line_number).collect::<Vec<_>>(), [6, 10]);
+        assert!(!summary.truncated);
+        assert_eq!(summary.next_offset, None);
+
+        let (results, summary) = scan(file.path(), "error", options(2, 10, 0, 0), |_| None);
+        assert!(results.is_empty());
+        assert_eq!(summary.total, 4);
+        assert!(!summary.truncated);
+    }
+
+    #[test]
+    fn assigns_pages_from_lines_per_page() {
+        let file = temp_file(".log", LOG);
+        let (results, _) = scan(file.path(), "error", options(10, 0, 0, 0), |_| None);
+        assert!(results.iter().all(|r| r.page == 1));
+
+        // 读到一定字节数后才确定分页
+        let (results, _) = scan(file.path(), "error", options(10, 0, 0, 0), |bytes| (bytes >= 10).then_some(3));
+        assert_eq!(results.iter().map(|r| r.page).collect::<Vec<_>>(), [1, 2, 2, 4]);
+    }
+
+    #[test]
+    fn emits_results_before_end_and_stops_on_error() {
+        let file = temp_file(".log", LOG);
+        let mut scanner = LineScanner::open(file.path()).unwrap();
+        let mut emitted = Vec::new();
+        let err = scan_lines(&mut scanner, &matcher("error"), options(10, 0, 0, 1), |_| Some(100), |event| {
+            if let SearchEvent::Result(result) = event {
+                emitted.push(result.line_number);
+            }
+            Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"))
+        })
+        .unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+        assert_eq!(emitted, [2]);
+        // 第一个结果的后文读完就发出，不会等到文件末尾
+        assert!(scanner.bytes_read() < LOG.len() as u64);
+    }
+
+    #[tokio::test]
+    async fn stream_sends_progress_results_and_done() {
+        let file = temp_file(".log", LOG);
+        let scanner = LineScanner::open(file.path()).unwrap();
+        let permits = Arc::new(Semaphore::new(1));
+        let permit = Arc::clone(&permits).acquire_owned().await.unwrap();
+        let response = stream(scanner, matcher("error"), options(2, 0, 0, 0), Some(LOG.len() as u64), |_| None, permit)
+            .into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+
+        let events: Vec<&str> = body
+            .lines()
+            .filter_map(|line| line.strip_prefix("event: "))
+            .collect();
+        assert_eq!(events, ["progress", "result", "result", "done"]);
+        assert!(body.contains(&format!(r#""totalBytes":{}"#, LOG.len())));
+        assert!(body.contains(&format!(r#""total":4,"truncated":true,"nextOffset":2,"bytesScanned":{}"#, LOG.len())));
+        // 搜索线程结束后释放许可
+        assert_eq!(permits.available_permits(), 1);
+    }
+}
@@ -0,0 +1,127 @@
+use crate::{config::Config, upload, AppState};
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tracing::warn;
+
+/// 根据配置构建 CORS 层
+///
+/// 未配置 `corsAllowedOrigins` 时不返回任何 CORS 头，只允许同源访问；
+/// 配置为 `["*"]` 时允许任意来源，但不允许携带 Cookie。
+pub fn cors_layer(config: &Config) -> CorsLayer {
+    let origins = &config.cors_allowed_origins;
+    if origins.is_empty() {
+        return CorsLayer::new();
+    }
+
+    let layer = CorsLayer::new()
+        .allow_methods([
+            Method::GET,
+            Method::HEAD,
+            Method::POST,
+            Method::PUT,
+            Method::PATCH,
+            Method::DELETE,
+        ])
+        .allow_headers(
+            [
+                header::CONTENT_TYPE,
+                header::AUTHORIZATION,
+                header::RANGE,
+                header::IF_RANGE,
+                header::IF_NONE_MATCH,
+                header::IF_MODIFIED_SINCE,
+            ]
+            .into_iter()
+            .chain(upload::REQUEST_HEADERS)
+            .collect::<Vec<_>>(),
+        )
+        .expose_headers(
+            [
+                header::CONTENT_RANGE,
+                header::CONTENT_DISPOSITION,
+                header::ETAG,
+                header::LAST_MODIFIED,
+            ]
+            .into_iter()
+            .chain(upload::RESPONSE_HEADERS)
+            .collect::<Vec<_>>(),
+        );
+
+    if origins.iter().any(|o| o == "*") {
+        return layer.allow_origin(AllowOrigin::any());
+    }
+
+    let allowed: Vec<HeaderValue> = origins
+        .iter()
+        .filter_map(|o| match HeaderValue::from_str(o.trim_end_matches('/')) {
+            Ok(value) => Some(value),
+            Err(_) => {
+                warn!("忽略无效的 CORS 来源: {}", o);
+                None
+            }
+        })
+        .collect();
+
+    layer
+        .allow_origin(AllowOrigin::list(allowed))
+        .allow_credentials(true)
+}
+
+/// CSRF 防护中间件：修改类请求的 Origin（或 Referer）必须与当前站点同源或在允许列表中
+///
+/// 浏览器发起的跨站请求总会携带 Origin，没有 Origin 和 Referer 的请求来自脚本或命令行工具，直接放行。
+pub async fn check_origin(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    if matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+        return Ok(next.run(req).await);
+    }
+
+    let headers = req.headers();
+    let origin = header_str(headers, &header::ORIGIN)
+        .map(str::to_string)
+        .or_else(|| header_str(headers, &header::REFERER).and_then(origin_of));
+
+    if let Some(origin) = origin {
+        if !is_allowed_origin(&state.config, headers, &origin) {
+            warn!("拒绝跨站请求: {} {} (Origin: {})", req.method(), req.uri().path(), origin);
+            return Err(StatusCode::FORBIDDEN);
+        }
+    }
+
+    Ok(next.run(req).await)
+}
+
+/// 判断来源是否与 Host 同源或在 CORS 允许列表中
+fn is_allowed_origin(config: &Config, headers: &HeaderMap, origin: &str) -> bool {
+    let origin = origin.trim_end_matches('/');
+
+    let same_origin = match (origin.split_once("://"), header_str(headers, &header::HOST)) {
+        (Some((_, authority)), Some(host)) => authority.eq_ignore_ascii_case(host),
+        _ => false,
+    };
+
+    same_origin
+        || config
+            .cors_allowed_origins
+            .iter()
+            .any(|allowed| allowed != "*" && allowed.trim_end_matches('/') == origin)
+}
+
+/// 从 Referer 中提取 `scheme://host[:port]`
+fn origin_of(referer: &str) -> Option<String> {
+    let (scheme, rest) = referer.split_once("://")?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    Some(format!("{}://{}", scheme, authority))
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &HeaderName) -> Option<&'a str> {
+    headers.get(name).and_then(|v| v.to_str().ok())
+}
@@ -0,0 +1,389 @@
+use crate::encoding;
+use crate::search::{json_event, MatchRange, Matcher};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use encoding_rs::Encoding;
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+use std::{
+    convert::Infallible,
+    fs::{self, File},
+    io::{self, Read, Seek, SeekFrom},
+    path::PathBuf,
+    time::Duration,
+};
+use tokio::{sync::mpsc, time::MissedTickBehavior};
+use tracing::{debug, error};
+
+/// 默认先发送的末尾行数
+const DEFAULT_LINES: usize = 100;
+
+/// 最多先发送的末尾行数
+const MAX_LINES: usize = 10000;
+
+/// 读取末尾行时最多向前读取的字节数，避免超长的行占用过多内存
+const MAX_BACKLOG_BYTES: u64 = 16 * 1024 * 1024;
+
+/// 每次读取的数据块大小
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// 检查文件变化的间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 每次检查最多读取的字节数，剩余的内容发送完这一批后继续读取
+const MAX_READ_BYTES: u64 = 1024 * 1024;
+
+/// 没有换行符的内容超过这个大小时先作为一行发送
+const MAX_PARTIAL_BYTES: usize = 1024 * 1024;
+
+/// 跟踪任务与响应之间最多缓存的事件数量
+const CHANNEL_CAPACITY: usize = 64;
+
+fn default_lines() -> usize {
+    DEFAULT_LINES
+}
+
+/// 实时跟踪查询参数，过滤条件见 `search::SearchOptions`
+#[derive(Debug, Deserialize)]
+pub struct TailQuery {
+    pub path: String,
+    /// 先发送的末尾行数
+    #[serde(default = "default_lines")]
+    pub lines: usize,
+    /// 指定文件编码，默认根据文件开头自动检测
+    pub encoding: Option<String>,
+}
+
+/// 发送给客户端的一行
+#[derive(Debug, Serialize)]
+struct TailLine {
+    line: String,
+    /// 匹配内容在行中的字节范围，没有过滤条件时为空
+    matches: Vec<MatchRange>,
+}
+
+/// 文件被截断或轮转后重新从头读取
+#[derive(Debug, Serialize)]
+struct Reset {
+    /// `truncated` 或 `rotated`
+    reason: &'static str,
+}
+
+/// 错误事件的内容
+#[derive(Debug, Serialize)]
+struct Failure {
+    message: String,
+}
+
+/// 像 `tail -F` 一样跟踪文件，通过 Server-Sent Events 发送新增的行
+///
+/// 先发送末尾的 `lines` 行，之后每隔一段时间检查文件是否有新内容。文件被截断时从头读取，
+/// 被轮转（路径指向了新文件）时读完旧文件剩余的内容后打开新文件。指定 `matcher` 时只发送匹配的行。
+/// 没有指定 `encoding` 时根据文件开头检测，轮转后重新检测。客户端断开连接后停止跟踪。
+///
+/// 等待在异步任务中进行，只有读取文件时才短暂占用阻塞线程，跟踪的连接再多也不会占满阻塞线程池。
+pub fn follow(
+    path: PathBuf,
+    lines: usize,
+    matcher: Option<Matcher>,
+    encoding: Option<&'static Encoding>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let (tx, rx) = mpsc::channel::<Event>(CHANNEL_CAPACITY);
+
+    tokio::spawn(async move {
+        let file = path.display().to_string();
+        match run(path, lines.min(MAX_LINES), matcher, encoding, &tx).await {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {
+                debug!("客户端已断开连接，停止跟踪: {}", file);
+            }
+            Err(e) => {
+                error!("跟踪文件失败 {}: {}", file, e);
+                let _ = tx.send(json_event("error", &Failure { message: e.to_string() })).await;
+            }
+            Ok(()) => {}
+        }
+    });
+
+    Sse::new(futures::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|event| (Ok(event), rx))
+    }))
+    .keep_alive(KeepAlive::default())
+}
+
+/// 定时检查文件，每次检查在阻塞线程中读取新内容后发送
+async fn run(
+    path: PathBuf,
+    lines: usize,
+    matcher: Option<Matcher>,
+    encoding: Option<&'static Encoding>,
+    tx: &mpsc::Sender<Event>,
+) -> io::Result<()> {
+    let mut follower = tokio::task::spawn_blocking(move || Follower::open(path, lines, matcher, encoding))
+        .await
+        .map_err(io::Error::other)??;
+    send_all(tx, follower.take_events()).await?;
+
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        // 没有新内容时不会发送事件，需要主动检查客户端是否已断开
+        if tx.is_closed() {
+            return Err(io::Error::new(io::ErrorKind::BrokenPipe, "客户端已断开连接"));
+        }
+
+        // 一次最多读取 `MAX_READ_BYTES`，还有内容时发送完这一批后立即继续读取
+        loop {
+            let (returned, result) = tokio::task::spawn_blocking(move || {
+                let result = follower.poll();
+                (follower, result)
+            })
+            .await
+            .map_err(io::Error::other)?;
+            follower = returned;
+
+            let more = result?;
+            send_all(tx, follower.take_events()).await?;
+            if !more {
+                break;
+            }
+        }
+    }
+}
+
+async fn send_all(tx: &mpsc::Sender<Event>, events: Vec<Event>) -> io::Result<()> {
+    for event in events {
+        tx.send(event)
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "客户端已断开连接"))?;
+    }
+    Ok(())
+}
+
+struct Follower {
+    path: PathBuf,
+    file: File,
+    /// 已经读到的位置
+    position: u64,
+    /// 打开的文件的标识，用于检测轮转
+    id: Option<(u64, u64)>,
+    matcher: Option<Matcher>,
+    /// 请求中指定的编码
+    forced_encoding: Option<&'static Encoding>,
+    /// 当前文件使用的编码
+    encoding: &'static Encoding,
+    /// 等待发送的事件
+    events: Vec<Event>,
+    /// 还没有读到换行符的内容
+    partial: Vec<u8>,
+}
+
+impl Follower {
+    /// 打开文件，准备好末尾 `lines` 行对应的事件
+    fn open(
+        path: PathBuf,
+        lines: usize,
+        matcher: Option<Matcher>,
+        forced_encoding: Option<&'static Encoding>,
+    ) -> io::Result<Self> {
+        let mut file = File::open(&path)?;
+        let metadata = file.metadata()?;
+        let encoding = match forced_encoding {
+            Some(encoding) => encoding,
+            None => encoding::detect_file(&path)?,
+        };
+        let backlog = last_lines(&mut file, metadata.len(), lines, encoding)?;
+
+        let mut follower = Follower {
+            path,
+            file,
+            position: metadata.len(),
+            id: file_id(&metadata),
+            matcher,
+            forced_encoding,
+            encoding,
+            events: Vec::new(),
+            partial: Vec::new(),
+        };
+        for line in backlog {
+            follower.send_line(&line);
+        }
+        Ok(follower)
+    }
+
+    /// 检查一次文件变化，返回是否还有没读完的内容
+    fn poll(&mut self) -> io::Result<bool> {
+        let len = self.file.metadata()?.len();
+        if len < self.position {
+            // 文件被截断（如 copytruncate 轮转）
+            self.partial.clear();
+            self.send(json_event("reset", &Reset { reason: "truncated" }));
+            self.position = 0;
+        }
+        if len > self.position {
+            self.read_until(len)?;
+            if self.position < len {
+                return Ok(true);
+            }
+        }
+
+        // 路径暂时不存在时（轮转过程中）继续读取旧文件
+        let Ok(current) = fs::metadata(&self.path) else {
+            return Ok(false);
+        };
+        if self.id.is_some() && file_id(&current) != self.id {
+            // 旧文件在轮转前可能还写入了内容，读完后再打开新文件
+            if self.file.metadata()?.len() > self.position {
+                return Ok(true);
+            }
+            self.flush_partial();
+            self.file = File::open(&self.path)?;
+            self.detect_encoding()?;
+            self.id = file_id(&self.file.metadata()?);
+            self.position = 0;
+            self.send(json_event("reset", &Reset { reason: "rotated" }));
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// 从当前位置最多读取 `MAX_READ_BYTES`（不超过 `end`），把其中完整的行加入待发送的事件
+    fn read_until(&mut self, end: u64) -> io::Result<()> {
+        let end = end.min(self.position + MAX_READ_BYTES);
+        self.file.seek(SeekFrom::Start(self.position))?;
+        let mut buf = vec![0u8; CHUNK_SIZE];
+        while self.position < end {
+            let wanted = (end - self.position).min(CHUNK_SIZE as u64) as usize;
+            let n = self.file.read(&mut buf[..wanted])?;
+            if n == 0 {
+                break;
+            }
+            self.position += n as u64;
+
+            let mut data = &buf[..n];
+            while let Some(newline) = data.iter().position(|&b| b == b'\n') {
+                self.partial.extend_from_slice(&data[..newline]);
+                data = &data[newline + 1..];
+                let line = take_line(&mut self.partial, self.encoding);
+                self.send_line(&line);
+            }
+            self.partial.extend_from_slice(data);
+
+            // 一直没有换行符时先把已读到的内容作为一行发送，避免缓存无限增长
+            if self.partial.len() >= MAX_PARTIAL_BYTES {
+                self.flush_partial();
+            }
+        }
+        Ok(())
+    }
+
+    /// 发送没有换行符的最后一行
+    fn flush_partial(&mut self) {
+        if self.partial.is_empty() {
+            return;
+        }
+        let line = take_line(&mut self.partial, self.encoding);
+        self.send_line(&line);
+    }
+
+    fn detect_encoding(&mut self) -> io::Result<()> {
+        self.encoding = match self.forced_encoding {
+            Some(encoding) => encoding,
+            None => encoding::detect_file(&self.path)?,
+        };
+        Ok(())
+    }
+
+    /// 发送一行，不符合过滤条件时跳过
+    fn send_line(&mut self, line: &str) {
+        let matches = match &self.matcher {
+            Some(matcher) => match matcher.matches(line) {
+                Some(matches) => matches,
+                None => return,
+            },
+            None => Vec::new(),
+        };
+        self.send(json_event("line", &TailLine {
+            line: line.to_string(),
+            matches,
+        }));
+    }
+
+    fn send(&mut self, event: Event) {
+        self.events.push(event);
+    }
+
+    fn take_events(&mut self) -> Vec<Event> {
+        std::mem::take(&mut self.events)
+    }
+}
+
+/// 取出缓存的一行，去掉行尾的 `\r`，无效的字节按替换字符显示
+fn take_line(partial: &mut Vec<u8>, encoding: &'static Encoding) -> String {
+    if partial.ends_with(b"\r") {
+        partial.pop();
+    }
+    let mut line = String::new();
+    encoding::decode_line(encoding, partial, false, &mut line);
+    partial.clear();
+    line
+}
+
+/// 从文件末尾向前读取最后 `count` 行
+///
+/// 最多向前读取 `MAX_BACKLOG_BYTES`，读到的第一行不完整时丢弃。
+fn last_lines(file: &mut File, len: u64, count: usize, encoding: &'static Encoding) -> io::Result<Vec<String>> {
+    if count == 0 || len == 0 {
+        return Ok(Vec::new());
+    }
+
+    let mut start = len;
+    let mut data: Vec<u8> = Vec::new();
+    loop {
+        let read = (start.min(CHUNK_SIZE as u64)) as usize;
+        start -= read as u64;
+        let mut chunk = vec![0u8; read];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut chunk)?;
+        chunk.extend_from_slice(&data);
+        data = chunk;
+
+        // 末尾的换行符不算作分隔
+        let body = data.strip_suffix(b"\n").unwrap_or(&data);
+        let newlines = body.iter().filter(|&&b| b == b'\n').count();
+        if newlines >= count || start == 0 || len - start >= MAX_BACKLOG_BYTES {
+            break;
+        }
+    }
+
+    let body = data.strip_suffix(b"\n").unwrap_or(&data);
+    let mut lines: Vec<&[u8]> = body.split(|&b| b == b'\n').collect();
+    if start > 0 {
+        lines.remove(0);
+    }
+    let skip = lines.len().saturating_sub(count);
+
+    // 从文件开头读取时第一行可能带有 BOM
+    let first = start == 0 && skip == 0;
+    Ok(lines[skip..]
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            let mut text = String::new();
+            encoding::decode_line(encoding, line, first && i == 0, &mut text);
+            text
+        })
+        .collect())
+}
+
+/// 用于判断路径是否指向了另一个文件，不支持的平台返回 `None`，只能检测截断
+#[cfg(unix)]
+fn file_id(metadata: &fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_metadata: &fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
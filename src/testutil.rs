@@ -0,0 +1,14 @@
+use std::fs;
+use tempfile::{NamedTempFile, TempDir};
+
+/// 创建临时目录，返回值被丢弃时连同内容一起删除
+pub fn temp_dir() -> TempDir {
+    tempfile::tempdir().unwrap()
+}
+
+/// 创建写入了 `content` 的临时文件，`suffix` 为文件名后缀（如 `.gz`），返回值被丢弃时删除
+pub fn temp_file(suffix: &str, content: impl AsRef<[u8]>) -> NamedTempFile {
+    let file = tempfile::Builder::new().suffix(suffix).tempfile().unwrap();
+    fs::write(file.path(), content).unwrap();
+    file
+}
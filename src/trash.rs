@@ -0,0 +1,322 @@
+use crate::fileops::{self, OverwritePolicy};
+use crate::upload::UPLOAD_DIR;
+use path_clean::PathClean;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::{Component, Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// 每个根目录下的回收站目录名
+pub const TRASH_DIR: &str = ".trash";
+
+/// 回收站中保存被删除条目的子目录
+const FILES_DIR: &str = "files";
+
+/// 回收站中保存条目信息的子目录
+const INFO_DIR: &str = "info";
+
+/// 同一纳秒内多次删除时用于区分 ID
+static ID_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// 回收站条目信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashItem {
+    pub id: String,
+    pub name: String,
+    /// 删除前的路径（相对于根目录）
+    #[serde(rename = "originalPath")]
+    pub original_path: String,
+    #[serde(rename = "deletedAt")]
+    pub deleted_at: String,
+    #[serde(rename = "deletedBy", default, skip_serializing_if = "Option::is_none")]
+    pub deleted_by: Option<String>,
+    #[serde(rename = "isDir")]
+    pub is_dir: bool,
+    #[serde(default)]
+    pub size: u64,
+}
+
+/// 判断路径是否位于根目录的回收站中
+pub fn is_in_trash(root: &Path, path: &Path) -> bool {
+    path.starts_with(root.join(TRASH_DIR))
+}
+
+/// 把文件或目录移动到根目录的回收站
+pub fn move_to_trash(root: &Path, path: &Path, deleted_by: Option<&str>) -> io::Result<TrashItem> {
+    let trash = root.join(TRASH_DIR);
+    fs::create_dir_all(trash.join(FILES_DIR))?;
+    fs::create_dir_all(trash.join(INFO_DIR))?;
+
+    let now = SystemTime::now();
+    let nanos = now.duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+    let id = format!("{:x}-{:x}", nanos, ID_COUNTER.fetch_add(1, Ordering::Relaxed));
+
+    let original_path = pathdiff::diff_paths(path, root).unwrap_or_else(|| path.to_path_buf());
+    let (_, size) = fileops::tree_size(path)?;
+    let item = TrashItem {
+        id: id.clone(),
+        name: path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        original_path: original_path.to_string_lossy().into_owned(),
+        deleted_at: humantime::format_rfc3339_seconds(now).to_string(),
+        deleted_by: deleted_by.map(str::to_string),
+        is_dir: fs::symlink_metadata(path)?.is_dir(),
+        size,
+    };
+
+    // 先写信息文件，移动失败时再删除，避免留下无法识别的条目
+    let info_path = info_path(&trash, &id);
+    fs::write(&info_path, serde_json::to_vec_pretty(&item)?)?;
+    if let Err(e) = fileops::move_path(path, &trash.join(FILES_DIR).join(&id)) {
+        let _ = fs::remove_file(&info_path);
+        return Err(e);
+    }
+
+    Ok(item)
+}
+
+/// 列出回收站中的条目，按删除时间倒序
+pub fn list(root: &Path) -> io::Result<Vec<TrashItem>> {
+    let info_dir = root.join(TRASH_DIR).join(INFO_DIR);
+    let entries = match fs::read_dir(&info_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut items: Vec<TrashItem> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| fs::read(entry.path()).ok())
+        .filter_map(|data| serde_json::from_slice(&data).ok())
+        .collect();
+
+    items.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at).then_with(|| b.id.cmp(&a.id)));
+    Ok(items)
+}
+
+/// 把回收站条目恢复到原位置，返回恢复后的完整路径，覆盖时原位置已有的条目移到回收站
+pub fn restore(root: &Path, id: &str, policy: OverwritePolicy, restored_by: Option<&str>) -> io::Result<PathBuf> {
+    let trash = root.join(TRASH_DIR);
+    let item = read_item(&trash, id)?;
+
+    // 信息文件可能被篡改，恢复前重新校验原路径
+    let original = Path::new(&item.original_path).clean();
+    if original.is_absolute()
+        || original.components().any(|c| c == Component::ParentDir)
+        || original.file_name().is_none()
+        || original.starts_with(TRASH_DIR)
+        || original.starts_with(UPLOAD_DIR)
+    {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "非法的原始路径"));
+    }
+
+    // 创建父目录前检查已存在的最深祖先，防止经由符号链接在根目录外创建目录
+    let parent = root.join(original.parent().unwrap_or_else(|| Path::new("")));
+    let existing = parent
+        .ancestors()
+        .find(|ancestor| fs::symlink_metadata(ancestor).is_ok())
+        .unwrap_or(root);
+    ensure_within_root(root, existing)?;
+    fs::create_dir_all(&parent)?;
+    ensure_within_root(root, &parent)?;
+
+    let stored = trash.join(FILES_DIR).join(id);
+    let destination = parent.join(original.file_name().unwrap_or_default());
+    let destination = fileops::prepare_destination(root, &stored, &destination, policy, restored_by)?;
+
+    fileops::move_path(&stored, &destination)?;
+    fs::remove_file(info_path(&trash, id))?;
+
+    Ok(destination)
+}
+
+/// 永久删除回收站条目，`id` 为空时清空整个回收站，返回删除的条目数
+pub fn purge(root: &Path, id: Option<&str>) -> io::Result<usize> {
+    match id {
+        Some(id) => {
+            let trash = root.join(TRASH_DIR);
+            read_item(&trash, id)?;
+            remove_item(&trash, id)?;
+            Ok(1)
+        }
+        None => purge_matching(root, |_| true),
+    }
+}
+
+/// 永久删除超过保留期的条目
+pub fn purge_expired(root: &Path, retention: Duration) -> io::Result<usize> {
+    let cutoff = SystemTime::now() - retention;
+    purge_matching(root, |item| {
+        humantime::parse_rfc3339(&item.deleted_at)
+            .map(|deleted_at| deleted_at < cutoff)
+            .unwrap_or(false)
+    })
+}
+
+fn purge_matching(root: &Path, predicate: impl Fn(&TrashItem) -> bool) -> io::Result<usize> {
+    let trash = root.join(TRASH_DIR);
+    let mut purged = 0;
+    for item in list(root)?.iter().filter(|item| predicate(item)) {
+        remove_item(&trash, &item.id)?;
+        purged += 1;
+    }
+    Ok(purged)
+}
+
+fn remove_item(trash: &Path, id: &str) -> io::Result<()> {
+    let stored = trash.join(FILES_DIR).join(id);
+    if fs::symlink_metadata(&stored).is_ok() {
+        fileops::remove_path(&stored)?;
+    }
+    fs::remove_file(info_path(trash, id))
+}
+
+fn read_item(trash: &Path, id: &str) -> io::Result<TrashItem> {
+    // ID 只允许十六进制数字和连字符，防止路径穿越
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_hexdigit() || c == '-') {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "非法的回收站 ID"));
+    }
+
+    let data = fs::read(info_path(trash, id))?;
+    serde_json::from_slice(&data).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn ensure_within_root(root: &Path, path: &Path) -> io::Result<()> {
+    if fs::canonicalize(path)?.starts_with(root) {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "访问被拒绝：路径超出根目录",
+        ))
+    }
+}
+
+fn info_path(trash: &Path, id: &str) -> PathBuf {
+    trash.join(INFO_DIR).join(format!("{}.json", id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::temp_dir;
+    use tempfile::TempDir;
+
+    /// 临时根目录，回收站要求根目录是规范路径
+    fn temp_root() -> (TempDir, PathBuf) {
+        let dir = temp_dir();
+        let root = fs::canonicalize(dir.path()).unwrap();
+        (dir, root)
+    }
+
+    /// 创建文件后立即删除到回收站，再在原位置创建同名文件
+    fn trash_with_collision(root: &Path) -> TrashItem {
+        let path = root.join("docs/a.txt");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "old").unwrap();
+        let item = move_to_trash(root, &path, Some("alice")).unwrap();
+        fs::write(&path, "new").unwrap();
+        item
+    }
+
+    #[test]
+    fn restore_fails_on_collision_by_default() {
+        let (_dir, root) = temp_root();
+        let item = trash_with_collision(&root);
+
+        let err = restore(&root, &item.id, OverwritePolicy::Fail, None).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read_to_string(root.join("docs/a.txt")).unwrap(), "new");
+        assert_eq!(list(&root).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn restore_renames_on_collision() {
+        let (_dir, root) = temp_root();
+        let item = trash_with_collision(&root);
+        fs::write(root.join("docs/a (1).txt"), "taken").unwrap();
+
+        let restored = restore(&root, &item.id, OverwritePolicy::Rename, None).unwrap();
+        assert_eq!(restored, root.join("docs/a (2).txt"));
+        assert_eq!(fs::read_to_string(&restored).unwrap(), "old");
+        assert_eq!(fs::read_to_string(root.join("docs/a.txt")).unwrap(), "new");
+        assert!(list(&root).unwrap().is_empty());
+    }
+
+    #[test]
+    fn restore_overwrite_moves_existing_to_trash() {
+        let (_dir, root) = temp_root();
+        let item = trash_with_collision(&root);
+
+        let restored = restore(&root, &item.id, OverwritePolicy::Overwrite, Some("bob")).unwrap();
+        assert_eq!(restored, root.join("docs/a.txt"));
+        assert_eq!(fs::read_to_string(&restored).unwrap(), "old");
+
+        // 被覆盖的文件留在回收站，可以再次恢复
+        let items = list(&root).unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].original_path, "docs/a.txt");
+        assert_eq!(items[0].deleted_by.as_deref(), Some("bob"));
+        let stored = root.join(TRASH_DIR).join(FILES_DIR).join(&items[0].id);
+        assert_eq!(fs::read_to_string(stored).unwrap(), "new");
+    }
+
+    #[test]
+    fn restore_does_not_overwrite_different_type() {
+        let (_dir, root) = temp_root();
+        let item = trash_with_collision(&root);
+        fs::remove_file(root.join("docs/a.txt")).unwrap();
+        fs::create_dir(root.join("docs/a.txt")).unwrap();
+
+        let err = restore(&root, &item.id, OverwritePolicy::Overwrite, None).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert!(root.join("docs/a.txt").is_dir());
+        assert_eq!(list(&root).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn restore_rejects_invalid_ids() {
+        let (_dir, root) = temp_root();
+        for id in ["", "../info", "abc/def", "xyz"] {
+            assert!(restore(&root, id, OverwritePolicy::Fail, None).is_err(), "{}", id);
+        }
+    }
+
+    #[test]
+    fn restore_rejects_tampered_original_path() {
+        let (_dir, root) = temp_root();
+        let item = trash_with_collision(&root);
+
+        let trash = root.join(TRASH_DIR);
+        for original_path in ["../outside.txt", "/etc/passwd", ".trash/files/x", ".uploads/x", ""] {
+            let tampered = TrashItem { original_path: original_path.to_string(), ..item.clone() };
+            fs::write(info_path(&trash, &item.id), serde_json::to_vec(&tampered).unwrap()).unwrap();
+            let err = restore(&root, &item.id, OverwritePolicy::Rename, None).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{}", original_path);
+        }
+        assert!(trash.join(FILES_DIR).join(&item.id).exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn restore_does_not_create_directories_through_symlinks() {
+        let (_dir, root) = temp_root();
+        let (_outside_dir, outside) = temp_root();
+        let item = trash_with_collision(&root);
+        std::os::unix::fs::symlink(&outside, root.join("link")).unwrap();
+
+        let trash = root.join(TRASH_DIR);
+        let tampered = TrashItem { original_path: "link/sub/a.txt".to_string(), ..item.clone() };
+        fs::write(info_path(&trash, &item.id), serde_json::to_vec(&tampered).unwrap()).unwrap();
+
+        let err = restore(&root, &item.id, OverwritePolicy::Rename, None).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        assert!(!outside.join("sub").exists());
+        assert!(trash.join(FILES_DIR).join(&item.id).exists());
+    }
+}
@@ -0,0 +1,663 @@
+use crate::{auth::CurrentUser, config::Permission, fileops, AppState, RootQuery};
+use axum::{
+    body::Body,
+    extract::{Path as AxumPath, Query, Request, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use futures::StreamExt;
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{digest::DynDigest, Digest, Sha256};
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Mutex, MutexGuard},
+    time::{Duration, SystemTime},
+};
+use tokio::io::AsyncWriteExt;
+use tracing::{info, warn};
+
+/// 每个根目录下保存未完成上传的目录名
+pub const UPLOAD_DIR: &str = ".uploads";
+
+/// 支持的 tus 协议版本
+const TUS_VERSION: &str = "1.0.0";
+
+/// 支持的 tus 扩展
+const TUS_EXTENSIONS: &str = "creation,termination,checksum,expiration";
+
+/// 支持的校验和算法
+const CHECKSUM_ALGORITHMS: &str = "sha1,sha256";
+
+/// 未完成的上传超过该时间没有写入新数据即被清理
+const UPLOAD_EXPIRY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// tus checksum 扩展定义的“校验和不匹配”状态码
+const CHECKSUM_MISMATCH: u16 = 460;
+
+const TUS_RESUMABLE: HeaderName = HeaderName::from_static("tus-resumable");
+const TUS_VERSION_HEADER: HeaderName = HeaderName::from_static("tus-version");
+const TUS_EXTENSION: HeaderName = HeaderName::from_static("tus-extension");
+const TUS_MAX_SIZE: HeaderName = HeaderName::from_static("tus-max-size");
+const TUS_CHECKSUM_ALGORITHM: HeaderName = HeaderName::from_static("tus-checksum-algorithm");
+const UPLOAD_OFFSET: HeaderName = HeaderName::from_static("upload-offset");
+const UPLOAD_LENGTH: HeaderName = HeaderName::from_static("upload-length");
+const UPLOAD_METADATA: HeaderName = HeaderName::from_static("upload-metadata");
+const UPLOAD_CHECKSUM: HeaderName = HeaderName::from_static("upload-checksum");
+const UPLOAD_EXPIRES: HeaderName = HeaderName::from_static("upload-expires");
+
+/// 客户端会发送的 tus 请求头，跨域时需要允许
+pub const REQUEST_HEADERS: [HeaderName; 5] = [
+    TUS_RESUMABLE,
+    UPLOAD_OFFSET,
+    UPLOAD_LENGTH,
+    UPLOAD_METADATA,
+    UPLOAD_CHECKSUM,
+];
+
+/// 客户端需要读取的 tus 响应头，跨域时需要暴露
+pub const RESPONSE_HEADERS: [HeaderName; 9] = [
+    header::LOCATION,
+    TUS_RESUMABLE,
+    TUS_VERSION_HEADER,
+    TUS_EXTENSION,
+    TUS_MAX_SIZE,
+    TUS_CHECKSUM_ALGORITHM,
+    UPLOAD_OFFSET,
+    UPLOAD_LENGTH,
+    UPLOAD_EXPIRES,
+];
+
+/// 未完成上传的信息，与临时文件一起保存在 `.uploads` 目录中
+#[derive(Debug, Serialize, Deserialize)]
+struct UploadInfo {
+    /// 上传完成后的目标路径（相对于根目录）
+    path: String,
+    length: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    owner: Option<String>,
+}
+
+impl UploadInfo {
+    /// 上传是否属于指定用户（未启用认证时所有上传都可见）
+    fn is_visible_to(&self, username: Option<&str>) -> bool {
+        self.owner.is_none() || self.owner.as_deref() == username
+    }
+}
+
+/// 正在写入的上传，同一上传同时只允许一个请求写入
+#[derive(Default)]
+pub struct UploadStore {
+    active: Mutex<HashSet<String>>,
+}
+
+impl UploadStore {
+    /// 独占指定上传，已被其他请求占用时返回 `None`
+    fn acquire(&self, id: &str) -> Option<UploadLock<'_>> {
+        if !self.lock().insert(id.to_string()) {
+            return None;
+        }
+        Some(UploadLock {
+            store: self,
+            id: id.to_string(),
+        })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashSet<String>> {
+        self.active.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// 上传的独占锁，离开作用域时释放
+struct UploadLock<'a> {
+    store: &'a UploadStore,
+    id: String,
+}
+
+impl Drop for UploadLock<'_> {
+    fn drop(&mut self) {
+        self.store.lock().remove(&self.id);
+    }
+}
+
+/// 在根目录的上传临时目录中分配一个临时文件路径
+///
+/// 普通表单上传先写入该文件，接收完毕后再重命名到目标位置，避免留下写了一半的文件。
+pub fn temp_path(root: &Path) -> io::Result<PathBuf> {
+    let upload_dir = root.join(UPLOAD_DIR);
+    fs::create_dir_all(&upload_dir)?;
+    Ok(data_path(&upload_dir, &new_id()))
+}
+
+/// 判断路径是否位于根目录的上传临时目录中
+pub fn is_in_uploads(root: &Path, path: &Path) -> bool {
+    path.starts_with(root.join(UPLOAD_DIR))
+}
+
+/// 处理 tus 能力查询（`OPTIONS /api/tus`）
+///
+/// CORS 层会把所有 OPTIONS 请求当作预检请求直接应答，所以该中间件需要挂在 CORS 层外面，
+/// 只处理不带 `Access-Control-Request-Method` 的普通 OPTIONS 请求。
+pub async fn handle_discovery(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    let is_discovery = req.method() == Method::OPTIONS
+        && req.uri().path() == "/api/tus"
+        && !req.headers().contains_key(header::ACCESS_CONTROL_REQUEST_METHOD);
+    if !is_discovery {
+        return next.run(req).await;
+    }
+
+    let mut headers = HeaderMap::new();
+    headers.insert(TUS_RESUMABLE, HeaderValue::from_static(TUS_VERSION));
+    headers.insert(TUS_VERSION_HEADER, HeaderValue::from_static(TUS_VERSION));
+    headers.insert(TUS_EXTENSION, HeaderValue::from_static(TUS_EXTENSIONS));
+    headers.insert(TUS_CHECKSUM_ALGORITHM, HeaderValue::from_static(CHECKSUM_ALGORITHMS));
+    if state.config.max_upload_size > 0 {
+        headers.insert(TUS_MAX_SIZE, HeaderValue::from(state.config.max_upload_size));
+    }
+
+    (StatusCode::NO_CONTENT, headers).into_response()
+}
+
+/// 处理创建上传请求
+///
+/// 文件名和目标目录通过 `Upload-Metadata` 的 `filename`（或 `name`）和 `path` 传入。
+pub async fn handle_create(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Query(root_params): Query<RootQuery>,
+    headers: HeaderMap,
+) -> Response {
+    with_tus_headers(create_upload(&state, &user, &root_params, &headers).await)
+}
+
+/// 处理上传进度查询
+pub async fn handle_head(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<String>,
+    Query(root_params): Query<RootQuery>,
+    headers: HeaderMap,
+) -> Response {
+    with_tus_headers(upload_offset(&state, &user, &root_params, &id, &headers))
+}
+
+/// 处理上传数据块
+pub async fn handle_patch(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<String>,
+    Query(root_params): Query<RootQuery>,
+    headers: HeaderMap,
+    body: Body,
+) -> Response {
+    with_tus_headers(append_chunk(&state, &user, &root_params, &id, &headers, body).await)
+}
+
+/// 处理取消上传请求
+pub async fn handle_terminate(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<String>,
+    Query(root_params): Query<RootQuery>,
+    headers: HeaderMap,
+) -> Response {
+    with_tus_headers(terminate_upload(&state, &user, &root_params, &id, &headers))
+}
+
+async fn create_upload(
+    state: &AppState,
+    user: &CurrentUser,
+    root_params: &RootQuery,
+    headers: &HeaderMap,
+) -> Result<Response, StatusCode> {
+    check_version(headers)?;
+    let root_index = crate::get_root_index_from_query(root_params);
+    let root_path = crate::get_root_path(state, user, root_index, Permission::Upload)?;
+
+    let length = header_u64(headers, &UPLOAD_LENGTH).ok_or(StatusCode::BAD_REQUEST)?;
+    let max_size = state.config.max_upload_size;
+    if max_size > 0 && length > max_size {
+        return Err(StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    let metadata = match headers.get(&UPLOAD_METADATA) {
+        Some(value) => parse_metadata(value.to_str().map_err(|_| StatusCode::BAD_REQUEST)?)?,
+        None => HashMap::new(),
+    };
+    let name = metadata
+        .get("filename")
+        .or_else(|| metadata.get("name"))
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let directory = metadata.get("path").map(String::as_str).unwrap_or("/");
+
+    let target = crate::resolve_new_path(
+        &root_path,
+        &format!("{}/{}", directory.trim_end_matches('/'), name),
+    )?;
+    if crate::is_reserved_path(&root_path, &target) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if fs::symlink_metadata(&target).is_ok() {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let upload_dir = root_path.join(UPLOAD_DIR);
+    fs::create_dir_all(&upload_dir).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let id = new_id();
+    let relative = pathdiff::diff_paths(&target, &root_path).unwrap_or_else(|| target.clone());
+    let upload = UploadInfo {
+        path: relative.to_string_lossy().into_owned(),
+        length,
+        owner: user.0.clone(),
+    };
+
+    fs::write(data_path(&upload_dir, &id), b"").map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let info = serde_json::to_vec_pretty(&upload).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    fs::write(info_path(&upload_dir, &id), info).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    info!("创建上传 {}: {} ({} 字节)", id, upload.path, length);
+
+    // 空文件不会再收到数据块，直接完成
+    if length == 0 {
+        finish_upload(&root_path, &upload_dir, &id, &upload)?;
+    }
+
+    let location = HeaderValue::from_str(&format!("/api/tus/{}?root={}", id, root_index))
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert(header::LOCATION, location);
+    response_headers.insert(UPLOAD_OFFSET, HeaderValue::from(0u64));
+
+    Ok((StatusCode::CREATED, response_headers).into_response())
+}
+
+fn upload_offset(
+    state: &AppState,
+    user: &CurrentUser,
+    root_params: &RootQuery,
+    id: &str,
+    headers: &HeaderMap,
+) -> Result<Response, StatusCode> {
+    check_version(headers)?;
+    let root_index = crate::get_root_index_from_query(root_params);
+    let root_path = crate::get_root_path(state, user, root_index, Permission::Upload)?;
+
+    let upload_dir = root_path.join(UPLOAD_DIR);
+    let upload = read_info(&upload_dir, id, user)?;
+    let metadata = fs::metadata(data_path(&upload_dir, id)).map_err(|_| StatusCode::NOT_FOUND)?;
+
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert(UPLOAD_OFFSET, HeaderValue::from(metadata.len()));
+    response_headers.insert(UPLOAD_LENGTH, HeaderValue::from(upload.length));
+    response_headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    insert_expires(&mut response_headers, &metadata);
+
+    Ok((StatusCode::OK, response_headers).into_response())
+}
+
+async fn append_chunk(
+    state: &AppState,
+    user: &CurrentUser,
+    root_params: &RootQuery,
+    id: &str,
+    headers: &HeaderMap,
+    body: Body,
+) -> Result<Response, StatusCode> {
+    check_version(headers)?;
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    if content_type != "application/offset+octet-stream" {
+        return Err(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+    let offset = header_u64(headers, &UPLOAD_OFFSET).ok_or(StatusCode::BAD_REQUEST)?;
+    let checksum = headers.get(&UPLOAD_CHECKSUM).map(parse_checksum).transpose()?;
+
+    let root_index = crate::get_root_index_from_query(root_params);
+    let root_path = crate::get_root_path(state, user, root_index, Permission::Upload)?;
+    let upload_dir = root_path.join(UPLOAD_DIR);
+    let upload = read_info(&upload_dir, id, user)?;
+
+    let _lock = state.uploads.acquire(id).ok_or(StatusCode::LOCKED)?;
+
+    let data_path = data_path(&upload_dir, id);
+    let mut file = tokio::fs::OpenOptions::new()
+        .append(true)
+        .open(&data_path)
+        .await
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+    let current = file
+        .metadata()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .len();
+    if offset != current {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let (mut hasher, expected) = match checksum {
+        Some((hasher, expected)) => (Some(hasher), Some(expected)),
+        None => (None, None),
+    };
+
+    // 边接收边写入临时文件，不在内存中缓存整个数据块
+    let mut written = 0u64;
+    let mut stream = body.into_data_stream();
+    let received: Result<(), StatusCode> = async {
+        while let Some(chunk) = stream.next().await {
+            // 读取失败说明客户端中途断开
+            let chunk = chunk.map_err(|_| StatusCode::BAD_REQUEST)?;
+            if offset + written + chunk.len() as u64 > upload.length {
+                return Err(StatusCode::PAYLOAD_TOO_LARGE);
+            }
+            file.write_all(&chunk)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            if let Some(hasher) = hasher.as_mut() {
+                hasher.update(&chunk);
+            }
+            written += chunk.len() as u64;
+        }
+        file.flush().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+    }
+    .await;
+
+    if let Err(status) = received {
+        // 没有校验和时保留已收到的数据，客户端可以从新的偏移量继续上传
+        if expected.is_some() || status != StatusCode::BAD_REQUEST {
+            file.set_len(offset)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        }
+        return Err(status);
+    }
+
+    if let (Some(hasher), Some(expected)) = (hasher, expected) {
+        if *hasher.finalize() != *expected {
+            warn!("上传 {} 的数据块校验和不匹配，已丢弃", id);
+            file.set_len(offset)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            let status = StatusCode::from_u16(CHECKSUM_MISMATCH)
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            return Ok((status, "校验和不匹配").into_response());
+        }
+    }
+
+    let metadata = file
+        .metadata()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    drop(file);
+
+    let new_offset = offset + written;
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert(UPLOAD_OFFSET, HeaderValue::from(new_offset));
+    if new_offset == upload.length {
+        finish_upload(&root_path, &upload_dir, id, &upload)?;
+    } else {
+        insert_expires(&mut response_headers, &metadata);
+    }
+
+    Ok((StatusCode::NO_CONTENT, response_headers).into_response())
+}
+
+fn terminate_upload(
+    state: &AppState,
+    user: &CurrentUser,
+    root_params: &RootQuery,
+    id: &str,
+    headers: &HeaderMap,
+) -> Result<Response, StatusCode> {
+    check_version(headers)?;
+    let root_index = crate::get_root_index_from_query(root_params);
+    let root_path = crate::get_root_path(state, user, root_index, Permission::Upload)?;
+    let upload_dir = root_path.join(UPLOAD_DIR);
+    let upload = read_info(&upload_dir, id, user)?;
+
+    let _lock = state.uploads.acquire(id).ok_or(StatusCode::LOCKED)?;
+    remove_upload(&upload_dir, id).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    info!("取消上传 {}: {}", id, upload.path);
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+/// 上传完成后把临时文件重命名到目标位置
+fn finish_upload(
+    root_path: &Path,
+    upload_dir: &Path,
+    id: &str,
+    upload: &UploadInfo,
+) -> Result<PathBuf, StatusCode> {
+    // 目标目录可能在上传期间被删除或替换，重新校验
+    let target = crate::resolve_new_path(root_path, &upload.path)?;
+    // 上传期间同名文件被其他请求创建时另存为副本，避免丢弃已上传的数据
+    let target = if fs::symlink_metadata(&target).is_ok() {
+        fileops::unique_path(&target)
+    } else {
+        target
+    };
+
+    fileops::move_path(&data_path(upload_dir, id), &target)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let _ = fs::remove_file(info_path(upload_dir, id));
+    info!("上传完成 {}: {}", id, target.display());
+
+    Ok(target)
+}
+
+/// 清理长时间没有写入新数据的未完成上传，返回清理的数量
+pub fn purge_expired(root: &Path) -> io::Result<usize> {
+    let upload_dir = root.join(UPLOAD_DIR);
+    let entries = match fs::read_dir(&upload_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+
+    let cutoff = SystemTime::now() - UPLOAD_EXPIRY;
+    let mut purged = 0;
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        let Some(id) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+
+        // 没有信息文件的临时文件来自中断的表单上传
+        if path.extension().is_some_and(|ext| ext == "part") {
+            if !info_path(&upload_dir, id).exists() && entry.metadata()?.modified()? < cutoff {
+                fs::remove_file(&path)?;
+                purged += 1;
+            }
+            continue;
+        }
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+
+        // 以临时文件的最后写入时间为准，正在进行的上传不会被清理
+        let last_write = fs::metadata(data_path(&upload_dir, id))
+            .or_else(|_| entry.metadata())
+            .and_then(|m| m.modified())?;
+        if last_write < cutoff {
+            remove_upload(&upload_dir, id)?;
+            purged += 1;
+        }
+    }
+
+    Ok(purged)
+}
+
+fn remove_upload(upload_dir: &Path, id: &str) -> io::Result<()> {
+    match fs::remove_file(data_path(upload_dir, id)) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+        _ => {}
+    }
+    fs::remove_file(info_path(upload_dir, id))
+}
+
+fn read_info(upload_dir: &Path, id: &str, user: &CurrentUser) -> Result<UploadInfo, StatusCode> {
+    // ID 只允许十六进制数字，防止路径穿越
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let data = fs::read(info_path(upload_dir, id)).map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => StatusCode::NOT_FOUND,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    })?;
+    let upload: UploadInfo =
+        serde_json::from_slice(&data).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    // 其他用户的上传按不存在处理
+    if !upload.is_visible_to(user.name()) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(upload)
+}
+
+/// 生成随机的上传 ID
+fn new_id() -> String {
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn data_path(upload_dir: &Path, id: &str) -> PathBuf {
+    upload_dir.join(format!("{}.part", id))
+}
+
+fn info_path(upload_dir: &Path, id: &str) -> PathBuf {
+    upload_dir.join(format!("{}.json", id))
+}
+
+/// 为响应补充 tus 协议要求的头
+fn with_tus_headers(result: Result<Response, StatusCode>) -> Response {
+    let mut response = result.unwrap_or_else(tus_error);
+    let version_mismatch = response.status() == StatusCode::PRECONDITION_FAILED;
+    let headers = response.headers_mut();
+    headers.insert(TUS_RESUMABLE, HeaderValue::from_static(TUS_VERSION));
+    if version_mismatch {
+        headers.insert(TUS_VERSION_HEADER, HeaderValue::from_static(TUS_VERSION));
+    }
+    response
+}
+
+/// tus 协议特有的错误补充说明，其他错误由全局的错误处理补充
+fn tus_error(status: StatusCode) -> Response {
+    let message = match status {
+        StatusCode::PRECONDITION_FAILED => "不支持的 tus 协议版本",
+        StatusCode::LOCKED => "上传正在进行中",
+        _ => return status.into_response(),
+    };
+    let body = Json(crate::ErrorResponse {
+        success: false,
+        message: message.to_string(),
+    });
+    (status, body).into_response()
+}
+
+/// 检查客户端使用的协议版本
+fn check_version(headers: &HeaderMap) -> Result<(), StatusCode> {
+    match headers.get(&TUS_RESUMABLE) {
+        Some(version) if version == TUS_VERSION => Ok(()),
+        _ => Err(StatusCode::PRECONDITION_FAILED),
+    }
+}
+
+fn header_u64(headers: &HeaderMap, name: &HeaderName) -> Option<u64> {
+    headers.get(name)?.to_str().ok()?.trim().parse().ok()
+}
+
+fn insert_expires(headers: &mut HeaderMap, metadata: &fs::Metadata) {
+    if let Ok(modified) = metadata.modified() {
+        if let Ok(value) = HeaderValue::from_str(&httpdate::fmt_http_date(modified + UPLOAD_EXPIRY)) {
+            headers.insert(UPLOAD_EXPIRES, value);
+        }
+    }
+}
+
+/// 解析 `Upload-Metadata`：逗号分隔的 `key base64(value)` 列表
+fn parse_metadata(value: &str) -> Result<HashMap<String, String>, StatusCode> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, encoded) = pair.split_once(' ').unwrap_or((pair, ""));
+            let decoded = STANDARD
+                .decode(encoded.trim())
+                .map_err(|_| StatusCode::BAD_REQUEST)?;
+            let value = String::from_utf8(decoded).map_err(|_| StatusCode::BAD_REQUEST)?;
+            Ok((key.to_string(), value))
+        })
+        .collect()
+}
+
+/// 解析 `Upload-Checksum`：`算法 base64(摘要)`
+fn parse_checksum(value: &HeaderValue) -> Result<(Box<dyn DynDigest + Send>, Vec<u8>), StatusCode> {
+    let (algorithm, encoded) = value
+        .to_str()
+        .ok()
+        .and_then(|v| v.trim().split_once(' '))
+        .ok_or(StatusCode::BAD_REQUEST)?;
+
+    let hasher: Box<dyn DynDigest + Send> = match algorithm {
+        "sha1" => Box::new(Sha1::new()),
+        "sha256" => Box::new(Sha256::new()),
+        _ => return Err(StatusCode::BAD_REQUEST),
+    };
+    let expected = STANDARD
+        .decode(encoded.trim())
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    Ok((hasher, expected))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_metadata_pairs() {
+        let metadata = parse_metadata("filename d29ybGRfZG9taW5hdGlvbl9wbGFuLnBkZg==, path 5paH5qGjL+aKpeWRig==").unwrap();
+        assert_eq!(metadata.len(), 2);
+        assert_eq!(metadata["filename"], "world_domination_plan.pdf");
+        assert_eq!(metadata["path"], "文档/报告");
+    }
+
+    #[test]
+    fn parses_metadata_keys_without_value() {
+        let metadata = parse_metadata("is_confidential,filename YS50eHQ=,").unwrap();
+        assert_eq!(metadata["is_confidential"], "");
+        assert_eq!(metadata["filename"], "a.txt");
+        assert!(parse_metadata("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_malformed_metadata() {
+        assert_eq!(parse_metadata("filename not-base64!").unwrap_err(), StatusCode::BAD_REQUEST);
+        // 解码结果必须是 UTF-8
+        assert_eq!(parse_metadata("filename /w==").unwrap_err(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn parses_checksum_header() {
+        let (_, expected) = parse_checksum(&HeaderValue::from_static("sha1 Kq5sNclPz7QV2+lfQIuc6R7oRu0=")).unwrap();
+        assert_eq!(expected.len(), 20);
+
+        for value in ["md5 Kq5sNclPz7QV2+lfQIuc6R7oRu0=", "sha256", "sha256 ???"] {
+            assert!(parse_checksum(&HeaderValue::from_static(value)).is_err(), "{}", value);
+        }
+    }
+}
@@ -6,12 +6,41 @@ let currentPage = 1;
 let totalPages = 1;
 // 当前查看的文件路径
 let currentFilePath = '';
-// 每页显示的行数（需要与后端保持一致）
-const LinesPerPage = 1000;
 // 当前搜索结果
 let currentSearchResults = [];
 // 当前搜索结果索引
 let currentSearchIndex = -1;
+// 当前搜索的匹配总数和下一页偏移（没有更多结果时为 null）
+let currentSearchTotal = 0;
+let currentSearchNextOffset = null;
+let currentSearchParams = null;
+// 正在进行的流式搜索，取消时断开连接让服务器停止扫描
+let currentSearchAbort = null;
+// 搜索进度文字，搜索结束后为 null
+let currentSearchProgress = null;
+// 每次搜索加载的结果数量
+const SearchPageSize = 500;
+// 正在进行的实时跟踪
+let followAbort = null;
+// 实时跟踪时先显示的末尾行数
+const FollowInitialLines = 1000;
+// 实时跟踪时最多保留的行数
+const FollowMaxLines = 5000;
+// 分段查看时当前段的起始字节偏移，按行分页时为 null
+let byteViewOffset = null;
+// 分段查看每段读取的字节数
+const ByteViewLength = 256 * 1024;
+// 十六进制查看时当前段的起始偏移，不在十六进制模式时为 null
+let hexOffset = null;
+// 十六进制查看每段的字节数
+const HexWindowSize = 4096;
+// 当前查找的字节序列和定位到的匹配
+let hexPattern = { q: '', mode: 'hex' };
+let hexMatch = null;
+// 查看时指定的编码，为空时由服务器自动检测
+let currentEncoding = '';
+// 当前文件实际使用的编码，保存时按此编码写回
+let currentFileEncoding = 'UTF-8';
 // 当前根目录索引
 let currentRootIndex = 0;
 // 所有根目录配置
@@ -88,6 +117,22 @@ function isTextFile(extension) {
     return textExtensions.includes(extension.toLowerCase());
 }
 
+// 工具函数：获取文件扩展名，压缩日志（如 app.log.gz）取压缩前的扩展名
+function fileExtension(path) {
+    const name = path.split('/').pop().replace(/\.(gz|zst)$/i, '');
+    return name.includes('.') ? name.split('.').pop().toLowerCase() : '';
+}
+
+// 工具函数：判断是否为可以浏览和解压的压缩包
+function isArchiveFile(name) {
+    return /\.(zip|tar|tar\.gz|tgz|tar\.zst|tzst)$/i.test(name);
+}
+
+// 工具函数：判断路径是否位于压缩包内部
+function isInArchive(path) {
+    return path.split('/').slice(0, -1).some(isArchiveFile);
+}
+
 // 工具函数：获取文件图标
 function getFileIcon(isDir, extension) {
     const size = 'width="14" height="14" viewBox="0 0 16 16" fill="currentColor"';
@@ -144,6 +189,120 @@ function showError(message) {
     alert('错误: ' + message);
 }
 
+// 拦截 401 响应，会话失效时弹出登录框
+const nativeFetch = window.fetch.bind(window);
+window.fetch = async (input, init) => {
+    const response = await nativeFetch(input, init);
+    if (response.status === 401 && !String(input).startsWith('/api/login')) {
+        showLoginModal();
+    }
+    return response;
+};
+
+// 查询会话状态，返回是否可以继续加载页面
+async function checkSession() {
+    try {
+        const response = await fetch('/api/session');
+        const session = await response.json();
+        updateCurrentUser(session.username);
+        if (session.authEnabled && !session.username) {
+            showLoginModal();
+            return false;
+        }
+    } catch (error) {
+        console.error('查询会话失败:', error);
+    }
+    return true;
+}
+
+// 更新标题栏中的当前用户
+function updateCurrentUser(username) {
+    const currentUserEl = document.getElementById('currentUser');
+    const logoutBtn = document.getElementById('logoutBtn');
+    if (!currentUserEl || !logoutBtn) return;
+
+    currentUserEl.textContent = username || '';
+    currentUserEl.style.display = username ? 'inline' : 'none';
+    logoutBtn.style.display = username ? 'inline-flex' : 'none';
+}
+
+// 显示登录框
+function showLoginModal() {
+    if (document.getElementById('loginModal')) return;
+
+    const modal = document.createElement('div');
+    modal.id = 'loginModal';
+    modal.className = 'modal';
+    modal.innerHTML = `
+        <div class="modal-content login-content">
+            <div class="modal-header">
+                <h3>登录</h3>
+            </div>
+            <form class="modal-body login-form" id="loginForm">
+                <input type="text" id="loginUsername" class="login-input" placeholder="用户名" autocomplete="username" required>
+                <input type="password" id="loginPassword" class="login-input" placeholder="密码" autocomplete="current-password" required>
+                <div class="login-error" id="loginError"></div>
+                <button type="submit" class="btn btn-primary">登录</button>
+            </form>
+        </div>
+    `;
+    document.body.appendChild(modal);
+    modal.style.display = 'flex';
+
+    document.getElementById('loginForm').addEventListener('submit', async (e) => {
+        e.preventDefault();
+        await login(
+            document.getElementById('loginUsername').value,
+            document.getElementById('loginPassword').value
+        );
+    });
+    document.getElementById('loginUsername').focus();
+}
+
+// 登录
+async function login(username, password) {
+    const loginError = document.getElementById('loginError');
+    try {
+        const response = await fetch('/api/login', {
+            method: 'POST',
+            headers: {
+                'Content-Type': 'application/json',
+            },
+            body: JSON.stringify({ username, password }),
+        });
+
+        if (!response.ok) {
+            throw new Error(response.status === 401 ? '用户名或密码错误' : '登录失败');
+        }
+
+        const result = await response.json();
+        updateCurrentUser(result.username);
+        const modal = document.getElementById('loginModal');
+        if (modal) {
+            modal.remove();
+        }
+
+        await loadRoots();
+        await loadDirectory(currentPath);
+    } catch (error) {
+        if (loginError) {
+            loginError.textContent = error.message;
+        }
+    }
+}
+
+// 注销
+async function logout() {
+    try {
+        await fetch('/api/logout', { method: 'POST' });
+    } finally {
+        updateCurrentUser(null);
+        currentPath = '/';
+        fileList.innerHTML = '';
+        showLoginModal();
+    }
+}
+
 // 加载根目录列表
 async function loadRoots() {
     try {
@@ -158,6 +317,28 @@ async function loadRoots() {
     }
 }
 
+// 判断当前根目录是否允许指定操作（read/write/create/delete/upload/download）
+function rootAllows(permission) {
+    const root = rootDirs[currentRootIndex];
+    return !root || !root.permissions || root.permissions[permission] !== false;
+}
+
+// 根据当前根目录的权限显示或隐藏工具栏按钮
+function updateToolbarPermissions() {
+    const toggles = {
+        createFileBtn: 'create',
+        createDirBtn: 'create',
+        uploadBtn: 'upload',
+        uploadDirBtn: 'upload',
+    };
+    Object.entries(toggles).forEach(([id, permission]) => {
+        const btn = document.getElementById(id);
+        if (btn) {
+            btn.style.display = rootAllows(permission) ? '' : 'none';
+        }
+    });
+}
+
 // 更新根目录选择器
 function updateRootSelect() {
     // 重新获取元素引用
@@ -228,6 +409,7 @@ async function loadDirectory(path, rootIndex = currentRootIndex, hideContent = t
         currentRootIndex = rootIndex;
         renderFileList(files);
         updateBreadcrumb(path);
+        updateToolbarPermissions();
 
         // 更新根目录选择器
         rootSelect.value = currentRootIndex;
@@ -286,7 +468,44 @@ function renderFileList(files) {
     `;
 
     files.forEach(file => {
-        const actionButtons = file.isDir ? '' : `
+        // 压缩包中的条目只能浏览
+        const extractButton = file.inArchive || file.isDir || !isArchiveFile(file.name) || !rootAllows('create') ? '' : `
+            <button class="btn-small btn-extract-list btn-action" data-path="${file.path}" data-action="extract" title="解压">
+                <svg width="16" height="16" viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2" stroke-linecap="round" stroke-linejoin="round" style="color: #a074c4;">
+                    <polyline points="21 8 21 21 3 21 3 8"/>
+                    <rect x="1" y="3" width="22" height="5"/>
+                    <line x1="10" y1="12" x2="14" y2="12"/>
+                </svg>
+            </button>
+        `;
+        const renameButton = file.inArchive || !rootAllows('delete') || !rootAllows('create') ? '' : `
+            <button class="btn-small btn-rename-list btn-action" data-path="${file.path}" data-action="rename" title="重命名">
+                <svg width="16" height="16" viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2" stroke-linecap="round" stroke-linejoin="round" style="color: #75beff;">
+                    <path d="M12 20h9"/>
+                    <path d="M16.5 3.5a2.121 2.121 0 0 1 3 3L7 19l-4 1 1-4L16.5 3.5z"/>
+                </svg>
+            </button>
+        `;
+        const archiveButton = file.inArchive || !file.isDir || !rootAllows('download') ? '' : `
+            <button class="btn-small btn-archive-list btn-action" data-path="${file.path}" data-action="archive" title="打包下载">
+                <svg width="16" height="16" viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2" stroke-linecap="round" stroke-linejoin="round" style="color: #3794ff;">
+                    <path d="M21 15v4a2 2 0 0 1-2 2H5a2 2 0 0 1-2-2v-4"/>
+                    <polyline points="7 10 12 15 17 10"/>
+                    <line x1="12" y1="15" x2="12" y2="3"/>
+                </svg>
+            </button>
+        `;
+        const hexButton = file.inArchive || file.isDir ? '' : `
+            <button class="btn-small btn-hex-list btn-action" data-path="${file.path}" data-action="hex" title="十六进制查看">
+                <svg width="16" height="16" viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2" stroke-linecap="round" stroke-linejoin="round" style="color: #d7ba7d;">
+                    <rect x="3" y="4" width="18" height="16" rx="2"/>
+                    <line x1="7" y1="9" x2="17" y2="9"/>
+                    <line x1="7" y1="13" x2="17" y2="13"/>
+                    <line x1="7" y1="17" x2="12" y2="17"/>
+                </svg>
+            </button>
+        `;
+        const actionButtons = hexButton + extractButton + archiveButton + renameButton + (file.inArchive || !rootAllows('delete') ? '' : `
             <button class="btn-small btn-delete-list btn-action" data-path="${file.path}" data-action="delete" title="删除">
                 <svg width="16" height="16" viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2" stroke-linecap="round" stroke-linejoin="round" style="color: #f48771;">
                     <polyline points="3 6 5 6 21 6"/>
@@ -295,10 +514,10 @@ function renderFileList(files) {
                     <line x1="14" y1="11" x2="14" y2="17"/>
                 </svg>
             </button>
-        `;
+        `);
 
         html += `
-            <div class="file-item" data-path="${file.path}" data-is-dir="${file.isDir}">
+            <div class="file-item" data-path="${file.path}" data-is-dir="${file.isDir}" data-in-archive="${!!file.inArchive}">
                 <div class="file-icon">${getFileIcon(file.isDir, file.extension)}</div>
                 <div class="file-name-cell">${file.name}</div>
                 <div class="file-actions">${actionButtons}</div>
@@ -313,13 +532,17 @@ function renderFileList(files) {
         item.addEventListener('click', () => {
             const path = item.getAttribute('data-path');
             const isDir = item.getAttribute('data-is-dir') === 'true';
-            const extension = path.split('.').pop().toLowerCase();
+            const inArchive = item.getAttribute('data-in-archive') === 'true';
+            const extension = fileExtension(path);
 
-            if (isDir) {
+            if (isDir || (!inArchive && isArchiveFile(path))) {
+                // 目录和压缩包：浏览内容
                 loadDirectory(path);
             } else if (isTextFile(extension)) {
                 // 文本文件：查看内容
                 viewFile(path);
+            } else if (inArchive) {
+                showError('压缩包中的非文本文件无法直接查看，请先解压');
             } else {
                 // 非文本文件：直接下载
                 downloadFile(path);
@@ -336,6 +559,14 @@ function renderFileList(files) {
 
             if (action === 'delete') {
                 deleteFileFromList(path);
+            } else if (action === 'rename') {
+                renameItem(path);
+            } else if (action === 'archive') {
+                downloadArchive([path]);
+            } else if (action === 'extract') {
+                extractArchive(path);
+            } else if (action === 'hex') {
+                viewHex(path);
             }
         });
     });
@@ -343,6 +574,10 @@ function renderFileList(files) {
 
 // 下载文件
 function downloadFile(path) {
+    if (!rootAllows('download')) {
+        showError('当前根目录不允许下载');
+        return;
+    }
     // 规范化路径
     path = normalizePath(path);
     // 创建下载链接
@@ -356,12 +591,69 @@ function downloadFile(path) {
     document.body.removeChild(link);
 }
 
+// 把文件或目录打包下载（format: zip 或 tar.gz）
+function downloadArchive(paths, format = 'zip') {
+    if (!rootAllows('download')) {
+        showError('当前根目录不允许下载');
+        return;
+    }
+    const params = new URLSearchParams({ root: currentRootIndex, format });
+    paths.forEach(path => params.append('path', normalizePath(path)));
+
+    // 由浏览器直接下载，边打包边接收
+    const link = document.createElement('a');
+    link.href = `/api/archive?${params.toString()}`;
+    document.body.appendChild(link);
+    link.click();
+    document.body.removeChild(link);
+}
+
+// 在服务器上解压压缩包，目标目录默认为与压缩包同名的目录
+async function extractArchive(path) {
+    path = normalizePath(path);
+    const parts = path.split('/');
+    const name = parts.pop();
+    const stem = name.replace(/\.(zip|tar|tar\.gz|tgz|tar\.zst|tzst)$/i, '');
+
+    const destination = prompt('解压到目录:', [...parts, stem].join('/') || '/');
+    if (!destination) return;
+    const overwrite = rootAllows('write') && confirm('是否覆盖已存在的文件？\n选择"取消"将跳过已存在的文件。');
+
+    try {
+        showLoading();
+        const response = await fetch(`/api/extract?root=${currentRootIndex}`, {
+            method: 'POST',
+            headers: {
+                'Content-Type': 'application/json',
+            },
+            body: JSON.stringify({ path, destination, overwrite }),
+        });
+
+        if (!response.ok) {
+            throw new Error(await responseErrorMessage(response, '解压失败'));
+        }
+
+        const data = await response.json();
+        alert(data.message);
+        await loadDirectory(currentPath);
+    } catch (error) {
+        showError(error.message);
+    } finally {
+        hideLoading();
+    }
+}
+
 // 查看文件内容
 async function viewFile(path, page = 1) {
+    stopFollow();
     try {
         showLoading();
         // 规范化路径
         path = normalizePath(path);
+        // 打开其他文件时恢复自动检测编码
+        if (path !== currentFilePath) {
+            currentEncoding = '';
+        }
         currentFilePath = path; // 保存当前文件路径
 
         // 从文件路径中提取目录路径，保存到 currentPath
@@ -372,7 +664,7 @@ async function viewFile(path, page = 1) {
         // 更新面包屑导航
         updateBreadcrumb(currentPath);
 
-        const url = `/api/view?path=${encodeURIComponent(path)}&page=${page}&root=${currentRootIndex}`;
+        const url = `/api/view?path=${encodeURIComponent(path)}&page=${page}&root=${currentRootIndex}${encodingParam()}`;
         const response = await fetch(url);
 
         if (!response.ok) {
@@ -403,8 +695,48 @@ async function viewFile(path, page = 1) {
     }
 }
 
+// 按字节偏移分段查看文件，超长的行会拆成多段显示
+async function viewFileBytes(path, offset = 0) {
+    stopFollow();
+    try {
+        showLoading();
+        path = normalizePath(path);
+        if (path !== currentFilePath) {
+            currentEncoding = '';
+        }
+        currentFilePath = path;
+
+        const pathParts = path.split('/');
+        pathParts.pop();
+        currentPath = pathParts.join('/') || '/';
+        updateBreadcrumb(currentPath);
+
+        const url = `/api/view?path=${encodeURIComponent(path)}&offset=${offset}&length=${ByteViewLength}&root=${currentRootIndex}${encodingParam()}`;
+        const response = await fetch(url);
+        if (!response.ok) {
+            throw new Error(await responseErrorMessage(response, '读取文件失败'));
+        }
+
+        const data = await response.json();
+        isJsonFile = path.toLowerCase().endsWith('.json');
+
+        renderFileContent(data);
+        fileContent.scrollTop = 0;
+        showContentView();
+
+        if (fileList.innerHTML === '' || fileList.children.length === 0) {
+            loadDirectory(currentPath, currentRootIndex, false);
+        }
+    } catch (error) {
+        showError(error.message);
+    } finally {
+        hideLoading();
+    }
+}
+
 // 查看文件并滚动到指定行
 async function viewFileAndScroll(path, page, lineNumber) {
+    stopFollow();
     try {
         showLoading();
         currentFilePath = path;
@@ -417,7 +749,7 @@ async function viewFileAndScroll(path, page, lineNumber) {
         // 更新面包屑导航
         updateBreadcrumb(currentPath);
 
-        const url = `/api/view?path=${encodeURIComponent(path)}&page=${page}&root=${currentRootIndex}`;
+        const url = `/api/view?path=${encodeURIComponent(path)}&page=${page}&root=${currentRootIndex}${encodingParam()}`;
         const response = await fetch(url);
 
         if (!response.ok) {
@@ -445,6 +777,74 @@ async function viewFileAndScroll(path, page, lineNumber) {
     }
 }
 
+// 跳转到指定行号或时间：纯数字为行号，`HH:MM[:SS]` 为今天，`昨天 HH:MM[:SS]` 为昨天，也可以输入完整的日期时间
+async function gotoLineOrTime(path, input) {
+    input = input.trim();
+    let param;
+    if (/^\d+$/.test(input)) {
+        param = `line=${input}`;
+    } else {
+        const time = parseJumpTime(input);
+        if (!time) {
+            showError('请输入行号或时间，如 1200、14:02、昨天 23:50、2024-03-01 14:02:00');
+            return;
+        }
+        param = `time=${encodeURIComponent(time)}`;
+    }
+
+    stopFollow();
+    try {
+        showLoading();
+        const url = `/api/view?path=${encodeURIComponent(path)}&${param}&root=${currentRootIndex}${encodingParam()}`;
+        const response = await fetch(url);
+        if (!response.ok) {
+            throw new Error(await responseErrorMessage(response, '跳转失败'));
+        }
+
+        const data = await response.json();
+        currentPage = data.page;
+        totalPages = data.totalPages;
+        currentFileContent = data.lines;
+
+        renderFileContent(data);
+        showContentView();
+
+        if (data.highlightLine) {
+            requestAnimationFrame(() => {
+                setTimeout(() => {
+                    scrollToLine(data.highlightLine);
+                }, 50);
+            });
+        } else {
+            fileContent.scrollTop = fileContent.scrollHeight;
+            showError(`${input} 之后没有日志，已显示最后一页`);
+        }
+    } catch (error) {
+        showError(error.message);
+    } finally {
+        hideLoading();
+    }
+}
+
+// 把跳转输入的时间转换为 `YYYY-MM-DD HH:MM:SS`，无法识别时返回 null
+function parseJumpTime(input) {
+    const full = input.match(/^(\d{4}-\d{2}-\d{2})[ T](\d{1,2}):(\d{2})(?::(\d{2}))?$/);
+    if (full) {
+        return `${full[1]} ${full[2].padStart(2, '0')}:${full[3]}:${full[4] || '00'}`;
+    }
+
+    const short = input.match(/^(昨天\s*)?(\d{1,2}):(\d{2})(?::(\d{2}))?$/);
+    if (!short) {
+        return null;
+    }
+    const date = new Date();
+    if (short[1]) {
+        date.setDate(date.getDate() - 1);
+    }
+    const pad = n => String(n).padStart(2, '0');
+    return `${date.getFullYear()}-${pad(date.getMonth() + 1)}-${pad(date.getDate())} ${short[2].padStart(2, '0')}:${short[3]}:${short[4] || '00'}`;
+}
+
 // 滚动到指定行并高亮显示
 function scrollToLine(lineNumber) {
     const lineElement = fileContent.querySelector(`[data-line-number="${lineNumber}"]`);
@@ -489,18 +889,35 @@ function scrollToLine(lineNumber) {
 // 渲染文件内容
 function renderFileContent(data) {
     fileName.textContent = data.name;
-    fileInfo.textContent = `${formatSize(data.size)} • ${data.totalLines.toLocaleString()} 行`;
+    fileInfo.textContent = `${formatSize(data.size)} • ${data.totalLines.toLocaleString()} 行 • ${data.encoding}`;
+    currentFileEncoding = data.encoding;
+    document.getElementById('encodingSelect').value = currentEncoding;
+    leaveHexMode();
+
+    // 分段查看时没有行号，接续上一行的内容前显示标记
+    const byteMode = data.byteStart !== undefined;
+    byteViewOffset = byteMode ? data.byteStart : null;
+    document.getElementById('byteModeBtn').classList.toggle('active', byteMode);
+
+    let linesHtml;
+    if (byteMode) {
+        fileInfo.textContent += ` • 字节 ${data.byteStart.toLocaleString()} - ${data.byteEnd.toLocaleString()} / ${data.contentSize.toLocaleString()}`;
+        linesHtml = data.lines.map((line, index) =>
+            `<div class="file-line${data.continued[index] ? ' line-continued' : ''}">${escapeHtml(line)}</div>`
+        ).join('');
+    } else {
+        if (data.isPartial) {
+            fileInfo.textContent += ` • 第 ${data.page}/${data.totalPages} 页`;
+        }
 
-    if (data.isPartial) {
-        fileInfo.textContent += ` • 第 ${data.page}/${data.totalPages} 页`;
+        // 显示内容并标记行号（只读模式）
+        linesHtml = data.lines.map((line, index) => {
+            // 每页行数由服务器根据配置决定，不分页时不返回
+            const lineNum = (data.page - 1) * (data.pageSize || 0) + index + 1;
+            return `<div class="file-line" data-line-number="${lineNum}">${escapeHtml(line)}</div>`;
+        }).join('');
     }
 
-    // 显示内容并标记行号（只读模式）
-    const linesHtml = data.lines.map((line, index) => {
-        const lineNum = (data.page - 1) * LinesPerPage + index + 1;
-        return `<div class="file-line" data-line-number="${lineNum}">${escapeHtml(line)}</div>`;
-    }).join('');
-
     fileContent.innerHTML = linesHtml;
     fileContent.style.display = 'block';
     fileEditor.style.display = 'none';
@@ -510,7 +927,8 @@ function renderFileContent(data) {
     const advancedEditBtn = document.getElementById('advancedEditBtn');
     if (editFileBtn && advancedEditBtn) {
         const extension = currentFilePath.split('.').pop().toLowerCase();
-        if (isTextFile(extension)) {
+        // 分段查看时只有部分内容，不能编辑
+        if (isTextFile(extension) && rootAllows('write') && !isInArchive(currentFilePath) && !byteMode) {
             editFileBtn.style.display = 'inline-flex';
             // 如果是JSON文件，显示高级编辑按钮
             if (extension === 'json') {
@@ -524,8 +942,17 @@ function renderFileContent(data) {
         }
     }
 
+    // 压缩包中的文件无法实时跟踪
+    document.getElementById('followBtn').style.display = isInArchive(currentFilePath) ? 'none' : 'inline-flex';
+    document.getElementById('hexBtn').style.display = isInArchive(currentFilePath) ? 'none' : 'inline-flex';
+    document.getElementById('byteModeBtn').style.display = 'inline-flex';
+    document.getElementById('gotoBtn').style.display = 'inline-flex';
+
     // 如果是分页内容，显示分页控件
-    if (data.isPartial) {
+    if (byteMode) {
+        renderBytePagination(data);
+        pagination.style.display = 'flex';
+    } else if (data.isPartial) {
         renderPagination(currentFilePath, data.page, data.totalPages);
         pagination.style.display = 'flex';
     } else {
@@ -563,6 +990,197 @@ function renderPagination(path, page, totalPages) {
     });
 }
 
+// 渲染分段查看的翻页控件
+function renderBytePagination(data) {
+    const createButton = (text, offset) => offset === undefined
+        ? `<button class="btn btn-secondary" disabled>${text}</button>`
+        : `<button class="btn btn-secondary byte-page-btn" data-offset="${offset}">${text}</button>`;
+    const atStart = data.byteStart === 0;
+    const atEnd = data.byteEnd >= data.contentSize;
+
+    let html = createButton('« 开头', atStart ? undefined : 0);
+    html += createButton('‹ 上一段', atStart ? undefined : Math.max(data.byteStart - ByteViewLength, 0));
+    html += `<span class="pagination-info">${Math.floor(data.byteEnd * 100 / Math.max(data.contentSize, 1))}%</span>`;
+    html += createButton('下一段 ›', atEnd ? undefined : data.byteEnd);
+    html += createButton('末尾 »', atEnd ? undefined : Math.max(data.contentSize - ByteViewLength, 0));
+    pagination.innerHTML = html;
+
+    document.querySelectorAll('.byte-page-btn').forEach(btn => {
+        btn.addEventListener('click', () => viewFileBytes(currentFilePath, parseInt(btn.getAttribute('data-offset'))));
+    });
+}
+
+// 以十六进制查看文件，offset 所在的行显示在开头
+async function viewHex(path, offset = 0) {
+    stopFollow();
+    cancelSearch();
+    try {
+        showLoading();
+        path = normalizePath(path);
+        if (path !== currentFilePath) {
+            hexMatch = null;
+        }
+        currentFilePath = path;
+
+        const pathParts = path.split('/');
+        pathParts.pop();
+        currentPath = pathParts.join('/') || '/';
+        updateBreadcrumb(currentPath);
+
+        const params = new URLSearchParams({ path, offset, length: HexWindowSize, root: currentRootIndex });
+        const response = await fetch(`/api/hexview?${params.toString()}`);
+        if (!response.ok) {
+            throw new Error(await responseErrorMessage(response, '读取文件失败'));
+        }
+        const data = await response.json();
+
+        renderHexView(data);
+        showContentView();
+
+        if (fileList.innerHTML === '' || fileList.children.length === 0) {
+            loadDirectory(currentPath, currentRootIndex, false);
+        }
+    } catch (error) {
+        showError(error.message);
+    } finally {
+        hideLoading();
+    }
+}
+
+// 偏移显示为 8 位以上的十六进制
+function formatHexOffset(offset) {
+    return offset.toString(16).padStart(8, '0');
+}
+
+// 渲染十六进制内容
+function renderHexView(data) {
+    hexOffset = data.offset;
+    const end = data.offset + data.length;
+    fileName.textContent = data.name;
+    fileInfo.textContent = `${formatSize(data.size)} • 十六进制 • 0x${formatHexOffset(data.offset)} - 0x${formatHexOffset(Math.max(end - 1, data.offset))}`;
+
+    const inMatch = (offset) => hexMatch && offset >= hexMatch.offset && offset < hexMatch.offset + hexMatch.length;
+    fileContent.innerHTML = data.rows.map(row => {
+        const bytes = row.hex.split(' ').map((byte, i) =>
+            inMatch(row.offset + i) ? `<span class="hex-match">${byte}</span>` : byte
+        ).join(' ');
+        const ascii = Array.from(row.ascii).map((ch, i) =>
+            inMatch(row.offset + i) ? `<span class="hex-match">${escapeHtml(ch)}</span>` : escapeHtml(ch)
+        ).join('');
+        return `<div class="file-line hex-row"><span class="hex-offset">${formatHexOffset(row.offset)}</span><span class="hex-bytes">${bytes}</span><span class="hex-ascii">${ascii}</span></div>`;
+    }).join('');
+    fileContent.style.display = 'block';
+    fileEditor.style.display = 'none';
+    fileContent.scrollTop = 0;
+
+    // 十六进制模式下不能编辑、跟踪或按行搜索
+    document.getElementById('editFileBtn').style.display = 'none';
+    document.getElementById('advancedEditBtn').style.display = 'none';
+    document.getElementById('followBtn').style.display = 'none';
+    document.getElementById('byteModeBtn').style.display = 'none';
+    document.getElementById('gotoBtn').style.display = 'none';
+    document.getElementById('encodingSelect').style.display = 'none';
+    document.querySelector('.search-box').style.display = 'none';
+    searchNav.style.display = 'none';
+    searchResults.style.display = 'none';
+    document.getElementById('hexBtn').classList.add('active');
+
+    renderHexPagination(data);
+    pagination.style.display = 'flex';
+}
+
+// 退出十六进制模式，恢复文本查看的工具栏
+function leaveHexMode() {
+    hexOffset = null;
+    document.getElementById('encodingSelect').style.display = '';
+    document.querySelector('.search-box').style.display = '';
+    document.getElementById('hexBtn').classList.remove('active');
+}
+
+// 渲染十六进制分页、跳转和字节序列查找控件
+function renderHexPagination(data) {
+    const createButton = (text, offset) => offset === undefined
+        ? `<button class="btn btn-secondary" disabled>${text}</button>`
+        : `<button class="btn btn-secondary hex-page-btn" data-offset="${offset}">${text}</button>`;
+    const lastOffset = Math.floor(Math.max(data.size - 1, 0) / HexWindowSize) * HexWindowSize;
+
+    let html = createButton('« 开头', data.offset === 0 ? undefined : 0);
+    html += createButton('‹ 上一段', data.prevOffset);
+    html += `<span class="pagination-info">共 ${data.size.toLocaleString()} 字节</span>`;
+    html += createButton('下一段 ›', data.nextOffset);
+    html += createButton('末尾 »', data.nextOffset === undefined ? undefined : lastOffset);
+    html += `
+        <input type="text" id="hexOffsetInput" class="search-input hex-input" placeholder="偏移，如 0x1f40">
+        <button id="hexJumpBtn" class="btn btn-secondary">跳转</button>
+        <input type="text" id="hexPatternInput" class="search-input hex-input" placeholder="字节序列，如 7f 45 ?? 46" value="${escapeHtml(hexPattern.q)}">
+        <select id="hexPatternMode" class="encoding-select">
+            <option value="hex">十六进制</option>
+            <option value="text">文本</option>
+        </select>
+        <button id="hexFindBtn" class="btn btn-secondary">查找下一个</button>
+    `;
+    pagination.innerHTML = html;
+    document.getElementById('hexPatternMode').value = hexPattern.mode;
+
+    document.querySelectorAll('.hex-page-btn').forEach(btn => {
+        btn.addEventListener('click', () => viewHex(currentFilePath, parseInt(btn.getAttribute('data-offset'))));
+    });
+
+    const jump = () => {
+        const value = document.getElementById('hexOffsetInput').value.trim();
+        const offset = /^0x/i.test(value) ? parseInt(value.slice(2), 16) : parseInt(value, 10);
+        if (isNaN(offset) || offset < 0) {
+            showError('无效的偏移');
+            return;
+        }
+        viewHex(currentFilePath, offset);
+    };
+    document.getElementById('hexJumpBtn').addEventListener('click', jump);
+    document.getElementById('hexOffsetInput').addEventListener('keydown', (e) => {
+        if (e.key === 'Enter') jump();
+    });
+
+    document.getElementById('hexFindBtn').addEventListener('click', findHexPattern);
+    document.getElementById('hexPatternInput').addEventListener('keydown', (e) => {
+        if (e.key === 'Enter') findHexPattern();
+    });
+}
+
+// 从当前位置向后查找字节序列，找到后跳转并高亮
+async function findHexPattern() {
+    const q = document.getElementById('hexPatternInput').value.trim();
+    const mode = document.getElementById('hexPatternMode').value;
+    if (!q) return;
+
+    // 换了查找内容时从当前段的开头查找，否则从上一处匹配之后继续
+    if (q !== hexPattern.q || mode !== hexPattern.mode) {
+        hexPattern = { q, mode };
+        hexMatch = null;
+    }
+    const from = hexMatch ? hexMatch.offset + 1 : hexOffset;
+
+    try {
+        showLoading();
+        const params = new URLSearchParams({ path: currentFilePath, q, mode, from, maxResults: 1, root: currentRootIndex });
+        const response = await fetch(`/api/hexview/search?${params.toString()}`);
+        if (!response.ok) {
+            throw new Error(await responseErrorMessage(response, '查找失败'));
+        }
+        const data = await response.json();
+        if (data.matches.length === 0) {
+            showError('已查找到文件末尾，没有更多匹配');
+            return;
+        }
+        hexMatch = { offset: data.matches[0], length: data.length };
+    } catch (error) {
+        showError(error.message);
+        return;
+    } finally {
+        hideLoading();
+    }
+    viewHex(currentFilePath, hexMatch.offset);
+}
+
 // HTML 转义
 function escapeHtml(text) {
     const div = document.createElement('div');
@@ -584,6 +1202,9 @@ function showListView() {
     searchInput.value = ''; // 清空搜索框
     currentSearchResults = []; // 清空搜索结果
     currentSearchIndex = -1;
+    currentSearchNextOffset = null;
+    cancelSearch();
+    stopFollow();
 
     // 侧边栏保持显示
     // 加载目录内容
@@ -628,6 +1249,22 @@ document.getElementById('uploadBtn').addEventListener('click', () => {
     document.getElementById('fileInput').click();
 });
 
+document.getElementById('uploadDirBtn').addEventListener('click', () => {
+    document.getElementById('dirInput').click();
+});
+
+document.getElementById('grepBtn').addEventListener('click', () => {
+    grepDirectory(currentPath);
+});
+
+document.getElementById('findBtn').addEventListener('click', () => {
+    findByName(currentPath);
+});
+
+document.getElementById('trashBtn').addEventListener('click', () => {
+    showTrash();
+});
+
 document.getElementById('fileInput').addEventListener('change', (e) => {
     const files = e.target.files;
     if (files && files.length > 0) {
@@ -637,6 +1274,78 @@ document.getElementById('fileInput').addEventListener('change', (e) => {
     e.target.value = '';
 });
 
+document.getElementById('dirInput').addEventListener('change', (e) => {
+    const files = e.target.files;
+    if (files && files.length > 0) {
+        uploadFolder(files);
+    }
+    e.target.value = '';
+});
+
+// 切换文件编码后重新加载
+document.getElementById('encodingSelect').addEventListener('change', (e) => {
+    currentEncoding = e.target.value;
+    if (currentFilePath && byteViewOffset !== null) {
+        viewFileBytes(currentFilePath, byteViewOffset);
+    } else if (currentFilePath) {
+        viewFile(currentFilePath, currentPage);
+    }
+});
+
+// 实时跟踪开关
+document.getElementById('followBtn').addEventListener('click', () => {
+    if (followAbort) {
+        stopFollow();
+        viewFile(currentFilePath);
+    } else if (currentFilePath) {
+        followFile(currentFilePath);
+    }
+});
+
+// 分段查看开关
+document.getElementById('byteModeBtn').addEventListener('click', () => {
+    if (!currentFilePath) return;
+    if (byteViewOffset !== null) {
+        viewFile(currentFilePath);
+    } else {
+        viewFileBytes(currentFilePath);
+    }
+});
+
+// 跳转到行号或时间
+document.getElementById('gotoBtn').addEventListener('click', () => {
+    if (!currentFilePath) return;
+    const input = prompt('输入行号或时间（如 1200、14:02、昨天 23:50、2024-03-01 14:02:00）：');
+    if (input && input.trim()) {
+        gotoLineOrTime(currentFilePath, input);
+    }
+});
+
+// 十六进制查看开关
+document.getElementById('hexBtn').addEventListener('click', () => {
+    if (!currentFilePath) return;
+    if (hexOffset !== null) {
+        viewFile(currentFilePath);
+    } else {
+        viewHex(currentFilePath);
+    }
+});
+
+// 搜索选项开关
+document.querySelectorAll('.search-option').forEach(btn => {
+    btn.addEventListener('click', () => btn.classList.toggle('active'));
+});
+
+// 读取搜索选项，生成查询参数
+function searchOptionParams(query) {
+    const enabled = option => document.querySelector(`.search-option[data-option="${option}"]`)?.classList.contains('active');
+    const params = new URLSearchParams({ q: query, mode: enabled('regex') ? 'regex' : 'literal' });
+    ['caseSensitive', 'wholeWord', 'invert'].forEach(option => {
+        if (enabled(option)) params.set(option, 'true');
+    });
+    return params;
+}
+
 // 搜索功能
 searchBtn.addEventListener('click', () => {
     const query = searchInput.value.trim();
@@ -661,33 +1370,189 @@ nextResultBtn.addEventListener('click', nextSearchResult);
 
 // 搜索文件内容
 async function searchFile(path, query) {
-    try {
-        showLoading();
-        const url = `/api/search?path=${encodeURIComponent(path)}&q=${encodeURIComponent(query)}&root=${currentRootIndex}`;
-        const response = await fetch(url);
+    const params = searchOptionParams(query);
+    params.set('path', path);
+    params.set('root', currentRootIndex);
+    params.set('maxResults', SearchPageSize);
+    if (currentEncoding) params.set('encoding', currentEncoding);
+    currentSearchParams = params;
+    currentSearchResults = [];
+    currentSearchIndex = -1;
+    await loadSearchResults(0);
+}
+
+// 流式加载从 offset 开始的一页搜索结果，边搜索边追加到当前结果之后
+async function loadSearchResults(offset) {
+    cancelSearch();
+    const controller = new AbortController();
+    currentSearchAbort = controller;
+    currentSearchParams.set('offset', offset);
+    currentSearchNextOffset = null;
+    currentSearchProgress = '搜索中...';
+    renderSearchResults(currentSearchResults);
 
+    try {
+        const response = await fetch(`/api/search/stream?${currentSearchParams.toString()}`, { signal: controller.signal });
         if (!response.ok) {
-            throw new Error('搜索失败');
+            throw new Error(await responseErrorMessage(response, '搜索失败'));
+        }
+
+        let received = 0;
+        await readEventStream(response, (event, data) => {
+            if (event === 'result') {
+                currentSearchResults.push(data);
+                received++;
+                // 第一个结果到达时立即显示并自动跳转
+                if (received === 1) {
+                    renderSearchResults(currentSearchResults);
+                    if (offset === 0) goToSearchResult(0);
+                }
+            } else if (event === 'progress') {
+                currentSearchProgress = data.totalBytes
+                    ? `搜索中 ${Math.floor(data.bytesScanned * 100 / Math.max(data.totalBytes, 1))}%`
+                    : `搜索中，已扫描 ${formatSize(data.bytesScanned)}`;
+                renderSearchResults(currentSearchResults);
+            } else if (event === 'done') {
+                currentSearchTotal = data.total;
+                currentSearchNextOffset = data.truncated ? data.nextOffset : null;
+            } else if (event === 'error') {
+                throw new Error(data.message);
+            }
+        });
+    } catch (error) {
+        if (error.name === 'AbortError') return;
+        showError(error.message);
+    } finally {
+        if (currentSearchAbort === controller) {
+            currentSearchAbort = null;
+            currentSearchProgress = null;
+            renderSearchResults(currentSearchResults);
         }
+    }
+}
+
+// 取消正在进行的流式搜索
+function cancelSearch() {
+    if (currentSearchAbort) {
+        currentSearchAbort.abort();
+        currentSearchAbort = null;
+        currentSearchProgress = null;
+    }
+}
+
+// 实时跟踪文件，先显示末尾的行，之后追加新写入的行
+async function followFile(path) {
+    cancelSearch();
+    stopFollow();
+    const controller = new AbortController();
+    followAbort = controller;
+
+    const query = searchInput.value.trim();
+    const params = query ? searchOptionParams(query) : new URLSearchParams();
+    params.set('path', path);
+    params.set('root', currentRootIndex);
+    params.set('lines', FollowInitialLines);
+    if (currentEncoding) params.set('encoding', currentEncoding);
+
+    const followBtn = document.getElementById('followBtn');
+    followBtn.classList.add('active');
+    fileInfo.textContent = query ? `实时跟踪中 • 过滤: ${query}` : '实时跟踪中';
+    fileContent.innerHTML = '';
+    pagination.style.display = 'none';
+    searchResults.style.display = 'none';
+    searchNav.style.display = 'none';
 
-        const results = await response.json();
-        currentSearchResults = results;
-        currentSearchIndex = -1;
-        renderSearchResults(results, query);
+    const wrapper = fileContent.parentElement;
+    const append = html => {
+        // 已经滚动到底部时保持在底部
+        const atBottom = wrapper.scrollHeight - wrapper.scrollTop - wrapper.clientHeight < 40;
+        fileContent.insertAdjacentHTML('beforeend', html);
+        while (fileContent.children.length > FollowMaxLines) {
+            fileContent.firstElementChild.remove();
+        }
+        if (atBottom) {
+            wrapper.scrollTop = wrapper.scrollHeight;
+        }
+    };
 
-        // 自动跳转到第一个结果
-        if (results && results.length > 0) {
-            goToSearchResult(0);
+    try {
+        const response = await fetch(`/api/tail?${params.toString()}`, { signal: controller.signal });
+        if (!response.ok) {
+            throw new Error(await responseErrorMessage(response, '实时跟踪失败'));
         }
+
+        await readEventStream(response, (event, data) => {
+            if (event === 'line') {
+                const content = data.matches.length > 0 ? highlightMatches(data.line, data.matches) : escapeHtml(data.line);
+                append(`<div class="file-line">${content}</div>`);
+            } else if (event === 'reset') {
+                const reason = data.reason === 'rotated' ? '文件已轮转，从新文件开头继续' : '文件已被截断，从头继续';
+                append(`<div class="file-line follow-reset">── ${reason} ──</div>`);
+            } else if (event === 'error') {
+                throw new Error(data.message);
+            }
+        });
     } catch (error) {
+        if (error.name === 'AbortError') return;
         showError(error.message);
     } finally {
-        hideLoading();
+        if (followAbort === controller) {
+            followAbort = null;
+            followBtn.classList.remove('active');
+        }
+    }
+}
+
+// 停止实时跟踪
+function stopFollow() {
+    if (followAbort) {
+        followAbort.abort();
+        followAbort = null;
+        document.getElementById('followBtn').classList.remove('active');
+    }
+}
+
+// 查看文件时的编码参数
+function encodingParam() {
+    return currentEncoding ? `&encoding=${encodeURIComponent(currentEncoding)}` : '';
+}
+
+// 读取 Server-Sent Events 响应，逐个回调事件名和 JSON 数据
+async function readEventStream(response, onEvent) {
+    const reader = response.body.getReader();
+    const decoder = new TextDecoder();
+    let buffer = '';
+    while (true) {
+        const { done, value } = await reader.read();
+        if (done) break;
+        buffer += decoder.decode(value, { stream: true });
+
+        let end;
+        while ((end = buffer.indexOf('\n\n')) !== -1) {
+            const block = buffer.slice(0, end);
+            buffer = buffer.slice(end + 2);
+            let event = 'message';
+            const data = [];
+            block.split('\n').forEach(line => {
+                if (line.startsWith('event:')) event = line.slice(6).trim();
+                else if (line.startsWith('data:')) data.push(line.slice(5).trimStart());
+            });
+            // 忽略保活注释
+            if (data.length > 0) {
+                onEvent(event, JSON.parse(data.join('\n')));
+            }
+        }
     }
 }
 
 // 渲染搜索结果
-function renderSearchResults(results, query) {
+function renderSearchResults(results) {
+    if (currentSearchProgress !== null && results.length === 0) {
+        searchResults.innerHTML = `<div class="no-results">${currentSearchProgress}</div>`;
+        searchResults.style.display = 'block';
+        searchNav.style.display = 'none';
+        return;
+    }
     if (!results || results.length === 0) {
         searchResults.innerHTML = '<div class="no-results">未找到匹配的结果</div>';
         searchResults.style.display = 'block';
@@ -695,11 +1560,18 @@ function renderSearchResults(results, query) {
         return;
     }
 
-    let html = `<div class="search-results-header">找到 ${results.length} 个结果</div>`;
+    let html;
+    if (currentSearchProgress !== null) {
+        html = `<div class="search-results-header">${currentSearchProgress}，已找到 ${results.length} 个结果</div>`;
+    } else if (currentSearchNextOffset === null) {
+        html = `<div class="search-results-header">找到 ${currentSearchTotal} 个结果</div>`;
+    } else {
+        html = `<div class="search-results-header">找到 ${currentSearchTotal} 个结果，已显示 ${results.length} 个</div>`;
+    }
 
     results.forEach((result, index) => {
         // 高亮匹配的文本
-        const highlightedLine = highlightText(result.line, query);
+        const highlightedLine = highlightMatches(result.line, result.matches);
 
         // 只有多页文件才显示页码
         const pageInfo = totalPages > 1 ? `<span class="search-result-page">第 ${result.page} 页</span>` : '';
@@ -718,8 +1590,15 @@ function renderSearchResults(results, query) {
         `;
     });
 
+    if (currentSearchNextOffset !== null) {
+        html += '<div class="search-load-more" id="searchLoadMore">加载更多结果</div>';
+    }
+
+    // 搜索过程中会反复刷新，保持滚动位置
+    const scrollTop = searchResults.scrollTop;
     searchResults.innerHTML = html;
     searchResults.style.display = 'block';
+    searchResults.scrollTop = scrollTop;
 
     // 显示导航按钮
     searchNav.style.display = 'flex';
@@ -732,6 +1611,11 @@ function renderSearchResults(results, query) {
             goToSearchResult(index);
         });
     });
+
+    const loadMore = document.getElementById('searchLoadMore');
+    if (loadMore) {
+        loadMore.addEventListener('click', () => loadSearchResults(currentSearchNextOffset));
+    }
 }
 
 // 跳转到指定的搜索结果
@@ -777,89 +1661,335 @@ function updateSearchNavInfo() {
     nextResultBtn.disabled = currentSearchIndex >= currentSearchResults.length - 1;
 }
 
-// 上一个搜索结果
-function prevSearchResult() {
-    if (currentSearchIndex > 0) {
-        goToSearchResult(currentSearchIndex - 1);
+// 上一个搜索结果
+function prevSearchResult() {
+    if (currentSearchIndex > 0) {
+        goToSearchResult(currentSearchIndex - 1);
+    }
+}
+
+// 下一个搜索结果
+function nextSearchResult() {
+    if (currentSearchIndex < currentSearchResults.length - 1) {
+        goToSearchResult(currentSearchIndex + 1);
+    }
+}
+
+// 按匹配范围高亮搜索结果
+function highlightMatches(text, matches) {
+    if (!matches || matches.length === 0) {
+        return escapeHtml(text);
+    }
+
+    // 服务器返回的是 UTF-8 字节范围
+    const bytes = new TextEncoder().encode(text);
+    const decoder = new TextDecoder();
+    let html = '';
+    let last = 0;
+    matches.forEach(({ start, end }) => {
+        html += escapeHtml(decoder.decode(bytes.slice(last, start)));
+        html += `<span class="search-highlight">${escapeHtml(decoder.decode(bytes.slice(start, end)))}</span>`;
+        last = end;
+    });
+    html += escapeHtml(decoder.decode(bytes.slice(last)));
+    return html;
+}
+
+// 键盘快捷键
+document.addEventListener('keydown', (e) => {
+    if (contentView.style.display !== 'none') {
+        // 文件内容视图下的快捷键
+        if (e.key === 'Escape') {
+            // 如果正在编辑，先关闭编辑器
+            const modal = document.getElementById('editModal');
+            if (modal && modal.style.display !== 'none') {
+                closeEditModal();
+            } else {
+                showListView();
+            }
+        } else if (e.key === 'ArrowLeft' && currentPage > 1) {
+            if (currentFilePath) viewFile(currentFilePath, currentPage - 1);
+        } else if (e.key === 'ArrowRight' && currentPage < totalPages) {
+            if (currentFilePath) viewFile(currentFilePath, currentPage + 1);
+        }
+    }
+});
+
+// 编辑文件（从列表）
+async function editFile(path) {
+    try {
+        showLoading();
+        // 规范化路径
+        path = normalizePath(path);
+
+        // 加载完整文件内容
+        const url = `/api/view?path=${encodeURIComponent(path)}&root=${currentRootIndex}${encodingParam()}`;
+        const response = await fetch(url);
+
+        if (!response.ok) {
+            throw new Error('Failed to load file');
+        }
+
+        const data = await response.json();
+        const fullContent = data.lines.join('\n');
+        currentFileEncoding = data.encoding;
+
+        const modal = document.createElement('div');
+        modal.id = 'editModal';
+        modal.className = 'modal';
+        modal.innerHTML = `
+            <div class="modal-content">
+                <div class="modal-header">
+                    <h3>编辑文件: ${escapeHtml(data.name)}</h3>
+                    <button class="modal-close" onclick="closeEditModal()">&times;</button>
+                </div>
+                <div class="modal-body">
+                    <textarea id="editTextarea" class="edit-textarea" style="min-height: 500px;">${escapeHtml(fullContent)}</textarea>
+                </div>
+                <div class="modal-footer">
+                    <button class="btn btn-secondary" onclick="closeEditModal()">取消</button>
+                    <button class="btn btn-primary" onclick="saveFileEdit('${path}')">保存</button>
+                </div>
+            </div>
+        `;
+        document.body.appendChild(modal);
+        modal.style.display = 'flex';
+
+        // 自动聚焦到文本框
+        document.getElementById('editTextarea').focus();
+    } catch (error) {
+        showError(error.message);
+    } finally {
+        hideLoading();
+    }
+}
+
+// 关闭编辑模态框
+function closeEditModal() {
+    const modal = document.getElementById('editModal');
+    if (modal) {
+        modal.remove();
+    }
+}
+
+// 保存文件编辑
+async function saveFileEdit(path) {
+    const textarea = document.getElementById('editTextarea');
+    const newContent = textarea.value;
+
+    // 保存到服务器
+    try {
+        showLoading();
+        const response = await fetch(`/api/save?root=${currentRootIndex}`, {
+            method: 'POST',
+            headers: {
+                'Content-Type': 'application/json',
+            },
+            body: JSON.stringify({
+                path: path,
+                content: newContent,
+                encoding: currentFileEncoding,
+            }),
+        });
+
+        if (!response.ok) {
+            throw new Error(await responseErrorMessage(response, '保存失败'));
+        }
+
+        const result = await response.json();
+        alert(result.message);
+        closeEditModal();
+
+        // 重新加载目录列表
+        await loadDirectory(currentPath);
+    } catch (error) {
+        showError(error.message);
+    } finally {
+        hideLoading();
+    }
+}
+
+// 删除文件（从列表）
+async function deleteFileFromList(path) {
+    // 规范化路径
+    path = normalizePath(path);
+
+    if (!confirm('确定要删除 "' + path.split('/').pop() + '" 吗？删除后可以在回收站中恢复。')) {
+        return;
+    }
+
+    try {
+        showLoading();
+        const response = await fetch(`/api/delete?path=${encodeURIComponent(path)}&root=${currentRootIndex}`, {
+            method: 'DELETE',
+        });
+
+        if (!response.ok) {
+            throw new Error('删除失败');
+        }
+
+        const result = await response.json();
+        alert(result.message);
+
+        // 重新加载目录列表
+        await loadDirectory(currentPath);
+    } catch (error) {
+        showError(error.message);
+    } finally {
+        hideLoading();
+    }
+}
+
+// 在目录中递归搜索文件内容
+async function grepDirectory(path) {
+    const query = prompt(`在 ${path} 中搜索:`);
+    if (!query) return;
+
+    try {
+        showLoading();
+        const params = searchOptionParams(query);
+        params.set('path', path);
+        params.set('root', currentRootIndex);
+        params.set('maxResults', SearchPageSize);
+        const response = await fetch(`/api/grep?${params.toString()}`);
+        if (!response.ok) {
+            throw new Error(await responseErrorMessage(response, '搜索失败'));
+        }
+        const data = await response.json();
+
+        closeGrepModal();
+        const summary = data.truncated
+            ? `已搜索 ${data.filesSearched} 个文件，结果过多，只显示前 ${SearchPageSize} 个`
+            : `已搜索 ${data.filesSearched} 个文件，找到 ${data.total} 个结果`;
+        const rows = data.files.length === 0
+            ? '<div class="empty-state-text">未找到匹配的结果</div>'
+            : data.files.map(file => `
+                <div class="grep-file">
+                    <div class="grep-file-path">${escapeHtml(file.path)} <span class="file-info">(${file.total})</span></div>
+                    ${file.results.map(result => `
+                        <div class="search-result-item" data-path="${escapeHtml(file.path)}" data-page="${result.page}" data-line="${result.lineNumber}">
+                            <span class="search-result-line-number">行 ${result.lineNumber}</span>
+                            <div class="search-result-content">${highlightMatches(result.line, result.matches)}</div>
+                        </div>
+                    `).join('')}
+                </div>
+            `).join('');
+
+        const modal = document.createElement('div');
+        modal.id = 'grepModal';
+        modal.className = 'modal';
+        modal.innerHTML = `
+            <div class="modal-content">
+                <div class="modal-header">
+                    <h3>搜索: ${escapeHtml(query)}</h3>
+                    <button class="modal-close" onclick="closeGrepModal()">&times;</button>
+                </div>
+                <div class="modal-body">
+                    <div class="file-info">${summary}</div>
+                    ${rows}
+                </div>
+                <div class="modal-footer">
+                    <button class="btn btn-primary" onclick="closeGrepModal()">关闭</button>
+                </div>
+            </div>
+        `;
+        document.body.appendChild(modal);
+        modal.style.display = 'flex';
+
+        // 点击结果打开文件并定位到匹配行
+        modal.querySelectorAll('.search-result-item').forEach(item => {
+            item.addEventListener('click', () => {
+                closeGrepModal();
+                viewFileAndScroll(
+                    normalizePath(item.getAttribute('data-path')),
+                    parseInt(item.getAttribute('data-page')),
+                    parseInt(item.getAttribute('data-line'))
+                );
+            });
+        });
+    } catch (error) {
+        showError(error.message);
+    } finally {
+        hideLoading();
     }
 }
 
-// 下一个搜索结果
-function nextSearchResult() {
-    if (currentSearchIndex < currentSearchResults.length - 1) {
-        goToSearchResult(currentSearchIndex + 1);
+// 关闭目录搜索结果
+function closeGrepModal() {
+    const modal = document.getElementById('grepModal');
+    if (modal) {
+        modal.remove();
     }
 }
 
-// 高亮搜索文本
-function highlightText(text, query) {
-    const escapedText = escapeHtml(text);
-    const escapedQuery = escapeHtml(query);
-    const regex = new RegExp(`(${escapedQuery})`, 'gi');
-    return escapedText.replace(regex, '<span class="search-highlight">$1</span>');
-}
-
-// 键盘快捷键
-document.addEventListener('keydown', (e) => {
-    if (contentView.style.display !== 'none') {
-        // 文件内容视图下的快捷键
-        if (e.key === 'Escape') {
-            // 如果正在编辑，先关闭编辑器
-            const modal = document.getElementById('editModal');
-            if (modal && modal.style.display !== 'none') {
-                closeEditModal();
-            } else {
-                showListView();
-            }
-        } else if (e.key === 'ArrowLeft' && currentPage > 1) {
-            if (currentFilePath) viewFile(currentFilePath, currentPage - 1);
-        } else if (e.key === 'ArrowRight' && currentPage < totalPages) {
-            if (currentFilePath) viewFile(currentFilePath, currentPage + 1);
-        }
-    }
-});
+// 在目录中按文件名查找，包含 * 或 ? 时按 glob 匹配
+async function findByName(path) {
+    const query = prompt(`在 ${path} 中查找文件名:`);
+    if (!query) return;
 
-// 编辑文件（从列表）
-async function editFile(path) {
     try {
         showLoading();
-        // 规范化路径
-        path = normalizePath(path);
-
-        // 加载完整文件内容
-        const url = `/api/view?path=${encodeURIComponent(path)}&root=${currentRootIndex}`;
-        const response = await fetch(url);
-
+        const params = new URLSearchParams({ path, q: query, root: currentRootIndex });
+        if (/[*?]/.test(query)) {
+            params.set('mode', 'glob');
+        }
+        const response = await fetch(`/api/find?${params.toString()}`);
         if (!response.ok) {
-            throw new Error('Failed to load file');
+            throw new Error(await responseErrorMessage(response, '查找失败'));
         }
-
         const data = await response.json();
-        const fullContent = data.lines.join('\n');
+
+        closeFindModal();
+        const summary = data.truncated
+            ? `结果过多，只显示前 ${data.results.length} 个`
+            : `找到 ${data.results.length} 个结果`;
+        const rows = data.results.length === 0
+            ? '<div class="empty-state-text">未找到匹配的文件</div>'
+            : data.results.map(item => `
+                <div class="trash-item find-item" data-path="${escapeHtml(item.path)}" data-is-dir="${item.isDir}">
+                    <div class="file-icon">${getFileIcon(item.isDir, item.extension)}</div>
+                    <div class="trash-item-info">
+                        <div>${escapeHtml(item.path)}</div>
+                        <div class="file-info">${formatDate(item.modTime)}${item.isDir ? '' : ` · ${formatSize(item.size)}`}</div>
+                    </div>
+                </div>
+            `).join('');
 
         const modal = document.createElement('div');
-        modal.id = 'editModal';
+        modal.id = 'findModal';
         modal.className = 'modal';
         modal.innerHTML = `
             <div class="modal-content">
                 <div class="modal-header">
-                    <h3>编辑文件: ${escapeHtml(data.name)}</h3>
-                    <button class="modal-close" onclick="closeEditModal()">&times;</button>
+                    <h3>查找: ${escapeHtml(query)}</h3>
+                    <button class="modal-close" onclick="closeFindModal()">&times;</button>
                 </div>
                 <div class="modal-body">
-                    <textarea id="editTextarea" class="edit-textarea" style="min-height: 500px;">${escapeHtml(fullContent)}</textarea>
+                    <div class="file-info">${summary}</div>
+                    ${rows}
                 </div>
                 <div class="modal-footer">
-                    <button class="btn btn-secondary" onclick="closeEditModal()">取消</button>
-                    <button class="btn btn-primary" onclick="saveFileEdit('${path}')">保存</button>
+                    <button class="btn btn-primary" onclick="closeFindModal()">关闭</button>
                 </div>
             </div>
         `;
         document.body.appendChild(modal);
         modal.style.display = 'flex';
 
-        // 自动聚焦到文本框
-        document.getElementById('editTextarea').focus();
+        // 点击结果打开目录或文件
+        modal.querySelectorAll('.find-item').forEach(item => {
+            item.addEventListener('click', () => {
+                closeFindModal();
+                const itemPath = normalizePath(item.getAttribute('data-path'));
+                if (item.getAttribute('data-is-dir') === 'true' || isArchiveFile(itemPath)) {
+                    loadDirectory(itemPath);
+                } else if (isTextFile(fileExtension(itemPath))) {
+                    viewFile(itemPath);
+                } else {
+                    downloadFile(itemPath);
+                }
+            });
+        });
     } catch (error) {
         showError(error.message);
     } finally {
@@ -867,43 +1997,100 @@ async function editFile(path) {
     }
 }
 
-// 关闭编辑模态框
-function closeEditModal() {
-    const modal = document.getElementById('editModal');
+// 关闭文件名查找结果
+function closeFindModal() {
+    const modal = document.getElementById('findModal');
     if (modal) {
         modal.remove();
     }
 }
 
-// 保存文件编辑
-async function saveFileEdit(path) {
-    const textarea = document.getElementById('editTextarea');
-    const newContent = textarea.value;
+// 显示回收站
+async function showTrash() {
+    try {
+        showLoading();
+        const response = await fetch(`/api/trash?root=${currentRootIndex}`);
+        if (!response.ok) {
+            throw new Error('加载回收站失败');
+        }
+        const items = await response.json();
+
+        closeTrashModal();
+        const rows = items.length === 0
+            ? '<div class="empty-state-text">回收站为空</div>'
+            : items.map(item => `
+                <div class="trash-item">
+                    <div class="file-icon">${getFileIcon(item.isDir, item.name.split('.').pop())}</div>
+                    <div class="trash-item-info">
+                        <div>${escapeHtml(item.originalPath)}</div>
+                        <div class="file-info">${formatDate(item.deletedAt)} · ${formatSize(item.size)}</div>
+                    </div>
+                    ${rootAllows('create') ? `<button class="btn btn-small" onclick="restoreTrashItem('${item.id}')">恢复</button>` : ''}
+                    ${rootAllows('delete') ? `<button class="btn btn-small" onclick="purgeTrash('${item.id}')">永久删除</button>` : ''}
+                </div>
+            `).join('');
 
-    // 保存到服务器
+        const modal = document.createElement('div');
+        modal.id = 'trashModal';
+        modal.className = 'modal';
+        modal.innerHTML = `
+            <div class="modal-content">
+                <div class="modal-header">
+                    <h3>回收站</h3>
+                    <button class="modal-close" onclick="closeTrashModal()">&times;</button>
+                </div>
+                <div class="modal-body">${rows}</div>
+                <div class="modal-footer">
+                    ${items.length > 0 && rootAllows('delete') ? '<button class="btn btn-secondary" onclick="purgeTrash()">清空回收站</button>' : ''}
+                    <button class="btn btn-primary" onclick="closeTrashModal()">关闭</button>
+                </div>
+            </div>
+        `;
+        document.body.appendChild(modal);
+        modal.style.display = 'flex';
+    } catch (error) {
+        showError(error.message);
+    } finally {
+        hideLoading();
+    }
+}
+
+// 关闭回收站
+function closeTrashModal() {
+    const modal = document.getElementById('trashModal');
+    if (modal) {
+        modal.remove();
+    }
+}
+
+// 恢复回收站条目
+async function restoreTrashItem(id) {
     try {
         showLoading();
-        const response = await fetch(`/api/save?root=${currentRootIndex}`, {
+        let response = await fetch(`/api/trash/restore?root=${currentRootIndex}`, {
             method: 'POST',
             headers: {
                 'Content-Type': 'application/json',
             },
-            body: JSON.stringify({
-                path: path,
-                content: newContent,
-            }),
+            body: JSON.stringify({ id }),
         });
 
-        if (!response.ok) {
-            throw new Error('保存失败');
+        if (response.status === 409 && confirm('原位置已存在同名条目，是否恢复为副本？')) {
+            response = await fetch(`/api/trash/restore?root=${currentRootIndex}`, {
+                method: 'POST',
+                headers: {
+                    'Content-Type': 'application/json',
+                },
+                body: JSON.stringify({ id, overwrite: 'rename' }),
+            });
         }
 
-        const result = await response.json();
-        alert(result.message);
-        closeEditModal();
+        if (!response.ok) {
+            throw new Error('恢复失败');
+        }
 
-        // 重新加载目录列表
         await loadDirectory(currentPath);
+        await showTrash();
     } catch (error) {
         showError(error.message);
     } finally {
@@ -911,27 +2098,68 @@ async function saveFileEdit(path) {
     }
 }
 
-// 删除文件（从列表）
-async function deleteFileFromList(path) {
-    // 规范化路径
-    path = normalizePath(path);
-
-    if (!confirm('确定要删除文件 "' + path.split('/').pop() + '" 吗？此操作不可撤销！')) {
+// 永久删除回收站条目，不传 id 时清空回收站
+async function purgeTrash(id) {
+    const message = id ? '确定要永久删除该条目吗？此操作不可撤销！' : '确定要清空回收站吗？此操作不可撤销！';
+    if (!confirm(message)) {
         return;
     }
 
     try {
         showLoading();
-        const response = await fetch(`/api/delete?path=${encodeURIComponent(path)}&root=${currentRootIndex}`);
+        const response = await fetch(`/api/trash/purge?root=${currentRootIndex}`, {
+            method: 'POST',
+            headers: {
+                'Content-Type': 'application/json',
+            },
+            body: JSON.stringify(id ? { id } : {}),
+        });
 
         if (!response.ok) {
             throw new Error('删除失败');
         }
 
-        const result = await response.json();
-        alert(result.message);
+        await showTrash();
+    } catch (error) {
+        showError(error.message);
+    } finally {
+        hideLoading();
+    }
+}
+
+// 重命名文件或文件夹
+async function renameItem(path) {
+    path = normalizePath(path);
+    const parts = path.split('/');
+    const oldName = parts.pop();
+
+    const newName = prompt('请输入新名称:', oldName);
+    if (!newName || newName === oldName) return;
+    if (newName.includes('/') || newName.includes('\\')) {
+        showError('名称不能包含路径分隔符');
+        return;
+    }
+
+    try {
+        showLoading();
+        const response = await fetch(`/api/move?root=${currentRootIndex}`, {
+            method: 'POST',
+            headers: {
+                'Content-Type': 'application/json',
+            },
+            body: JSON.stringify({
+                source: path,
+                destination: [...parts, newName].join('/'),
+            }),
+        });
+
+        if (!response.ok) {
+            if (response.status === 409) {
+                throw new Error('目标已存在');
+            }
+            throw new Error('重命名失败');
+        }
 
-        // 重新加载目录列表
         await loadDirectory(currentPath);
     } catch (error) {
         showError(error.message);
@@ -948,7 +2176,7 @@ async function advancedEditFile(path) {
         path = normalizePath(path);
 
         // 加载完整文件内容
-        const url = `/api/view?path=${encodeURIComponent(path)}&root=${currentRootIndex}`;
+        const url = `/api/view?path=${encodeURIComponent(path)}&root=${currentRootIndex}${encodingParam()}`;
         const response = await fetch(url);
 
         if (!response.ok) {
@@ -957,6 +2185,7 @@ async function advancedEditFile(path) {
 
         const data = await response.json();
         const fullContent = data.lines.join('\n');
+        currentFileEncoding = data.encoding;
 
         // 验证文件内容不为空
         if (!fullContent || fullContent.trim() === '') {
@@ -1204,11 +2433,12 @@ async function saveAdvancedEdit() {
             body: JSON.stringify({
                 path: currentFilePath,
                 content: newContent,
+                encoding: currentFileEncoding,
             }),
         });
 
         if (!response.ok) {
-            throw new Error('保存失败');
+            throw new Error(await responseErrorMessage(response, '保存失败'));
         }
 
         const result = await response.json();
@@ -1383,37 +2613,204 @@ async function uploadFiles(files) {
     }, 1000);
 }
 
-// 上传单个文件
-async function uploadSingleFile(file, progressId, textId) {
+// 上传文件夹：一次请求提交所有文件，服务端按相对路径创建目录
+async function uploadFolder(files) {
+    const uploadProgress = document.getElementById('uploadProgress');
+    uploadProgress.style.display = 'block';
+    uploadProgress.innerHTML = '';
+
+    const folderName = (files[0].webkitRelativePath || files[0].name).split('/')[0];
+    const progressId = 'upload-progress-folder';
+    const progressItem = document.createElement('div');
+    progressItem.className = 'upload-progress-item';
+    progressItem.innerHTML = `
+        <div class="upload-progress-name">${escapeHtml(folderName)}（${files.length} 个文件）</div>
+        <div class="upload-progress-bar">
+            <div class="upload-progress-fill" id="${progressId}" style="width: 0%"></div>
+            <div class="upload-progress-text" id="${progressId}-text">0%</div>
+        </div>
+    `;
+    uploadProgress.appendChild(progressItem);
+
+    // path 字段必须在文件字段之前
     const formData = new FormData();
-    formData.append('file', file);
     formData.append('path', currentPath);
+    for (const file of files) {
+        formData.append('file', file, file.webkitRelativePath || file.name);
+    }
 
     const xhr = new XMLHttpRequest();
-
-    return new Promise((resolve, reject) => {
+    const result = await new Promise((resolve) => {
         xhr.upload.addEventListener('progress', (e) => {
             if (e.lengthComputable) {
                 const percentComplete = Math.round((e.loaded / e.total) * 100);
-                const progressBar = document.getElementById(progressId);
-                const progressText = document.getElementById(textId);
+                document.getElementById(progressId).style.width = percentComplete + '%';
+                document.getElementById(progressId + '-text').textContent = percentComplete + '%';
+            }
+        });
 
-                if (progressBar) {
-                    progressBar.style.width = percentComplete + '%';
-                }
-                if (progressText) {
-                    progressText.textContent = percentComplete + '%';
-                }
+        xhr.addEventListener('load', () => {
+            try {
+                resolve(JSON.parse(xhr.responseText));
+            } catch (error) {
+                resolve({ success: false, message: '上传失败', files: [] });
+            }
+        });
+
+        xhr.addEventListener('error', () => {
+            resolve({ success: false, message: '网络错误', files: [] });
+        });
+
+        xhr.open('POST', `/api/upload?root=${currentRootIndex}`);
+        xhr.send(formData);
+    });
+
+    // 汇总失败的文件，最多列出 10 个
+    let summary = result.message || '上传失败';
+    if (result.success) {
+        progressItem.classList.add('upload-progress-complete');
+    } else {
+        document.getElementById(progressId).style.background = '#e74c3c';
+        const failures = (result.files || []).filter(f => !f.success);
+        failures.slice(0, 10).forEach(f => summary += `\n${f.path}: ${f.message}`);
+        if (failures.length > 10) {
+            summary += `\n……`;
+        }
+    }
+
+    setTimeout(() => {
+        alert(summary);
+        uploadProgress.style.display = 'none';
+        loadDirectory(currentPath);
+    }, 1000);
+}
+
+// 分块上传的块大小
+const UPLOAD_CHUNK_SIZE = 8 * 1024 * 1024;
+
+// 单个数据块上传失败时的最大重试次数
+const UPLOAD_MAX_RETRIES = 5;
+
+// 上传单个文件（tus 协议分块上传，中断后可从已上传的位置继续）
+async function uploadSingleFile(file, progressId, textId) {
+    const updateProgress = (loaded) => {
+        const percentComplete = file.size > 0 ? Math.round((loaded / file.size) * 100) : 100;
+        const progressBar = document.getElementById(progressId);
+        const progressText = document.getElementById(textId);
+
+        if (progressBar) {
+            progressBar.style.width = percentComplete + '%';
+        }
+        if (progressText) {
+            progressText.textContent = percentComplete + '%';
+        }
+    };
+
+    // 同一文件上传到同一位置时复用之前未完成的上传
+    const fingerprint = `tus::${currentRootIndex}::${currentPath}::${file.name}::${file.size}::${file.lastModified}`;
+    let uploadUrl = localStorage.getItem(fingerprint);
+    let offset = uploadUrl ? await getUploadOffset(uploadUrl) : null;
+
+    if (offset === null) {
+        uploadUrl = await createUpload(file);
+        localStorage.setItem(fingerprint, uploadUrl);
+        offset = 0;
+    }
+    updateProgress(offset);
+
+    let retries = 0;
+    while (offset < file.size) {
+        const chunk = file.slice(offset, Math.min(offset + UPLOAD_CHUNK_SIZE, file.size));
+        try {
+            offset = await uploadChunk(uploadUrl, chunk, offset, (loaded) => updateProgress(offset + loaded));
+            retries = 0;
+            updateProgress(offset);
+        } catch (error) {
+            if (error.fatal || retries >= UPLOAD_MAX_RETRIES) {
+                throw error;
+            }
+            retries++;
+            await new Promise(resolve => setTimeout(resolve, 1000 * retries));
+
+            // 重新查询服务器已接收的位置后继续
+            const serverOffset = await getUploadOffset(uploadUrl);
+            if (serverOffset === null) {
+                localStorage.removeItem(fingerprint);
+                throw new Error('上传已失效，请重新上传');
+            }
+            offset = serverOffset;
+        }
+    }
+
+    localStorage.removeItem(fingerprint);
+}
+
+// 创建上传，返回上传地址
+async function createUpload(file) {
+    const metadata = [
+        `filename ${encodeBase64(file.name)}`,
+        `path ${encodeBase64(currentPath)}`
+    ].join(',');
+
+    const response = await fetch(`/api/tus?root=${currentRootIndex}`, {
+        method: 'POST',
+        headers: {
+            'Tus-Resumable': '1.0.0',
+            'Upload-Length': String(file.size),
+            'Upload-Metadata': metadata
+        }
+    });
+
+    if (response.status === 409) {
+        throw new Error('文件已存在');
+    }
+    if (response.status !== 201) {
+        throw new Error(await responseErrorMessage(response, '创建上传失败'));
+    }
+
+    return response.headers.get('Location');
+}
+
+// 查询上传已接收的字节数，上传不存在时返回 null
+async function getUploadOffset(uploadUrl) {
+    try {
+        const response = await fetch(uploadUrl, {
+            method: 'HEAD',
+            headers: { 'Tus-Resumable': '1.0.0' }
+        });
+        if (!response.ok) {
+            return null;
+        }
+        return parseInt(response.headers.get('Upload-Offset'), 10);
+    } catch (error) {
+        return null;
+    }
+}
+
+// 上传一个数据块，返回新的偏移量
+async function uploadChunk(uploadUrl, chunk, offset, onProgress) {
+    const data = await chunk.arrayBuffer();
+    const checksum = await chunkChecksum(data);
+
+    const xhr = new XMLHttpRequest();
+
+    return new Promise((resolve, reject) => {
+        xhr.upload.addEventListener('progress', (e) => {
+            if (e.lengthComputable) {
+                onProgress(e.loaded);
             }
         });
 
         xhr.addEventListener('load', () => {
-            if (xhr.status === 200) {
-                resolve();
-            } else if (xhr.status === 409) {
-                reject(new Error('文件已存在'));
-            } else {
+            if (xhr.status === 204) {
+                resolve(parseInt(xhr.getResponseHeader('Upload-Offset'), 10));
+            } else if (xhr.status === 460 || xhr.status === 409 || xhr.status === 423 || xhr.status >= 500) {
+                // 校验失败、偏移量不一致等情况可以重试
                 reject(new Error('上传失败'));
+            } else {
+                const error = new Error(xhr.status === 413 ? '文件过大' : '上传失败');
+                error.fatal = true;
+                reject(error);
             }
         });
 
@@ -1421,11 +2818,44 @@ async function uploadSingleFile(file, progressId, textId) {
             reject(new Error('网络错误'));
         });
 
-        xhr.open('POST', `/api/upload?root=${currentRootIndex}`);
-        xhr.send(formData);
+        xhr.open('PATCH', uploadUrl);
+        xhr.setRequestHeader('Tus-Resumable', '1.0.0');
+        xhr.setRequestHeader('Upload-Offset', String(offset));
+        xhr.setRequestHeader('Content-Type', 'application/offset+octet-stream');
+        if (checksum) {
+            xhr.setRequestHeader('Upload-Checksum', `sha256 ${checksum}`);
+        }
+        xhr.send(data);
     });
 }
 
+// 计算数据块的 SHA-256（非安全上下文中浏览器不提供 crypto.subtle，此时跳过校验）
+async function chunkChecksum(data) {
+    if (!window.crypto || !window.crypto.subtle) {
+        return null;
+    }
+    const digest = await window.crypto.subtle.digest('SHA-256', data);
+    return btoa(String.fromCharCode(...new Uint8Array(digest)));
+}
+
+// 把字符串按 UTF-8 编码为 base64
+function encodeBase64(text) {
+    const bytes = new TextEncoder().encode(text);
+    let binary = '';
+    bytes.forEach(b => binary += String.fromCharCode(b));
+    return btoa(binary);
+}
+
+// 读取 JSON 错误响应中的信息
+async function responseErrorMessage(response, fallback) {
+    try {
+        const data = await response.json();
+        return data.message || fallback;
+    } catch (error) {
+        return fallback;
+    }
+}
+
 // 初始化
 window.onload = function() {
     // 添加侧边栏切换功能
@@ -1514,12 +2944,13 @@ window.onload = function() {
                     body: JSON.stringify({
                         path: currentFilePath,
                         content: newContent,
+                        encoding: currentFileEncoding,
                         root: currentRootIndex
                     })
                 });
 
                 if (!response.ok) {
-                    throw new Error('保存失败');
+                    throw new Error(await responseErrorMessage(response, '保存失败'));
                 }
 
                 // 更新当前内容
@@ -1572,8 +3003,17 @@ window.onload = function() {
         });
     }
 
-    // 先加载根目录列表
-    loadRoots().then(() => {
+    // 注销按钮事件
+    const logoutBtn = document.getElementById('logoutBtn');
+    if (logoutBtn) {
+        logoutBtn.addEventListener('click', logout);
+    }
+
+    // 先确认登录状态，再加载根目录列表
+    checkSession().then(async (ready) => {
+        if (!ready) return;
+        await loadRoots();
+
         // 检查URL中是否有文件路径参数（用于直接访问文件）
         const urlParams = new URLSearchParams(window.location.search);
         const filePath = urlParams.get('file');